@@ -0,0 +1,19 @@
+//! address conversions that fail with a `ContractError` naming the address, instead of a bare StdError
+use cosmwasm_std::{Addr, Api, CanonicalAddr};
+
+use crate::error::ContractError;
+
+pub fn validate(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
+
+pub fn canonicalize(api: &dyn Api, address: &str) -> Result<CanonicalAddr, ContractError> {
+    api.addr_canonicalize(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
+
+pub fn humanize(api: &dyn Api, address: &CanonicalAddr) -> Result<Addr, ContractError> {
+    api.addr_humanize(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
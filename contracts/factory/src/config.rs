@@ -1,19 +1,23 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
+use cosmwasm_std::{CanonicalAddr, StdError, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
 
-pub static CONFIG_KEY: &[u8] = b"config_003";
+use yieldpay_core::fee::{FeeModel, FeePeriod};
+
+use crate::error::ContractError;
+
+pub static CONFIG_KEY: &[u8] = b"config_005";
+pub static OWNERSHIP_PROPOSAL_KEY: &[u8] = b"ownership_proposal";
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct Config {
     pub this: CanonicalAddr,
     pub owner: CanonicalAddr,
     pub fee_collector: CanonicalAddr,
-    pub fee_amount: Decimal,
-    pub fee_max: Uint128,
-    pub fee_reset_every_num_blocks: u64,
+    pub fee_model: FeeModel,
+    pub fee_period: FeePeriod,
     pub money_market: CanonicalAddr,
     pub dp_code_id: u64,
     pub anchor_pool_code_id: u64,
@@ -27,8 +31,36 @@ pub fn store(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
     singleton(storage, CONFIG_KEY).save(data)
 }
 
-pub fn read(storage: &dyn Storage) -> StdResult<Config> {
-    singleton_read(storage, CONFIG_KEY).load()
+pub fn read(storage: &dyn Storage) -> Result<Config, ContractError> {
+    singleton_read(storage, CONFIG_KEY)
+        .load()
+        .map_err(|err| match err {
+            StdError::NotFound { .. } => ContractError::ConfigMissing("config".to_string()),
+            err => ContractError::Std(err),
+        })
+}
+
+/// an ownership transfer waiting on the proposed owner to accept it
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub proposed_owner: CanonicalAddr,
+    /// seconds since epoch
+    pub expires_at: u64,
+}
+
+pub fn ownership_proposal_store(
+    storage: &mut dyn Storage,
+    data: &OwnershipProposal,
+) -> StdResult<()> {
+    singleton(storage, OWNERSHIP_PROPOSAL_KEY).save(data)
+}
+
+pub fn ownership_proposal_read(storage: &dyn Storage) -> StdResult<Option<OwnershipProposal>> {
+    singleton_read(storage, OWNERSHIP_PROPOSAL_KEY).may_load()
+}
+
+pub fn ownership_proposal_remove(storage: &mut dyn Storage) {
+    singleton::<OwnershipProposal>(storage, OWNERSHIP_PROPOSAL_KEY).remove()
 }
 /*
 //pub static TEMP_FUND_CONTRACT_ACTIVE: &[u8] = b"temp_fund_contract_active";
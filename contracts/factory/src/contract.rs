@@ -6,23 +6,25 @@ const CONTRACT_NAME: &str = "yieldpay-factory";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use std::str::FromStr;
 
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response,
-    StdError, StdResult, SubMsg, WasmMsg,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn, Response, StdError,
+    SubMsg, WasmMsg,
 };
 use protobuf::Message;
 use yieldpay_core::factory_msg::{ExecuteMsg, QueryMsg};
 use yieldpay_core::factory_msg::{InstantiateMsg, MigrateMsg};
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
 
+use crate::addr;
 use crate::config;
 #[allow(unused_imports)]
 use crate::config::read;
 use crate::error::ContractError;
 use crate::handler::core as CoreHandler;
 use crate::handler::query as QueryHandler;
-use crate::migrations::ConfigV100;
+use crate::migrations::{ConfigV003, ConfigV004, ConfigV100};
 use crate::querier::nft::NFTInstantiateMsg;
 use crate::response::MsgInstantiateContractResponse;
 
@@ -41,15 +43,17 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if !msg.fee_model.is_valid() || !msg.fee_period.is_valid() {
+        return Err(ContractError::InvalidFeeModel);
+    }
 
     let config = config::Config {
-        this: deps.api.addr_canonicalize(env.contract.address.as_str())?,
-        owner: deps.api.addr_canonicalize(info.sender.as_str())?,
-        fee_collector: deps.api.addr_canonicalize(msg.fee_collector.as_str())?,
-        fee_amount: Decimal::from_str(&msg.fee_amount)?,
-        fee_max: msg.fee_max,
-        fee_reset_every_num_blocks: msg.fee_reset_every_num_blocks,
-        money_market: deps.api.addr_canonicalize(msg.money_market.as_str())?,
+        this: addr::canonicalize(deps.api, env.contract.address.as_str())?,
+        owner: addr::canonicalize(deps.api, info.sender.as_str())?,
+        fee_collector: addr::canonicalize(deps.api, msg.fee_collector.as_str())?,
+        fee_model: msg.fee_model,
+        fee_period: msg.fee_period,
+        money_market: addr::canonicalize(deps.api, msg.money_market.as_str())?,
         dp_code_id: msg.dp_code_id,
         anchor_pool_code_id: msg.anchor_pool_code_id,
         nft_code_id: msg.nft_code_id,
@@ -59,6 +63,7 @@ pub fn instantiate(
     };
 
     config::store(deps.storage, &config)?;
+    roles::set_holder(deps.storage, Role::Owner, &info.sender)?;
     if let Some(nft_code_id) = msg.nft_code_id {
         Ok(Response::new().add_submessage(SubMsg {
             // Create NFT token
@@ -111,9 +116,9 @@ pub fn execute(
         ),
         ExecuteMsg::Configure {
             fee_collector,
-            fee_amount,
-            fee_max,
-            fee_reset_every_num_blocks,
+            fee_collector_funds,
+            fee_model,
+            fee_period,
             money_market,
             dp_code_id,
             anchor_pool_code_id,
@@ -124,9 +129,9 @@ pub fn execute(
             env,
             info,
             fee_collector,
-            fee_amount,
-            fee_max,
-            fee_reset_every_num_blocks,
+            fee_collector_funds,
+            fee_model,
+            fee_period,
             money_market,
             dp_code_id,
             anchor_pool_code_id,
@@ -158,6 +163,23 @@ pub fn execute(
         ExecuteMsg::RemoveNftFromFund { contract } => {
             CoreHandler::remove_nft_from_fund(deps, env, info, contract)
         }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            CoreHandler::propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            CoreHandler::drop_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::AcceptOwnership {} => CoreHandler::accept_ownership(deps, env, info),
+        ExecuteMsg::Sweep {
+            denom_or_cw20,
+            recipient,
+        } => CoreHandler::sweep(deps, env, info, denom_or_cw20, recipient),
+        ExecuteMsg::GrantRole { role, address } => {
+            CoreHandler::grant_role(deps, env, info, role, address)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            CoreHandler::revoke_role(deps, env, info, role, address)
+        }
     }
 }
 
@@ -167,15 +189,13 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
     match msg.id {
         INSTANTIATE_REPLY_ID => {
             // get new token's contract address
-            let res: MsgInstantiateContractResponse = Message::parse_from_bytes(
-                msg.result.unwrap().data.unwrap().as_slice(),
-            )
-            .map_err(|_| {
-                ContractError::Std(StdError::parse_err(
-                    "MsgInstantiateContractResponse",
-                    "failed to parse data",
-                ))
-            })?;
+            let res: MsgInstantiateContractResponse =
+                Message::parse_from_bytes(reply_data(msg)?.as_slice()).map_err(|_| {
+                    ContractError::Std(StdError::parse_err(
+                        "MsgInstantiateContractResponse",
+                        "failed to parse data",
+                    ))
+                })?;
             let token_addr = Addr::unchecked(res.get_contract_address());
 
             CoreHandler::register_anchor_pool_token(deps, env, &token_addr)
@@ -192,15 +212,13 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
 
          */
         INSTANTIATE_NFT_REPLY_ID => {
-            let res: MsgInstantiateContractResponse = Message::parse_from_bytes(
-                msg.result.unwrap().data.unwrap().as_slice(),
-            )
-            .map_err(|_| {
-                ContractError::Std(StdError::parse_err(
-                    "MsgInstantiateContractResponse",
-                    "failed to parse data/NFT",
-                ))
-            })?;
+            let res: MsgInstantiateContractResponse =
+                Message::parse_from_bytes(reply_data(msg)?.as_slice()).map_err(|_| {
+                    ContractError::Std(StdError::parse_err(
+                        "MsgInstantiateContractResponse",
+                        "failed to parse data/NFT",
+                    ))
+                })?;
 
             CoreHandler::register_nft_token(deps, env, res.get_contract_address())
         }
@@ -208,14 +226,28 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
     }
 }
 
+/// the instantiate response bytes of a successful submessage
+fn reply_data(msg: Reply) -> Result<Binary, ContractError> {
+    msg.result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or_else(|| {
+            ContractError::Std(StdError::parse_err(
+                "MsgInstantiateContractResponse",
+                "no reply data",
+            ))
+        })
+}
+
 #[allow(dead_code)]
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => QueryHandler::config(deps, env),
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    Ok(match msg {
+        QueryMsg::Config {} => QueryHandler::config(deps, env)?,
         QueryMsg::AllAnchorFunds { start_after, limit } => to_binary(
             &QueryHandler::all_anchor_funds(deps, env, start_after, limit)?,
-        ),
+        )?,
         QueryMsg::AnchorFundsByBeneficiary {
             beneficiary,
             start_after,
@@ -226,7 +258,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             &beneficiary,
             start_after,
             limit,
-        )?),
+        )?)?,
 
         QueryMsg::AnchorFundsByOwner {
             owner,
@@ -238,7 +270,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             &owner,
             start_after,
             limit,
-        )?),
+        )?)?,
 
         QueryMsg::AnchorFundsByPoolName {
             pool_name,
@@ -250,18 +282,31 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             &pool_name,
             start_after,
             limit,
-        )?),
+        )?)?,
 
         QueryMsg::AnchorFund { contract } => {
-            to_binary(&QueryHandler::anchor_fund(deps, env, &contract)?)
+            to_binary(&QueryHandler::anchor_fund(deps, env, &contract)?)?
         }
         QueryMsg::AnchorFundEx { contract } => {
-            to_binary(&QueryHandler::anchor_fund_ex(deps, env, &contract)?)
+            to_binary(&QueryHandler::anchor_fund_ex(deps, env, &contract)?)?
         }
         QueryMsg::AllAnchorFundsCount => {
-            to_binary(&QueryHandler::all_anchor_fund_count(deps, env)?)
+            to_binary(&QueryHandler::all_anchor_fund_count(deps, env)?)?
         }
-    }
+        QueryMsg::OwnershipProposal {} => to_binary(&QueryHandler::ownership_proposal(deps, env)?)?,
+        QueryMsg::Roles { address } => to_binary(&QueryHandler::roles(deps, env, &address)?)?,
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&QueryHandler::role_members(
+            deps,
+            env,
+            role,
+            start_after,
+            limit,
+        )?)?,
+    })
 }
 
 #[allow(dead_code)]
@@ -288,6 +333,23 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         }
     }
 
+    // factories from before the fee model
+    if let Some(config_v003) = ConfigV003::may_load(deps.storage)? {
+        config::store(deps.storage, &config_v003.migrate_from())?;
+        ConfigV003::remove(deps.storage);
+    }
+    // and from before fee periods were in seconds
+    if let Some(config_v004) = ConfigV004::may_load(deps.storage)? {
+        config::store(deps.storage, &config_v004.migrate_from())?;
+        ConfigV004::remove(deps.storage);
+    }
+
+    // factories from before roles existed
+    let owner = addr::humanize(deps.api, &config::read(deps.storage)?.owner)?;
+    if !roles::has_role(deps.storage, Role::Owner, &owner) {
+        roles::set_holder(deps.storage, Role::Owner, &owner)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -1,6 +1,7 @@
 use cosmwasm_std::{OverflowError, StdError};
 use protobuf::ProtobufError;
 use thiserror::Error;
+use yieldpay_core::roles::RolesError;
 
 #[derive(Error, Debug)]
 pub enum ContractError {
@@ -18,6 +19,8 @@ pub enum ContractError {
         expected: String,
         actual: String,
     },
+    #[error("Factory: {0} can't be granted or revoked directly")]
+    RoleNotGrantable(String),
     #[error(transparent)]
     JsonDe(#[from] serde_json_wasm::de::Error),
     #[error(transparent)]
@@ -27,6 +30,11 @@ pub enum ContractError {
     #[error(transparent)]
     ProtobufError(#[from] ProtobufError),
 
+    #[error("Factory: invalid address {0:?}")]
+    InvalidAddress(String),
+    #[error("Factory: {0} has not been stored")]
+    ConfigMissing(String),
+
     #[error("Factory: Invalid reply ID (ID: {id:?}")]
     InvalidReplyId { id: u64 },
     #[error("Factory: InstantiateError Failed - {action:?} ")]
@@ -34,6 +42,8 @@ pub enum ContractError {
     #[error("Factory: ExecuteError Failed - {action:?} ")]
     ExecuteError { action: String },
 
+    #[error("Factory: fee rates must be at most 1, with tiers in increasing threshold order and a non zero period")]
+    InvalidFeeModel,
     #[error("Factory: Contract can't be migrated! {current_name:?} {current_version:?}")]
     MigrationError {
         current_name: String,
@@ -45,4 +55,30 @@ pub enum ContractError {
     AnchorPoolNotFound(String),
     #[error("Factory:NFT Contract is not set")]
     NFTContractNotSet,
+    #[error("Factory: no ownership transfer is pending")]
+    NoOwnershipProposal,
+    #[error("Factory: ownership proposal has expired")]
+    OwnershipProposalExpired,
+    #[error("Factory: ownership proposal expiry must be between 1 and {max} seconds")]
+    OwnershipProposalExpiryInvalid { max: u64 },
+    #[error("Factory: nothing to sweep")]
+    NothingToSweep,
+}
+
+impl From<RolesError> for ContractError {
+    fn from(err: RolesError) -> Self {
+        match err {
+            RolesError::Std(err) => ContractError::Std(err),
+            RolesError::Unauthorized {
+                action,
+                expected,
+                actual,
+            } => ContractError::Unauthorized {
+                action,
+                expected,
+                actual,
+            },
+            RolesError::NotGrantable(role) => ContractError::RoleNotGrantable(role),
+        }
+    }
 }
@@ -1,58 +1,66 @@
 use crate::collection::{CollectionExecuteMsg, NewCollectionMsg, Trait};
 
 use cosmwasm_std::*;
-use std::str::FromStr;
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use yieldpay_core::dex::AssetInfo;
 use yieldpay_core::factory_response::AnchorPool;
+use yieldpay_core::fee::{FeeModel, FeePeriod};
 use yieldpay_core::pool_msg::{
     NftCallback, NFT_REPLY_COLLECTION_ACTIVE, NFT_REPLY_COLLECTION_REDEEMED,
 };
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
 use yieldpay_core::{pool_anchor_msg, pool_msg};
 
+use crate::addr;
 use crate::config;
-use crate::config::read;
+use crate::config::{
+    ownership_proposal_read, ownership_proposal_remove, ownership_proposal_store, read,
+    OwnershipProposal,
+};
 use crate::contract::INSTANTIATE_REPLY_ID;
 use crate::error::ContractError;
 use crate::querier::pool_anchor::pool_anchor_config;
 use crate::state::anchor_pools;
 
+/// longest a proposed owner has to accept before the proposal lapses
+pub const MAX_OWNERSHIP_PROPOSAL_EXPIRY: u64 = 14 * 86_400;
+
 #[allow(clippy::too_many_arguments)]
 pub fn configure(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     fee_collector: Option<String>,
-    fee_amount: Option<String>,
-    fee_max: Option<Uint128>,
-    fee_reset_every_num_blocks: Option<u64>,
+    fee_collector_funds: Option<Vec<String>>,
+    fee_model: Option<FeeModel>,
+    fee_period: Option<FeePeriod>,
     money_market: Option<String>,
     dp_code_id: Option<u64>,
     anchor_pool_code_id: Option<u64>,
     nft_contract: Option<String>,
     homepage: Option<String>,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    if config.owner != deps.api.addr_canonicalize(info.sender.as_str()).unwrap() {
-        return Err(ContractError::Unauthorized {
-            action: "configure".to_string(),
-            expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
+    roles::ensure_any(deps.storage, "configure", &info.sender, &[Role::Owner])?;
+    let mut config = config::read(deps.storage)?;
 
     if let Some(fee_collector) = fee_collector {
-        config.fee_collector = deps.api.addr_canonicalize(fee_collector.as_str()).unwrap();
-    }
-    if let Some(fee_amount) = fee_amount {
-        config.fee_amount = Decimal::from_str(&fee_amount)?
+        config.fee_collector = addr::canonicalize(deps.api, fee_collector.as_str())?;
     }
-    if let Some(fee_max) = fee_max {
-        config.fee_max = fee_max
+    if let Some(fee_model) = fee_model {
+        if !fee_model.is_valid() {
+            return Err(ContractError::InvalidFeeModel);
+        }
+        config.fee_model = fee_model
     }
-    if let Some(fee_reset_every_num_blocks) = fee_reset_every_num_blocks {
-        config.fee_reset_every_num_blocks = fee_reset_every_num_blocks
+    if let Some(fee_period) = fee_period {
+        if !fee_period.is_valid() {
+            return Err(ContractError::InvalidFeeModel);
+        }
+        config.fee_period = fee_period
     }
     if let Some(money_market) = money_market {
-        config.money_market = deps.api.addr_canonicalize(money_market.as_str())?
+        config.money_market = addr::canonicalize(deps.api, money_market.as_str())?
     }
     if let Some(dp_code_id) = dp_code_id {
         config.dp_code_id = dp_code_id
@@ -64,7 +72,7 @@ pub fn configure(
         if nft.to_ascii_lowercase() == "none" {
             config.nft_contract = None;
         } else {
-            let nft_addr = deps.api.addr_canonicalize(nft.as_str())?;
+            let nft_addr = addr::canonicalize(deps.api, nft.as_str())?;
             config.nft_contract = Some(nft_addr)
         }
     }
@@ -78,7 +86,38 @@ pub fn configure(
 
     config::store(deps.storage, &config)?;
 
-    Ok(Response::default())
+    let mut response = Response::default();
+    if let Some(funds) = fee_collector_funds {
+        // the factory owns the funds it created, which lets it move their fee collector. funds
+        // added from elsewhere are skipped and reported, so they don't fail the rest
+        let fee_collector = addr::humanize(deps.api, &config.fee_collector)?.to_string();
+        let ap = anchor_pools();
+        let mut skipped = vec![];
+        for fund in funds {
+            let address = addr::validate(deps.api, &fund)?;
+            if ap.may_load(deps.storage, address.to_string())?.is_none() {
+                return Err(ContractError::AnchorPoolNotFound(address.to_string()));
+            }
+            if pool_anchor_config(deps.as_ref(), &address)?.owner != env.contract.address {
+                skipped.push(address.to_string());
+                continue;
+            }
+            response = response.add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: address.to_string(),
+                msg: to_binary(&pool_msg::ExecuteMsg::Configure {
+                    beneficiary: None,
+                    fee_collector: Some(fee_collector.clone()),
+                })?,
+                funds: vec![],
+            }));
+        }
+        response = response.add_attribute("fee_collector", fee_collector);
+        if !skipped.is_empty() {
+            response = response.add_attribute("skipped_funds", skipped.join(","));
+        }
+    }
+
+    Ok(response)
 }
 
 pub fn create_anchor_fund(
@@ -90,9 +129,9 @@ pub fn create_anchor_fund(
     pool_description: String,
     beneficiary: String,
 ) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage).unwrap();
+    let config = config::read(deps.storage)?;
     let nft_contract = if let Some(nft_addr) = config.nft_contract {
-        Some(deps.api.addr_humanize(&nft_addr)?.to_string())
+        Some(addr::humanize(deps.api, &nft_addr)?.to_string())
     } else {
         None
     };
@@ -110,16 +149,17 @@ pub fn create_anchor_fund(
                 pool_title,
                 pool_description,
                 beneficiary,
-                fee_collector: deps.api.addr_humanize(&config.fee_collector)?.to_string(),
-                fee_amount: config.fee_amount.to_string(),
-                fee_max: config.fee_max,
-                fee_reset_every_num_blocks: config.fee_reset_every_num_blocks,
-                money_market: deps.api.addr_humanize(&config.money_market)?.to_string(),
+                fee_collector: addr::humanize(deps.api, &config.fee_collector)?.to_string(),
+                fee_model: config.fee_model,
+                fee_period: config.fee_period,
+                money_market: addr::humanize(deps.api, &config.money_market)?.to_string(),
                 dp_code_id: config.dp_code_id,
                 owner_can_change_config: false, // TODO should this be configurable
                 nft_contract,
                 nft_collection_active: None,
                 nft_collection_redeemed: None,
+                beneficiary_change_delay: None,
+                stable_token: None,
             })
             .map_err(|_o| ContractError::InstantiateError {
                 action: "anchor_pool_code_id".to_string(),
@@ -138,15 +178,13 @@ pub fn add_anchor_fund(
     info: MessageInfo,
     contract: String,
 ) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage)?;
-    if deps.api.addr_humanize(&config.owner)? != info.sender {
-        return Err(ContractError::Unauthorized {
-            action: "add_anchor_fund".to_string(),
-            expected: deps.api.addr_humanize(&config.owner)?.to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
-    let address = deps.api.addr_validate(&contract)?;
+    roles::ensure_any(
+        deps.storage,
+        "add_anchor_fund",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    let address = addr::validate(deps.api, &contract)?;
 
     let pool_config = pool_anchor_config(deps.as_ref(), &address)?;
 
@@ -178,15 +216,15 @@ pub fn migrate_anchor_fund(
     info: MessageInfo,
     contract: String,
 ) -> Result<Response, ContractError> {
+    // funds only move to the code id the owner configured, so operators can roll them out
+    roles::ensure_any(
+        deps.storage,
+        "migrate_anchor_fund",
+        &info.sender,
+        &[Role::Owner, Role::Operator],
+    )?;
     let config = config::read(deps.storage)?;
-    if deps.api.addr_humanize(&config.owner)? != info.sender {
-        return Err(ContractError::Unauthorized {
-            action: "migrate_anchor_fund".to_string(),
-            expected: deps.api.addr_humanize(&config.owner)?.to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
-    let address = deps.api.addr_validate(&contract)?;
+    let address = addr::validate(deps.api, &contract)?;
 
     let ap = anchor_pools();
     if ap.may_load(deps.storage, address.to_string())?.is_none() {
@@ -214,26 +252,32 @@ pub fn hide_anchor_fund(
     contract: String,
     visible: bool,
 ) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage)?;
-    let address = deps.api.addr_validate(&contract)?;
+    let address = addr::validate(deps.api, &contract)?;
 
     let ap = anchor_pools();
     if let Some(mut anchor_pool) = ap.may_load(deps.storage, address.to_string())? {
-        if (deps.api.addr_validate(&anchor_pool.beneficiary)? == info.sender && !visible)
-            || (deps.api.addr_humanize(&config.owner)? == info.sender)
-        {
-            anchor_pool.open = visible;
-            ap.save(deps.storage, address.to_string(), &anchor_pool)?;
-            Ok(Response::new()
-                .add_attribute("anchor_pool_token", address.to_string())
-                .add_attribute("visible", visible.to_string()))
-        } else {
-            Err(ContractError::Unauthorized {
-                action: "hide_anchor_fund".to_string(),
-                expected: deps.api.addr_humanize(&config.owner)?.to_string(),
-                actual: info.sender.to_string(),
-            })
+        // a fund's beneficiary or a guardian can hide it, only the owner can bring it back
+        let is_beneficiary = addr::validate(deps.api, &anchor_pool.beneficiary)? == info.sender;
+        if visible {
+            roles::ensure_any(
+                deps.storage,
+                "hide_anchor_fund",
+                &info.sender,
+                &[Role::Owner],
+            )?;
+        } else if !is_beneficiary {
+            roles::ensure_any(
+                deps.storage,
+                "hide_anchor_fund",
+                &info.sender,
+                &[Role::Owner, Role::Guardian],
+            )?;
         }
+        anchor_pool.open = visible;
+        ap.save(deps.storage, address.to_string(), &anchor_pool)?;
+        Ok(Response::new()
+            .add_attribute("anchor_pool_token", address.to_string())
+            .add_attribute("visible", visible.to_string()))
     } else {
         Err(ContractError::AnchorPoolNotFound(contract))
     }
@@ -245,26 +289,23 @@ pub fn remove_nft_from_fund(
     info: MessageInfo,
     contract: String,
 ) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage)?;
-    let address = deps.api.addr_validate(&contract)?;
+    let address = addr::validate(deps.api, &contract)?;
 
     let ap = anchor_pools();
     if let Some(anchor_pool) = ap.may_load(deps.storage, address.to_string())? {
-        if (deps.api.addr_validate(&anchor_pool.beneficiary)? == info.sender)
-            || (deps.api.addr_humanize(&config.owner)? == info.sender)
-        {
-            let clear_nft_message = pool_msg::ExecuteMsg::ClearNftContract {};
-
-            Ok(Response::new()
-                .add_attribute("remove_nft_from_fund", address.to_string())
-                .add_message(clear_nft_message.into_cosmos_msg(address)?))
-        } else {
-            Err(ContractError::Unauthorized {
-                action: "remove_nft_from_fund".to_string(),
-                expected: deps.api.addr_humanize(&config.owner)?.to_string(),
-                actual: info.sender.to_string(),
-            })
+        if addr::validate(deps.api, &anchor_pool.beneficiary)? != info.sender {
+            roles::ensure_any(
+                deps.storage,
+                "remove_nft_from_fund",
+                &info.sender,
+                &[Role::Owner],
+            )?;
         }
+        let clear_nft_message = pool_msg::ExecuteMsg::ClearNftContract {};
+
+        Ok(Response::new()
+            .add_attribute("remove_nft_from_fund", address.to_string())
+            .add_message(clear_nft_message.into_cosmos_msg(address)?))
     } else {
         Err(ContractError::AnchorPoolNotFound(contract))
     }
@@ -279,18 +320,15 @@ pub fn create_collections_for_fund(
     redeemed_meta: String,
 ) -> Result<Response, ContractError> {
     let config = read(deps.storage)?;
-    if config.nft_contract.is_none() {
-        return Err(ContractError::NFTContractNotSet);
-    }
-    let nft_contract = deps
-        .api
-        .addr_humanize(&config.nft_contract.unwrap())?
-        .to_string();
-    let address = deps.api.addr_validate(&contract)?;
+    let nft_contract = match config.nft_contract {
+        Some(ref nft_contract) => addr::humanize(deps.api, nft_contract)?.to_string(),
+        None => return Err(ContractError::NFTContractNotSet),
+    };
+    let address = addr::validate(deps.api, &contract)?;
 
     let ap = anchor_pools();
     let anchor_pool = ap.load(deps.storage, address.to_string())?;
-    let beneficiary = deps.api.addr_validate(&anchor_pool.beneficiary)?;
+    let beneficiary = addr::validate(deps.api, &anchor_pool.beneficiary)?;
     if info.sender != beneficiary {
         return Err(ContractError::Unauthorized {
             action: "create_collections_for_fund".to_string(),
@@ -430,7 +468,7 @@ pub fn register_anchor_pool_token(
 
     address: &Addr,
 ) -> Result<Response, ContractError> {
-    //  let config = config::read(deps.storage).unwrap();
+    //  let config = config::read(deps.storage)?;
 
     let pool_config = pool_anchor_config(deps.as_ref(), address)?;
 
@@ -459,8 +497,8 @@ pub fn register_nft_token(
     _env: Env,
     address: &str,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    config.nft_contract = Some(deps.api.addr_canonicalize(address)?);
+    let mut config = config::read(deps.storage)?;
+    config.nft_contract = Some(addr::canonicalize(deps.api, address)?);
 
     config::store(deps.storage, &config)?;
     Ok(Response::new().add_attribute("nft_contract", address.to_string()))
@@ -471,17 +509,16 @@ pub fn revert_nft_admin(
     _env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "revert_nft_admin",
+        &info.sender,
+        &[Role::Owner],
+    )?;
     let config = read(deps.storage)?;
-    let address = deps.api.addr_humanize(&config.owner)?;
-    if info.sender != address {
-        return Err(ContractError::Unauthorized {
-            action: "revert_nft_admin".to_string(),
-            expected: address.to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
+    let address = addr::humanize(deps.api, &config.owner)?;
     if let Some(nft_canonical_addr) = config.nft_contract {
-        let nft_addr = deps.api.addr_humanize(&nft_canonical_addr)?;
+        let nft_addr = addr::humanize(deps.api, &nft_canonical_addr)?;
         let updateadmin = CollectionExecuteMsg::UpdateAdmin {
             admin: Some(address.to_string()),
         }
@@ -495,3 +532,167 @@ pub fn revert_nft_admin(
         Err(ContractError::NFTContractNotSet)
     }
 }
+
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "propose_new_owner",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    if expires_in == 0 || expires_in > MAX_OWNERSHIP_PROPOSAL_EXPIRY {
+        return Err(ContractError::OwnershipProposalExpiryInvalid {
+            max: MAX_OWNERSHIP_PROPOSAL_EXPIRY,
+        });
+    }
+    let new_owner = addr::validate(deps.api, &owner)?;
+    let expires_at = env.block.time.seconds() + expires_in;
+    ownership_proposal_store(
+        deps.storage,
+        &OwnershipProposal {
+            proposed_owner: addr::canonicalize(deps.api, new_owner.as_str())?,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("proposed_owner", new_owner)
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+pub fn drop_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "drop_ownership_proposal",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    ownership_proposal_remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_ownership_proposal"))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let proposal =
+        ownership_proposal_read(deps.storage)?.ok_or(ContractError::NoOwnershipProposal)?;
+    let proposed_owner = addr::humanize(deps.api, &proposal.proposed_owner)?;
+    if info.sender != proposed_owner {
+        return Err(ContractError::Unauthorized {
+            action: "accept_ownership".to_string(),
+            expected: proposed_owner.to_string(),
+            actual: info.sender.to_string(),
+        });
+    }
+    if env.block.time.seconds() > proposal.expires_at {
+        return Err(ContractError::OwnershipProposalExpired);
+    }
+
+    let mut config = read(deps.storage)?;
+    let previous_owner = addr::humanize(deps.api, &config.owner)?;
+    config.owner = proposal.proposed_owner;
+    config::store(deps.storage, &config)?;
+    roles::set_holder(deps.storage, Role::Owner, &proposed_owner)?;
+    ownership_proposal_remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", proposed_owner))
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::grant(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::revoke(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+/// owner only. the factory holds nothing of its own, so anything it has was sent by mistake
+pub fn sweep(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom_or_cw20: AssetInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(deps.storage, "sweep", &info.sender, &[Role::Owner])?;
+    let recipient = addr::validate(deps.api, &recipient)?;
+    let (amount, msg): (Uint128, CosmosMsg) = match denom_or_cw20 {
+        AssetInfo::NativeToken { denom } => {
+            let amount = deps
+                .querier
+                .query_balance(&env.contract.address, &denom)?
+                .amount;
+            let msg = BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin { denom, amount }],
+            };
+            (amount, msg.into())
+        }
+        AssetInfo::Token { contract_addr } => {
+            let balance: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                &contract_addr,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            let msg = WasmMsg::Execute {
+                contract_addr,
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: balance.balance,
+                })?,
+                funds: vec![],
+            };
+            (balance.balance, msg.into())
+        }
+    };
+    if amount.is_zero() {
+        return Err(ContractError::NothingToSweep);
+    }
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "sweep")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount.to_string()))
+}
@@ -1,57 +1,70 @@
 use cosmwasm_std::*;
 use cw_storage_plus::Bound;
 use yieldpay_core::factory_response::{
-    AnchorPool, ConfigResponse, FundsCountResponse, FundsResponse,
+    AnchorPool, ConfigResponse, FundsCountResponse, FundsResponse, OwnershipProposalResponse,
 };
 use yieldpay_core::pool_anchor_response;
+use yieldpay_core::roles;
+use yieldpay_core::roles::{Role, RoleMembersResponse, RolesResponse};
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
 
+use crate::addr;
 use crate::config;
+use crate::error::ContractError;
 use crate::querier::pool_anchor::pool_anchor_config;
 use crate::state::anchor_pools;
 
-pub fn config(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let config: config::Config = config::read(deps.storage).unwrap();
+pub fn config(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config: config::Config = config::read(deps.storage)?;
     let nft_contract = if let Some(nft_canonical_addr) = config.nft_contract {
-        let addr = deps.api.addr_humanize(&nft_canonical_addr).unwrap();
+        let addr = addr::humanize(deps.api, &nft_canonical_addr)?;
         Some(addr.to_string())
     } else {
         None
     };
-    to_binary(&ConfigResponse {
-        fee_collector: deps
-            .api
-            .addr_humanize(&config.fee_collector)
-            .unwrap()
-            .to_string(),
-        money_market: deps
-            .api
-            .addr_humanize(&config.money_market)
-            .unwrap()
-            .to_string(),
+    Ok(to_binary(&ConfigResponse {
+        owner: addr::humanize(deps.api, &config.owner)?.to_string(),
+        fee_collector: addr::humanize(deps.api, &config.fee_collector)?.to_string(),
+        money_market: addr::humanize(deps.api, &config.money_market)?.to_string(),
         dp_code_id: config.dp_code_id,
-        fee_amount: config.fee_amount.to_string(),
-        fee_max: config.fee_max.to_string(),
-        fee_reset_every_num_blocks: config.fee_reset_every_num_blocks,
+        fee_model: config.fee_model,
+        fee_period: config.fee_period,
         anchor_pool_code_id: config.anchor_pool_code_id,
         nft_code_id: config.nft_code_id,
         nft_instantiate: config.nft_instantiate,
         nft_contract,
         homepage: config.homepage,
-    })
+    })?)
 }
-pub fn anchor_fund(deps: Deps, _env: Env, contract: &str) -> StdResult<Option<AnchorPool>> {
-    let addr = deps.api.addr_validate(contract)?;
-    anchor_pools().may_load(deps.storage, addr.to_string())
+pub fn ownership_proposal(
+    deps: Deps,
+    _env: Env,
+) -> Result<Option<OwnershipProposalResponse>, ContractError> {
+    config::ownership_proposal_read(deps.storage)?
+        .map(|proposal| {
+            Ok(OwnershipProposalResponse {
+                proposed_owner: addr::humanize(deps.api, &proposal.proposed_owner)?.to_string(),
+                expires_at: proposal.expires_at,
+            })
+        })
+        .transpose()
+}
+pub fn anchor_fund(
+    deps: Deps,
+    _env: Env,
+    contract: &str,
+) -> Result<Option<AnchorPool>, ContractError> {
+    let addr = addr::validate(deps.api, contract)?;
+    Ok(anchor_pools().may_load(deps.storage, addr.to_string())?)
 }
 
 pub fn anchor_fund_ex(
     deps: Deps,
     _env: Env,
     contract: &str,
-) -> StdResult<pool_anchor_response::ConfigResponse> {
-    let addr = deps.api.addr_validate(contract)?;
+) -> Result<pool_anchor_response::ConfigResponse, ContractError> {
+    let addr = addr::validate(deps.api, contract)?;
     let pool_config = pool_anchor_config(deps, &addr)?;
     Ok(pool_config)
 }
@@ -61,7 +74,7 @@ pub fn all_anchor_funds(
     _env: Env,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<FundsResponse> {
+) -> Result<FundsResponse, ContractError> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     //  let start_addr = maybe_addr(deps.api, start_after)?;
     let start = start_after.map(Bound::exclusive);
@@ -77,7 +90,7 @@ pub fn all_anchor_funds(
             .collect::<StdResult<Vec<AnchorPool>>>()?,
     })
 }
-pub fn all_anchor_fund_count(deps: Deps, _env: Env) -> StdResult<FundsCountResponse> {
+pub fn all_anchor_fund_count(deps: Deps, _env: Env) -> Result<FundsCountResponse, ContractError> {
     let i_map = anchor_pools();
     let len = i_map
         .range(deps.storage, None, None, Order::Ascending)
@@ -90,9 +103,9 @@ pub fn anchor_funds_by_beneficiary(
     beneficiary: &str,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<FundsResponse> {
+) -> Result<FundsResponse, ContractError> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let beneficiary_addr = deps.api.addr_validate(beneficiary)?.to_string();
+    let beneficiary_addr = addr::validate(deps.api, beneficiary)?.to_string();
     //let start_addr = maybe_addr(deps.api, start_after)?;
     let start = start_after.map(Bound::exclusive);
     Ok(FundsResponse {
@@ -112,9 +125,9 @@ pub fn anchor_funds_by_owner(
     owner: &str,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<FundsResponse> {
+) -> Result<FundsResponse, ContractError> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let owner_addr = deps.api.addr_validate(owner)?.to_string();
+    let owner_addr = addr::validate(deps.api, owner)?.to_string();
     let start = start_after.map(Bound::exclusive);
     Ok(FundsResponse {
         funds: anchor_pools()
@@ -134,7 +147,7 @@ pub fn anchor_funds_by_pool_name(
     pool_name: &str,
     start_after: Option<String>,
     limit: Option<u32>,
-) -> StdResult<FundsResponse> {
+) -> Result<FundsResponse, ContractError> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     let start = start_after.map(Bound::exclusive);
@@ -142,10 +155,37 @@ pub fn anchor_funds_by_pool_name(
         funds: anchor_pools()
             .idx
             .pool
-            .prefix(pool_name.parse().unwrap())
+            .prefix(pool_name.to_string())
             .range(deps.storage, start, None, Order::Ascending)
             .take(limit)
             .map(|item| item.map(|(_, v)| v))
             .collect::<StdResult<Vec<AnchorPool>>>()?,
     })
 }
+
+pub fn roles(deps: Deps, _env: Env, address: &str) -> Result<RolesResponse, ContractError> {
+    let address = addr::validate(deps.api, address)?;
+    Ok(RolesResponse {
+        roles: roles::roles_of(deps.storage, &address),
+        address: address.to_string(),
+    })
+}
+
+pub fn role_members(
+    deps: Deps,
+    _env: Env,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<RoleMembersResponse, ContractError> {
+    let start_after = start_after
+        .map(|address| addr::validate(deps.api, &address))
+        .transpose()?;
+    Ok(RoleMembersResponse {
+        role,
+        members: roles::members(deps.storage, role, start_after, limit)?
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect(),
+    })
+}
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod contract;
 
+mod addr;
 mod error;
 mod handler;
 mod querier;
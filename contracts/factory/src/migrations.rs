@@ -1,8 +1,9 @@
 use crate::config::Config;
 use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
-use cosmwasm_storage::singleton_read;
+use cosmwasm_storage::{singleton, singleton_read};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use yieldpay_core::fee::{FeeModel, FeePeriod};
 
 const CONFIG_V100: &[u8] = b"config_001";
 
@@ -28,9 +29,8 @@ impl ConfigV100 {
             this: self.this.clone(),
             owner: self.owner.clone(),
             fee_collector: self.fee_collector.clone(),
-            fee_amount: self.fee_amount,
-            fee_max: self.fee_max,
-            fee_reset_every_num_blocks: self.fee_reset_every_num_blocks,
+            fee_model: FeeModel::from_legacy(self.fee_amount, self.fee_max),
+            fee_period: FeePeriod::PerClaim {},
             money_market: self.money_market.clone(),
             dp_code_id: self.dp_code_id,
             anchor_pool_code_id: self.anchor_pool_code_id,
@@ -41,3 +41,93 @@ impl ConfigV100 {
         }
     }
 }
+
+const CONFIG_V003: &[u8] = b"config_003";
+/// config from before fees were a `FeeModel`. pools capped the fee on each claim with
+/// `fee_max`, so new funds keep getting a per claim period
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ConfigV003 {
+    pub this: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub fee_collector: CanonicalAddr,
+    pub fee_amount: Decimal,
+    pub fee_max: Uint128,
+    pub fee_reset_every_num_blocks: u64,
+    pub money_market: CanonicalAddr,
+    pub dp_code_id: u64,
+    pub anchor_pool_code_id: u64,
+    pub nft_code_id: Option<u64>,
+    pub nft_instantiate: Option<String>,
+    pub nft_contract: Option<CanonicalAddr>,
+    pub homepage: Option<String>,
+}
+impl ConfigV003 {
+    pub fn may_load(storage: &dyn Storage) -> StdResult<Option<Self>> {
+        singleton_read(storage, CONFIG_V003).may_load()
+    }
+
+    pub fn remove(storage: &mut dyn Storage) {
+        singleton::<Self>(storage, CONFIG_V003).remove()
+    }
+
+    pub fn migrate_from(&self) -> Config {
+        Config {
+            this: self.this.clone(),
+            owner: self.owner.clone(),
+            fee_collector: self.fee_collector.clone(),
+            fee_model: FeeModel::from_legacy(self.fee_amount, self.fee_max),
+            fee_period: FeePeriod::PerClaim {},
+            money_market: self.money_market.clone(),
+            dp_code_id: self.dp_code_id,
+            anchor_pool_code_id: self.anchor_pool_code_id,
+            nft_code_id: self.nft_code_id,
+            nft_instantiate: self.nft_instantiate.clone(),
+            nft_contract: self.nft_contract.clone(),
+            homepage: self.homepage.clone(),
+        }
+    }
+}
+
+const CONFIG_V004: &[u8] = b"config_004";
+/// config from before fee periods were in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ConfigV004 {
+    pub this: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub fee_collector: CanonicalAddr,
+    pub fee_model: FeeModel,
+    pub fee_reset_every_num_blocks: u64,
+    pub money_market: CanonicalAddr,
+    pub dp_code_id: u64,
+    pub anchor_pool_code_id: u64,
+    pub nft_code_id: Option<u64>,
+    pub nft_instantiate: Option<String>,
+    pub nft_contract: Option<CanonicalAddr>,
+    pub homepage: Option<String>,
+}
+impl ConfigV004 {
+    pub fn may_load(storage: &dyn Storage) -> StdResult<Option<Self>> {
+        singleton_read(storage, CONFIG_V004).may_load()
+    }
+
+    pub fn remove(storage: &mut dyn Storage) {
+        singleton::<Self>(storage, CONFIG_V004).remove()
+    }
+
+    pub fn migrate_from(&self) -> Config {
+        Config {
+            this: self.this.clone(),
+            owner: self.owner.clone(),
+            fee_collector: self.fee_collector.clone(),
+            fee_model: self.fee_model.clone(),
+            fee_period: FeePeriod::from_blocks(self.fee_reset_every_num_blocks),
+            money_market: self.money_market.clone(),
+            dp_code_id: self.dp_code_id,
+            anchor_pool_code_id: self.anchor_pool_code_id,
+            nft_code_id: self.nft_code_id,
+            nft_instantiate: self.nft_instantiate.clone(),
+            nft_contract: self.nft_contract.clone(),
+            homepage: self.homepage.clone(),
+        }
+    }
+}
@@ -1,13 +1,23 @@
 use crate::mock_querier::mock_dependencies;
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{from_binary, to_binary, StdError};
+use cosmwasm_std::{
+    from_binary, to_binary, Attribute, BankMsg, Coin, CosmosMsg, Decimal, StdError, Uint128,
+    WasmMsg,
+};
 
 use crate::contract;
+use crate::error::ContractError;
 use schemars::_serde_json::json;
+use yieldpay_core::dex::AssetInfo;
 use yieldpay_core::factory_msg;
-use yieldpay_core::factory_response::FundsCountResponse;
+use yieldpay_core::factory_response::{
+    ConfigResponse, FundsCountResponse, OwnershipProposalResponse,
+};
+use yieldpay_core::fee::{FeeModel, FeePeriod, FeeTier};
+use yieldpay_core::pool_anchor_msg;
 use yieldpay_core::pool_anchor_response;
 use yieldpay_core::pool_msg;
+use yieldpay_core::roles::{Role, RoleMembersResponse, RolesResponse};
 
 const ANCHOR_POOL: &str = "pool-anchor";
 const ANCHOR_POOL_2: &str = "pool-anchor-2";
@@ -51,9 +61,11 @@ fn instantiate() {
 
     let msg = factory_msg::InstantiateMsg {
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 666,
         anchor_pool_code_id: 12345,
@@ -130,9 +142,11 @@ fn add_pool() {
     );
     let msg = factory_msg::InstantiateMsg {
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 666,
         anchor_pool_code_id: 12345,
@@ -227,9 +241,11 @@ fn create_anchor_fund() {
 
     let msg = factory_msg::InstantiateMsg {
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 666,
         anchor_pool_code_id: 12345,
@@ -251,9 +267,9 @@ fn create_anchor_fund() {
 
     let msg = factory_msg::ExecuteMsg::Configure {
         fee_collector: None,
-        fee_amount: None,
-        fee_max: None,
-        fee_reset_every_num_blocks: None,
+        fee_collector_funds: None,
+        fee_model: None,
+        fee_period: None,
         money_market: None,
         dp_code_id: None,
         anchor_pool_code_id: None,
@@ -274,3 +290,591 @@ fn create_anchor_fund() {
     // println!("{:?}", resp);
     // assert!(false, "see prints")
 }
+
+#[test]
+fn ownership_transfer() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let info = mock_info("owner", &[]);
+
+    let msg = factory_msg::InstantiateMsg {
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 666,
+        anchor_pool_code_id: 12345,
+        nft_code_id: None,
+        homepage: None,
+    };
+    let _resp = contract::instantiate(deps.as_mut(), env.clone(), info.clone(), msg)
+        .expect("testing: should init contract");
+
+    let propose = factory_msg::ExecuteMsg::ProposeNewOwner {
+        owner: "new-owner".to_string(),
+        expires_in: 600,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("new-owner", &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let _resp = contract::execute(deps.as_mut(), env.clone(), info.clone(), propose)
+        .expect("testing: owner should propose");
+
+    let proposal = from_binary::<Option<OwnershipProposalResponse>>(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            factory_msg::QueryMsg::OwnershipProposal {},
+        )
+        .unwrap(),
+    )
+    .unwrap()
+    .expect("testing: proposal should be pending");
+    assert_eq!(proposal.proposed_owner, "new-owner");
+
+    let mut late = env.clone();
+    late.block.time = late.block.time.plus_seconds(601);
+    let err = contract::execute(
+        deps.as_mut(),
+        late,
+        mock_info("new-owner", &[]),
+        factory_msg::ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipProposalExpired));
+
+    let _resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("new-owner", &[]),
+        factory_msg::ExecuteMsg::AcceptOwnership {},
+    )
+    .expect("testing: proposed owner should accept");
+    let config = from_binary::<ConfigResponse>(
+        &contract::query(deps.as_ref(), env, factory_msg::QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.owner, "new-owner");
+
+    // the owner role moves with it
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        factory_msg::ExecuteMsg::ProposeNewOwner {
+            owner: "owner".to_string(),
+            expires_in: 600,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+}
+
+#[test]
+fn roles() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+    deps.querier.register_wasm_smart_query_handler(
+        ANCHOR_POOL.to_string(),
+        Box::new(|x| match from_binary::<pool_msg::QueryMsg>(x).unwrap() {
+            pool_msg::QueryMsg::Config {} => to_binary(&pool_anchor_response::ConfigResponse {
+                pool_name: "pool_name".to_string(),
+                pool_title: "pool_title".to_string(),
+                pool_description: "pool_description".to_string(),
+                beneficiary: BENEFICIARY.to_string(),
+                fee_collector: "fee_addr".to_string(),
+                owner: "owner_addr".to_string(),
+                money_market: "money_addr".to_string(),
+                stable_denom: "stable".to_string(),
+                anchor_token: "anchor_token".to_string(),
+                dp_token: "1234".to_string(),
+                owner_can_change_config: false,
+                nft_contract: None,
+                nft_collection_active: None,
+                nft_collection_redeemed: None,
+            }),
+            _ => Err(StdError::GenericErr {
+                msg: "wrong".to_string(),
+            }),
+        }),
+    );
+    let msg = factory_msg::InstantiateMsg {
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 666,
+        anchor_pool_code_id: 12345,
+        nft_code_id: None,
+        homepage: None,
+    };
+    contract::instantiate(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    let grant = factory_msg::ExecuteMsg::GrantRole {
+        role: Role::Guardian,
+        address: "guardian".to_string(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("guardian", &[]),
+        grant.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(deps.as_mut(), env.clone(), owner.clone(), grant).unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        factory_msg::ExecuteMsg::GrantRole {
+            role: Role::Owner,
+            address: "guardian".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::RoleNotGrantable(_)));
+    let roles = from_binary::<RolesResponse>(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            factory_msg::QueryMsg::Roles {
+                address: "guardian".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(roles.roles, vec![Role::Guardian]);
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        factory_msg::ExecuteMsg::AddAnchorFund {
+            contract: ANCHOR_POOL.into(),
+        },
+    )
+    .unwrap();
+    let hide = |visible: bool| factory_msg::ExecuteMsg::HideAnchorFund {
+        contract: ANCHOR_POOL.into(),
+        visible,
+    };
+    // a guardian or the fund's beneficiary can hide it, only the owner can unhide
+    for sender in ["guardian", BENEFICIARY] {
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(sender, &[]),
+            hide(false),
+        )
+        .unwrap();
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(sender, &[]),
+            hide(true),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        contract::execute(deps.as_mut(), env.clone(), owner.clone(), hide(true)).unwrap();
+    }
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("someone", &[]),
+        hide(false),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    // operators roll funds out to the configured code id
+    let migrate = factory_msg::ExecuteMsg::MigrateAnchorFund {
+        contract: ANCHOR_POOL.into(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        migrate.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        factory_msg::ExecuteMsg::GrantRole {
+            role: Role::Operator,
+            address: "operator".to_string(),
+        },
+    )
+    .unwrap();
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        migrate,
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Migrate {
+            contract_addr: ANCHOR_POOL.to_string(),
+            new_code_id: 12345,
+            msg: to_binary(&pool_anchor_msg::MigrateMsg {}).unwrap(),
+        })
+    );
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner,
+        factory_msg::ExecuteMsg::RevokeRole {
+            role: Role::Guardian,
+            address: "guardian".to_string(),
+        },
+    )
+    .unwrap();
+    let members = from_binary::<RoleMembersResponse>(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            factory_msg::QueryMsg::RoleMembers {
+                role: Role::Guardian,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(members.members.is_empty());
+}
+
+#[test]
+fn typed_errors() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+
+    // nothing stored before instantiate
+    let err =
+        contract::query(deps.as_ref(), env.clone(), factory_msg::QueryMsg::Config {}).unwrap_err();
+    assert!(matches!(err, ContractError::ConfigMissing(item) if item == "config"));
+
+    let msg = factory_msg::InstantiateMsg {
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 666,
+        anchor_pool_code_id: 12345,
+        nft_code_id: None,
+        homepage: None,
+    };
+    contract::instantiate(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        factory_msg::ExecuteMsg::Configure {
+            fee_collector: Some("x".to_string()),
+            fee_collector_funds: None,
+            fee_model: None,
+            fee_period: None,
+            money_market: None,
+            dp_code_id: None,
+            anchor_pool_code_id: None,
+            nft_contract: None,
+            homepage: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidAddress(address) if address == "x"));
+
+    let err = contract::query(
+        deps.as_ref(),
+        env.clone(),
+        factory_msg::QueryMsg::AnchorFund {
+            contract: "Not-Normalized".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidAddress(address) if address == "Not-Normalized"));
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info(BENEFICIARY, &[]),
+        factory_msg::ExecuteMsg::CreateCollectionsForFund {
+            contract: ANCHOR_POOL.to_string(),
+            active_meta: ACTIVE_META.to_string(),
+            redeemed_meta: REDEEM_META.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NFTContractNotSet));
+}
+
+#[test]
+fn fee_model() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+    let msg = |fee_model: FeeModel| factory_msg::InstantiateMsg {
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model,
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 666,
+        anchor_pool_code_id: 12345,
+        nft_code_id: None,
+        homepage: None,
+    };
+    let unordered = FeeModel::Tiered {
+        tiers: vec![
+            FeeTier {
+                threshold: Uint128::from(1_000u64),
+                rate: Decimal::percent(5),
+            },
+            FeeTier {
+                threshold: Uint128::zero(),
+                rate: Decimal::percent(10),
+            },
+        ],
+        cap: Uint128::zero(),
+    };
+    let err = contract::instantiate(deps.as_mut(), env.clone(), owner.clone(), msg(unordered))
+        .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidFeeModel));
+
+    let free_below = FeeModel::FreeBelow {
+        threshold: Uint128::from(1_000_000u64),
+        rate: Decimal::percent(2),
+        cap: Uint128::from(50_000_000u64),
+    };
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        msg(free_below.clone()),
+    )
+    .unwrap();
+    let config: ConfigResponse = from_binary(
+        &contract::query(deps.as_ref(), env.clone(), factory_msg::QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.fee_model, free_below);
+
+    let flat = FeeModel::Flat {
+        amount: Uint128::from(10_000u64),
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner,
+        factory_msg::ExecuteMsg::Configure {
+            fee_collector: None,
+            fee_collector_funds: None,
+            fee_model: Some(flat.clone()),
+            fee_period: None,
+            money_market: None,
+            dp_code_id: None,
+            anchor_pool_code_id: None,
+            nft_contract: None,
+            homepage: None,
+        },
+    )
+    .unwrap();
+    let config: ConfigResponse = from_binary(
+        &contract::query(deps.as_ref(), env, factory_msg::QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.fee_model, flat);
+}
+
+#[test]
+fn fee_collector_funds() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+    // the first fund was made by the factory, the second elsewhere and only added to it
+    for (pool, pool_owner) in [
+        (ANCHOR_POOL, env.contract.address.to_string()),
+        (ANCHOR_POOL_2, "someone-else".to_string()),
+    ] {
+        deps.querier.register_wasm_smart_query_handler(
+            pool.to_string(),
+            Box::new(
+                move |x| match from_binary::<pool_msg::QueryMsg>(x).unwrap() {
+                    pool_msg::QueryMsg::Config {} => {
+                        to_binary(&pool_anchor_response::ConfigResponse {
+                            pool_name: "pool_name".to_string(),
+                            pool_title: "pool_title".to_string(),
+                            pool_description: "pool_description".to_string(),
+                            beneficiary: BENEFICIARY.to_string(),
+                            fee_collector: "test-fee-collector".to_string(),
+                            owner: pool_owner.clone(),
+                            money_market: "money_addr".to_string(),
+                            stable_denom: "stable".to_string(),
+                            anchor_token: "anchor_token".to_string(),
+                            dp_token: "1234".to_string(),
+                            owner_can_change_config: false,
+                            nft_contract: None,
+                            nft_collection_active: None,
+                            nft_collection_redeemed: None,
+                        })
+                    }
+                    _ => Err(StdError::GenericErr {
+                        msg: "wrong".to_string(),
+                    }),
+                },
+            ),
+        );
+    }
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        factory_msg::InstantiateMsg {
+            fee_collector: "test-fee-collector".to_string(),
+            fee_model: FeeModel::Percent {
+                rate: Decimal::percent(5),
+                cap: Uint128::zero(),
+            },
+            fee_period: FeePeriod::PerClaim {},
+            money_market: MONEY_MARKET.to_string(),
+            dp_code_id: 666,
+            anchor_pool_code_id: 12345,
+            nft_code_id: None,
+            homepage: None,
+        },
+    )
+    .unwrap();
+    for pool in [ANCHOR_POOL, ANCHOR_POOL_2] {
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            factory_msg::ExecuteMsg::AddAnchorFund {
+                contract: pool.to_string(),
+            },
+        )
+        .unwrap();
+    }
+    let configure = |funds: Vec<&str>| factory_msg::ExecuteMsg::Configure {
+        fee_collector: Some("fee-splitter".to_string()),
+        fee_collector_funds: Some(funds.into_iter().map(String::from).collect()),
+        fee_model: None,
+        fee_period: None,
+        money_market: None,
+        dp_code_id: None,
+        anchor_pool_code_id: None,
+        nft_contract: None,
+        homepage: None,
+    };
+
+    // only funds the factory knows about
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner.clone(),
+        configure(vec![ANCHOR_POOL, "unknown-fund"]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::AnchorPoolNotFound(pool) if pool == "unknown-fund"));
+
+    // a fund the factory doesn't own is skipped, not a reason to fail the rest
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        owner,
+        configure(vec![ANCHOR_POOL, ANCHOR_POOL_2]),
+    )
+    .unwrap();
+    assert_eq!(resp.messages.len(), 1);
+    assert!(resp
+        .attributes
+        .contains(&Attribute::new("skipped_funds", ANCHOR_POOL_2)));
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: ANCHOR_POOL.to_string(),
+            msg: to_binary(&pool_msg::ExecuteMsg::Configure {
+                beneficiary: None,
+                fee_collector: Some("fee-splitter".to_string()),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    let config: ConfigResponse = from_binary(
+        &contract::query(deps.as_ref(), env, factory_msg::QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.fee_collector, "fee-splitter");
+}
+
+#[test]
+fn sweep() {
+    let mut deps = mock_dependencies(&[Coin::new(500, "uluna")]);
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+    let msg = factory_msg::InstantiateMsg {
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 666,
+        anchor_pool_code_id: 12345,
+        nft_code_id: None,
+        homepage: None,
+    };
+    contract::instantiate(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+    let sweep = |denom: &str| factory_msg::ExecuteMsg::Sweep {
+        denom_or_cw20: AssetInfo::NativeToken {
+            denom: denom.to_string(),
+        },
+        recipient: "alice".to_string(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        sweep("uluna"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err =
+        contract::execute(deps.as_mut(), env.clone(), owner.clone(), sweep("uusd")).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToSweep));
+    let resp = contract::execute(deps.as_mut(), env, owner, sweep("uluna")).unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(500, "uluna")],
+        })
+    );
+}
@@ -0,0 +1,19 @@
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use std::env::current_dir;
+use std::fs::create_dir_all;
+use yieldpay_core::fee_splitter_msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use yieldpay_core::fee_splitter_response::{ClaimableResponse, ConfigResponse, PayoutsResponse};
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(ClaimableResponse), &out_dir);
+    export_schema(&schema_for!(PayoutsResponse), &out_dir);
+}
@@ -0,0 +1,39 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Decimal, StdError, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+
+use yieldpay_core::fee_splitter_msg::ShareKind;
+
+use crate::error::ContractError;
+
+pub static CONFIG_KEY: &[u8] = b"config";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Config {
+    pub shares: Vec<FeeShare>,
+    /// cw20 tokens split along with the native balance
+    #[serde(default)]
+    pub tokens: Vec<CanonicalAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeShare {
+    pub recipient: CanonicalAddr,
+    pub kind: ShareKind,
+    pub share: Decimal,
+}
+
+pub fn store(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
+    singleton(storage, CONFIG_KEY).save(data)
+}
+
+pub fn read(storage: &dyn Storage) -> Result<Config, ContractError> {
+    singleton_read(storage, CONFIG_KEY)
+        .load()
+        .map_err(|err| match err {
+            StdError::NotFound { .. } => ContractError::ConfigMissing("config".to_string()),
+            err => ContractError::Std(err),
+        })
+}
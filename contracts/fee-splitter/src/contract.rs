@@ -0,0 +1,89 @@
+use cw2::{get_contract_version, set_contract_version};
+
+/// Contract name that is used for migration.
+const CONTRACT_NAME: &str = "yieldpay-fee-splitter";
+/// Contract version that is used for migration.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use yieldpay_core::fee_splitter_msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::config;
+use crate::error::ContractError;
+use crate::handler::core as CoreHandler;
+use crate::handler::query as QueryHandler;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let shares = CoreHandler::validate_shares(deps.as_ref(), &msg.shares)?;
+    let tokens = CoreHandler::validate_tokens(deps.as_ref(), &msg.tokens.unwrap_or_default())?;
+    config::store(deps.storage, &config::Config { shares, tokens })?;
+    roles::set_holder(deps.storage, Role::Owner, &info.sender)?;
+
+    Ok(Response::new().add_attribute("action", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Distribute {} => CoreHandler::distribute(deps, env, info),
+        ExecuteMsg::Claim {} => CoreHandler::claim(deps, env, info),
+        ExecuteMsg::UpdateShares { shares } => CoreHandler::update_shares(deps, env, info, shares),
+        ExecuteMsg::UpdateTokens { tokens } => CoreHandler::update_tokens(deps, env, info, tokens),
+        ExecuteMsg::GrantRole { role, address } => {
+            CoreHandler::grant_role(deps, env, info, role, address)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            CoreHandler::revoke_role(deps, env, info, role, address)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    Ok(match msg {
+        QueryMsg::Config {} => to_binary(&QueryHandler::config(deps, env)?)?,
+        QueryMsg::Claimable { address } => {
+            to_binary(&QueryHandler::claimable(deps, env, &address)?)?
+        }
+        QueryMsg::Payouts { start_after, limit } => {
+            to_binary(&QueryHandler::payouts(deps, env, start_after, limit)?)?
+        }
+        QueryMsg::Roles { address } => to_binary(&QueryHandler::roles(deps, env, &address)?)?,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_version = get_contract_version(deps.storage)?;
+    if contract_version.contract != CONTRACT_NAME {
+        return Err(ContractError::MigrationError {
+            current_name: contract_version.contract,
+            current_version: contract_version.version,
+        });
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("previous_contract_name", &contract_version.contract)
+        .add_attribute("previous_contract_version", &contract_version.version)
+        .add_attribute("new_contract_name", CONTRACT_NAME)
+        .add_attribute("new_contract_version", CONTRACT_VERSION))
+}
@@ -0,0 +1,56 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+use yieldpay_core::roles::RolesError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("StdError:{0}")]
+    Std(#[from] StdError),
+
+    #[error("Overflow:{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error(
+        "FeeSplitter: Unauthorized (action: {action:?}, expected: {expected:?}, actual: {actual:?})"
+    )]
+    Unauthorized {
+        action: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("FeeSplitter: {0} can't be granted or revoked directly")]
+    RoleNotGrantable(String),
+
+    #[error("FeeSplitter: invalid address {0:?}")]
+    InvalidAddress(String),
+    #[error("FeeSplitter: {0} has not been stored")]
+    ConfigMissing(String),
+
+    #[error("FeeSplitter: shares have to be non zero and add up to 1")]
+    InvalidShares,
+    #[error("FeeSplitter: nothing to claim for {0}")]
+    NothingToClaim(String),
+    #[error("FeeSplitter: Contract can't be migrated! {current_name:?} {current_version:?}")]
+    MigrationError {
+        current_name: String,
+        current_version: String,
+    },
+}
+
+impl From<RolesError> for ContractError {
+    fn from(err: RolesError) -> Self {
+        match err {
+            RolesError::Std(err) => ContractError::Std(err),
+            RolesError::Unauthorized {
+                action,
+                expected,
+                actual,
+            } => ContractError::Unauthorized {
+                action,
+                expected,
+                actual,
+            },
+            RolesError::NotGrantable(role) => ContractError::RoleNotGrantable(role),
+        }
+    }
+}
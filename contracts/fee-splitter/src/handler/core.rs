@@ -0,0 +1,256 @@
+use cosmwasm_std::*;
+use cw20::Cw20ExecuteMsg;
+use yieldpay_core::fee_splitter_msg::FeeShare;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+use yieldpay_core::token;
+
+use crate::addr;
+use crate::config;
+use crate::error::ContractError;
+use crate::state::{Payout, CLAIMABLE, CW20_PREFIX, PAYOUTS, PAYOUT_COUNT, TOTAL_CLAIMABLE};
+
+/// shares have to be non zero and add up to exactly 1, so nothing is left unallocated
+pub fn validate_shares(
+    deps: Deps,
+    shares: &[FeeShare],
+) -> Result<Vec<config::FeeShare>, ContractError> {
+    let mut total = Decimal::zero();
+    let mut validated = vec![];
+    for share in shares {
+        if share.share.is_zero() {
+            return Err(ContractError::InvalidShares);
+        }
+        total = total.checked_add(share.share)?;
+        validated.push(config::FeeShare {
+            recipient: addr::canonicalize(deps.api, &share.recipient)?,
+            kind: share.kind,
+            share: share.share,
+        });
+    }
+    if total != Decimal::one() {
+        return Err(ContractError::InvalidShares);
+    }
+    Ok(validated)
+}
+
+pub fn validate_tokens(deps: Deps, tokens: &[String]) -> Result<Vec<CanonicalAddr>, ContractError> {
+    tokens
+        .iter()
+        .map(|token| addr::canonicalize(deps.api, addr::validate(deps.api, token)?.as_str()))
+        .collect()
+}
+
+/// the native balance, and the balance of each cw20 token under `cw20:{address}`
+fn balances(deps: Deps, env: &Env, config: &config::Config) -> Result<Vec<Coin>, ContractError> {
+    let mut balances = deps
+        .querier
+        .query_all_balances(env.contract.address.to_string())?;
+    for token in &config.tokens {
+        let token = addr::humanize(deps.api, token)?;
+        balances.push(Coin {
+            denom: format!("{}{}", CW20_PREFIX, token),
+            amount: token::balance_of(deps, token.to_string(), env.contract.address.to_string())?,
+        });
+    }
+    Ok(balances)
+}
+
+/// splits any balance that isn't already owed to someone between the current shares.
+/// returns what was split
+fn allocate(deps: DepsMut, env: &Env) -> Result<Vec<Coin>, ContractError> {
+    let config = config::read(deps.storage)?;
+    let mut allocated = vec![];
+    for balance in balances(deps.as_ref(), env, &config)? {
+        let denom = balance.denom.as_str();
+        let owed = TOTAL_CLAIMABLE
+            .may_load(deps.storage, denom)?
+            .unwrap_or_default();
+        let unallocated = balance.amount.saturating_sub(owed);
+        if unallocated.is_zero() {
+            continue;
+        }
+        // the first share also takes whatever rounding leaves over
+        let mut remaining = unallocated;
+        for (i, share) in config.shares.iter().enumerate().rev() {
+            let amount = if i == 0 {
+                remaining
+            } else {
+                unallocated * share.share
+            };
+            remaining = remaining.checked_sub(amount)?;
+            if amount.is_zero() {
+                continue;
+            }
+            let recipient = addr::humanize(deps.api, &share.recipient)?;
+            CLAIMABLE.update(
+                deps.storage,
+                (&recipient, denom),
+                |claimable| -> StdResult<_> {
+                    Ok(claimable.unwrap_or_default().checked_add(amount)?)
+                },
+            )?;
+        }
+        TOTAL_CLAIMABLE.save(deps.storage, denom, &(owed + unallocated))?;
+        allocated.push(Coin::new(unallocated.u128(), denom));
+    }
+    Ok(allocated)
+}
+
+fn allocated_attribute(allocated: &[Coin]) -> String {
+    allocated
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn distribute(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
+    let allocated = allocate(deps, &env)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "distribute")
+        .add_attribute("amount", allocated_attribute(&allocated)))
+}
+
+pub fn claim(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    allocate(deps.branch(), &env)?;
+
+    let amounts = CLAIMABLE
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    if amounts.is_empty() {
+        return Err(ContractError::NothingToClaim(info.sender.to_string()));
+    }
+    for coin in &amounts {
+        CLAIMABLE.remove(deps.storage, (&info.sender, coin.denom.as_str()));
+        TOTAL_CLAIMABLE.update(deps.storage, coin.denom.as_str(), |owed| -> StdResult<_> {
+            Ok(owed.unwrap_or_default().checked_sub(coin.amount)?)
+        })?;
+    }
+
+    let id = PAYOUT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PAYOUTS.save(
+        deps.storage,
+        id,
+        &Payout {
+            id,
+            recipient: info.sender.clone(),
+            amounts: amounts.clone(),
+            height: env.block.height,
+            time: env.block.time.seconds(),
+        },
+    )?;
+    PAYOUT_COUNT.save(deps.storage, &id)?;
+
+    let (tokens, native): (Vec<_>, Vec<_>) = amounts
+        .iter()
+        .cloned()
+        .partition(|coin| coin.denom.starts_with(CW20_PREFIX));
+    let mut messages = vec![];
+    if !native.is_empty() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: native,
+        }));
+    }
+    for coin in tokens {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: coin.denom[CW20_PREFIX.len()..].to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "claim")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("payout_id", id.to_string())
+        .add_attribute("amount", allocated_attribute(&amounts)))
+}
+
+pub fn update_shares(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    shares: Vec<FeeShare>,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "update_shares",
+        &info.sender,
+        &[Role::Owner, Role::Admin],
+    )?;
+    let shares = validate_shares(deps.as_ref(), &shares)?;
+    // fees that came in under the old shares are split under them
+    let allocated = allocate(deps.branch(), &env)?;
+    let mut config = config::read(deps.storage)?;
+    config.shares = shares;
+    config::store(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_shares")
+        .add_attribute("distributed", allocated_attribute(&allocated)))
+}
+
+pub fn update_tokens(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tokens: Vec<String>,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "update_tokens",
+        &info.sender,
+        &[Role::Owner, Role::Admin],
+    )?;
+    let tokens = validate_tokens(deps.as_ref(), &tokens)?;
+    // whatever was received of a token being dropped is still split
+    let allocated = allocate(deps.branch(), &env)?;
+    let mut config = config::read(deps.storage)?;
+    config.tokens = tokens;
+    config::store(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_tokens")
+        .add_attribute("distributed", allocated_attribute(&allocated)))
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::grant(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::revoke(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
@@ -0,0 +1,93 @@
+use cosmwasm_std::*;
+use cw_storage_plus::Bound;
+use yieldpay_core::fee_splitter_msg::FeeShare;
+use yieldpay_core::fee_splitter_response::{
+    ClaimableResponse, ConfigResponse, PayoutResponse, PayoutsResponse,
+};
+use yieldpay_core::roles;
+use yieldpay_core::roles::{Role, RolesResponse};
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+use crate::addr;
+use crate::config;
+use crate::error::ContractError;
+use crate::state::{CLAIMABLE, PAYOUTS};
+
+pub fn config(deps: Deps, _env: Env) -> Result<ConfigResponse, ContractError> {
+    let config = config::read(deps.storage)?;
+    let owner = roles::members(deps.storage, Role::Owner, None, Some(1))?
+        .pop()
+        .map(|owner| owner.to_string())
+        .unwrap_or_default();
+    let shares = config
+        .shares
+        .into_iter()
+        .map(|share| {
+            Ok(FeeShare {
+                recipient: addr::humanize(deps.api, &share.recipient)?.to_string(),
+                kind: share.kind,
+                share: share.share,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    let tokens = config
+        .tokens
+        .iter()
+        .map(|token| Ok(addr::humanize(deps.api, token)?.to_string()))
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    Ok(ConfigResponse {
+        owner,
+        shares,
+        tokens,
+    })
+}
+
+pub fn claimable(deps: Deps, _env: Env, address: &str) -> Result<ClaimableResponse, ContractError> {
+    let address = addr::validate(deps.api, address)?;
+    let amounts = CLAIMABLE
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ClaimableResponse {
+        address: address.to_string(),
+        amounts,
+    })
+}
+
+pub fn payouts(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<PayoutsResponse, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let payouts = PAYOUTS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(_, payout)| PayoutResponse {
+                id: payout.id,
+                recipient: payout.recipient.to_string(),
+                amounts: payout.amounts,
+                height: payout.height,
+                time: payout.time,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PayoutsResponse { payouts })
+}
+
+pub fn roles(deps: Deps, _env: Env, address: &str) -> Result<RolesResponse, ContractError> {
+    let address = addr::validate(deps.api, address)?;
+    Ok(RolesResponse {
+        roles: roles::roles_of(deps.storage, &address),
+        address: address.to_string(),
+    })
+}
@@ -0,0 +1,10 @@
+pub mod config;
+pub mod contract;
+
+mod addr;
+mod error;
+mod handler;
+
+pub mod state;
+#[cfg(test)]
+mod test;
@@ -0,0 +1,26 @@
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// cw20 balances are kept under `cw20:{address}` alongside the native denoms
+pub const CW20_PREFIX: &str = "cw20:";
+
+/// what each recipient can claim, per denom
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+/// sum of `CLAIMABLE` per denom. anything held above this hasn't been distributed yet
+pub const TOTAL_CLAIMABLE: Map<&str, Uint128> = Map::new("total_claimable");
+
+pub const PAYOUT_COUNT: Item<u64> = Item::new("payout_count");
+/// every claim, keyed by a running count. only ever appended to
+pub const PAYOUTS: Map<u64, Payout> = Map::new("payouts");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub id: u64,
+    pub recipient: Addr,
+    pub amounts: Vec<Coin>,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+}
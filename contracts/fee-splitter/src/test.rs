@@ -0,0 +1,301 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, BankMsg, Coin, ContractResult, CosmosMsg, Decimal,
+    MemoryStorage, OwnedDeps, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg};
+use yieldpay_core::fee_splitter_msg::{ExecuteMsg, FeeShare, InstantiateMsg, QueryMsg, ShareKind};
+use yieldpay_core::fee_splitter_response::{ClaimableResponse, ConfigResponse, PayoutsResponse};
+
+use crate::contract;
+use crate::error::ContractError;
+
+fn shares(treasury: u64, developer: u64, referrer: u64) -> Vec<FeeShare> {
+    vec![
+        FeeShare {
+            recipient: "treasury".to_string(),
+            kind: ShareKind::Treasury,
+            share: Decimal::percent(treasury),
+        },
+        FeeShare {
+            recipient: "developer".to_string(),
+            kind: ShareKind::Developer,
+            share: Decimal::percent(developer),
+        },
+        FeeShare {
+            recipient: "referrer".to_string(),
+            kind: ShareKind::Referrer,
+            share: Decimal::percent(referrer),
+        },
+    ]
+}
+
+fn claimable(deps: &OwnedDeps<MemoryStorage, MockApi, MockQuerier>, address: &str) -> Vec<Coin> {
+    from_binary::<ClaimableResponse>(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Claimable {
+                address: address.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+    .amounts
+}
+
+#[test]
+fn instantiate() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    for bad in [shares(60, 30, 5), shares(70, 30, 0)] {
+        let err = contract::instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                shares: bad,
+                tokens: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidShares);
+    }
+
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            shares: shares(60, 30, 10),
+            tokens: None,
+        },
+    )
+    .unwrap();
+    let config: ConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, "owner");
+    assert_eq!(config.shares, shares(60, 30, 10));
+}
+
+#[test]
+fn distribute_and_claim() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let contract_address = env.contract.address.to_string();
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            shares: shares(60, 30, 10),
+            tokens: None,
+        },
+    )
+    .unwrap();
+
+    // fees arrive as bank sends, so they sit there until someone distributes
+    deps.querier
+        .update_balance(contract_address.clone(), coins(1_001, "uusd"));
+    assert!(claimable(&deps, "treasury").is_empty());
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    // the treasury picks up the rounding
+    assert_eq!(claimable(&deps, "treasury"), coins(601, "uusd"));
+    assert_eq!(claimable(&deps, "developer"), coins(300, "uusd"));
+    assert_eq!(claimable(&deps, "referrer"), coins(100, "uusd"));
+
+    // distributing again doesn't split the same fees twice
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(claimable(&deps, "developer"), coins(300, "uusd"));
+
+    // claiming picks up anything that arrived since
+    deps.querier
+        .update_balance(contract_address.clone(), coins(2_001, "uusd"));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("developer", &[]),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "developer".to_string(),
+            amount: coins(600, "uusd"),
+        })
+    );
+    assert!(claimable(&deps, "developer").is_empty());
+    assert_eq!(claimable(&deps, "treasury"), coins(1_201, "uusd"));
+
+    deps.querier
+        .update_balance(contract_address, coins(1_401, "uusd"));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("developer", &[]),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToClaim("developer".to_string()));
+
+    let payouts: PayoutsResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Payouts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(payouts.payouts.len(), 1);
+    assert_eq!(payouts.payouts[0].recipient, "developer");
+    assert_eq!(payouts.payouts[0].amounts, coins(600, "uusd"));
+}
+
+#[test]
+fn update_shares() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            shares: shares(60, 30, 10),
+            tokens: None,
+        },
+    )
+    .unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("treasury", &[]),
+        ExecuteMsg::UpdateShares {
+            shares: shares(100, 0, 0),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    // what came in before the change is split the old way
+    deps.querier
+        .update_balance(env.contract.address.to_string(), coins(1_000, "uusd"));
+    contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateShares {
+            shares: vec![FeeShare {
+                recipient: "treasury".to_string(),
+                kind: ShareKind::Treasury,
+                share: Decimal::one(),
+            }],
+        },
+    )
+    .unwrap();
+    assert_eq!(claimable(&deps, "referrer"), coins(100, "uusd"));
+    assert_eq!(claimable(&deps, "treasury"), coins(600, "uusd"));
+}
+
+#[test]
+fn cw20_fees() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            shares: shares(60, 30, 10),
+            tokens: Some(vec!["stable".to_string()]),
+        },
+    )
+    .unwrap();
+    let config: ConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap())
+            .unwrap();
+    assert_eq!(config.tokens, vec!["stable".to_string()]);
+
+    // fees from cw20 pools arrive as cw20 transfers
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "stable" => {
+            SystemResult::Ok(ContractResult::Ok(
+                to_binary(&BalanceResponse {
+                    balance: Uint128::new(1_000),
+                })
+                .unwrap(),
+            ))
+        }
+        _ => panic!("unexpected query"),
+    });
+    deps.querier
+        .update_balance(env.contract.address.to_string(), coins(100, "uusd"));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("developer", &[]),
+        ExecuteMsg::Claim {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "developer".to_string(),
+            amount: coins(30, "uusd"),
+        })
+    );
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "stable".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "developer".to_string(),
+                amount: Uint128::new(300),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        claimable(&deps, "treasury"),
+        vec![coin(600, "cw20:stable"), coin(60, "uusd")]
+    );
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("treasury", &[]),
+        ExecuteMsg::UpdateTokens { tokens: vec![] },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateTokens { tokens: vec![] },
+    )
+    .unwrap();
+    let config: ConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+    assert!(config.tokens.is_empty());
+}
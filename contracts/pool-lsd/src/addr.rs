@@ -0,0 +1,28 @@
+//! address conversions that fail with a `ContractError` naming the address, instead of a bare StdError
+use cosmwasm_std::{Addr, Api, CanonicalAddr};
+
+use crate::config::Config;
+use crate::error::ContractError;
+
+pub fn validate(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
+
+pub fn canonicalize(api: &dyn Api, address: &str) -> Result<CanonicalAddr, ContractError> {
+    api.addr_canonicalize(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
+
+pub fn humanize(api: &dyn Api, address: &CanonicalAddr) -> Result<Addr, ContractError> {
+    api.addr_humanize(address)
+        .map_err(|_| ContractError::InvalidAddress(address.to_string()))
+}
+
+/// the DP token is only known once its instantiate reply has come back
+pub fn dp_token(api: &dyn Api, config: &Config) -> Result<Addr, ContractError> {
+    if config.dp_token.is_empty() {
+        return Err(ContractError::DpTokenNotRegistered);
+    }
+    humanize(api, &config.dp_token)
+}
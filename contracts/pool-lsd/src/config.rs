@@ -2,11 +2,47 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
+use cosmwasm_std::{CanonicalAddr, Decimal, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{singleton, singleton_read};
 
-pub static CONFIG_KEY: &[u8] = b"config_v104";
-pub static LAST_CLAIMED_KEY: &[u8] = b"last_claimed";
+use yieldpay_core::fee::{FeeModel, FeePeriod};
+use yieldpay_core::pool_msg::{HarvestConfig, IbcPayout, SwapPayout};
+
+use crate::error::ContractError;
+
+pub static CONFIG_KEY: &[u8] = b"config_v106";
+pub static LAST_CLAIMED_KEY: &[u8] = b"last_claimed_v2";
+pub static PENDING_OWNER_KEY: &[u8] = b"pending_owner";
+pub static PENDING_BENEFICIARY_KEY: &[u8] = b"pending_beneficiary";
+pub static BENEFICIARY_CHANGE_KEY: &[u8] = b"beneficiary_change";
+pub static BENEFICIARY_CHANGE_DELAY_KEY: &[u8] = b"beneficiary_change_delay";
+pub static BENEFICIARY_CHANGE_DELAY_CHANGE_KEY: &[u8] = b"beneficiary_change_delay_change";
+pub static GOVERNANCE_CONFIG_KEY: &[u8] = b"governance_config";
+pub static FUND_CLOSED_KEY: &[u8] = b"fund_closed";
+pub static PAYOUT_SPLIT_KEY: &[u8] = b"payout_split";
+pub static PAYOUT_SPLIT_CHANGE_KEY: &[u8] = b"payout_split_change";
+pub static ACCRUED_KEY: &[u8] = b"accrued";
+pub static HARVEST_CONFIG_KEY: &[u8] = b"harvest_config";
+pub static EARN_LIMITS_KEY: &[u8] = b"earn_limits";
+pub static YIELD_HOOK_KEY: &[u8] = b"yield_hook";
+pub static IBC_PAYOUT_KEY: &[u8] = b"ibc_payout";
+pub static SWAP_PAYOUT_KEY: &[u8] = b"swap_payout";
+pub static STABLE_TOKEN_KEY: &[u8] = b"stable_token";
+pub static DONOR_COLLECTION_KEY: &[u8] = b"donor_collection";
+pub static ENDOWMENT_KEY: &[u8] = b"endowment";
+pub static LIQUID_BUFFER_KEY: &[u8] = b"liquid_buffer";
+pub static INSURANCE_RESERVE_KEY: &[u8] = b"insurance_reserve";
+pub static DEPOSIT_CAPS_KEY: &[u8] = b"deposit_caps";
+pub static DEPOSITS_PAUSED_KEY: &[u8] = b"deposits_paused";
+
+/// used for pools instantiated before the delay was configurable
+pub const DEFAULT_BENEFICIARY_CHANGE_DELAY: u64 = 7 * 86_400;
+/// depositors always get at least a day to leave
+pub const MIN_BENEFICIARY_CHANGE_DELAY: u64 = 86_400;
+/// most of a harvest that can go to the keeper
+pub const MAX_HARVEST_BOUNTY: Decimal = Decimal::percent(10);
+/// most slippage a beneficiary can accept on a swapped payout
+pub const MAX_SWAP_SPREAD: Decimal = Decimal::percent(10);
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct Config {
@@ -14,9 +50,8 @@ pub struct Config {
     pub owner: CanonicalAddr,
     pub beneficiary: CanonicalAddr,
     pub fee_collector: CanonicalAddr,
-    pub fee_amount: Decimal,
-    pub fee_max: Uint128,
-    pub fee_reset_every_num_blocks: u64,
+    pub fee_model: FeeModel,
+    pub fee_period: FeePeriod,
     pub money_market: CanonicalAddr,
     pub atoken: CanonicalAddr,
     pub stable_denom: String,
@@ -34,13 +69,16 @@ pub fn store(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
     singleton(storage, CONFIG_KEY).save(data)
 }
 
-pub fn read(storage: &dyn Storage) -> StdResult<Config> {
-    singleton_read(storage, CONFIG_KEY).load()
+pub fn read(storage: &dyn Storage) -> Result<Config, ContractError> {
+    singleton_read(storage, CONFIG_KEY)
+        .load()
+        .map_err(|err| missing(err, "config"))
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct LastClaimed {
-    pub last_claimed_at_block_height: u64,
+    /// when the current fee period started, seconds since epoch
+    pub period_start: u64,
     pub fees_collected: Uint128,
     pub total_earned_at_last_claimed: Uint128,
 }
@@ -49,6 +87,445 @@ pub fn last_claimed_store(storage: &mut dyn Storage, data: &LastClaimed) -> StdR
     singleton(storage, LAST_CLAIMED_KEY).save(data)
 }
 
-pub fn last_claimed_read(storage: &dyn Storage) -> StdResult<LastClaimed> {
-    singleton_read(storage, LAST_CLAIMED_KEY).load()
+pub fn last_claimed_read(storage: &dyn Storage) -> Result<LastClaimed, ContractError> {
+    singleton_read(storage, LAST_CLAIMED_KEY)
+        .load()
+        .map_err(|err| missing(err, "last_claimed"))
+}
+
+/// yield that has been charged for but not paid out yet. both are still held as aTokens
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct Accrued {
+    /// owed to the fee collector, paid by CollectFees
+    pub fees: Uint128,
+    /// owed to the beneficiary (and payout split), paid by Earn
+    pub beneficiary: Uint128,
+}
+
+impl Accrued {
+    pub fn total(&self) -> Uint128 {
+        self.fees + self.beneficiary
+    }
+}
+
+pub fn accrued_store(storage: &mut dyn Storage, data: &Accrued) -> StdResult<()> {
+    singleton(storage, ACCRUED_KEY).save(data)
+}
+
+/// pools from before fees accrued have nothing owed
+pub fn accrued_read(storage: &dyn Storage) -> StdResult<Accrued> {
+    Ok(singleton_read(storage, ACCRUED_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn harvest_config_store(storage: &mut dyn Storage, data: &HarvestConfig) -> StdResult<()> {
+    singleton(storage, HARVEST_CONFIG_KEY).save(data)
+}
+
+pub fn harvest_config_read(storage: &dyn Storage) -> StdResult<Option<HarvestConfig>> {
+    singleton_read(storage, HARVEST_CONFIG_KEY).may_load()
+}
+
+pub fn harvest_config_remove(storage: &mut dyn Storage) {
+    singleton::<HarvestConfig>(storage, HARVEST_CONFIG_KEY).remove()
+}
+
+/// keeps earn (and CollectFees) from sending dust
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct EarnLimits {
+    /// smallest payout (or fee collection) allowed. zero for any
+    pub min_earn_amount: Uint128,
+    /// blocks between payouts to the beneficiary
+    pub min_blocks_between_earn: u64,
+}
+
+pub fn earn_limits_store(storage: &mut dyn Storage, data: &EarnLimits) -> StdResult<()> {
+    singleton(storage, EARN_LIMITS_KEY).save(data)
+}
+
+/// pools from before the limits have none
+pub fn earn_limits_read(storage: &dyn Storage) -> StdResult<EarnLimits> {
+    Ok(singleton_read(storage, EARN_LIMITS_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// the part of the pool's market position it owns itself, built up out of earns
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct Endowment {
+    /// share of each earn retained. zero for a plain pool
+    pub retain_ratio: Decimal,
+    pub principal: Uint128,
+    pub last_retained: Uint128,
+    /// seconds since epoch
+    pub first_retained_at: Option<u64>,
+    pub last_retained_at: Option<u64>,
+}
+
+pub fn endowment_store(storage: &mut dyn Storage, data: &Endowment) -> StdResult<()> {
+    singleton(storage, ENDOWMENT_KEY).save(data)
+}
+
+/// pools from before endowments retain nothing
+pub fn endowment_read(storage: &dyn Storage) -> StdResult<Endowment> {
+    Ok(singleton_read(storage, ENDOWMENT_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// deposits held back from the market so redemptions can be paid straight away
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct LiquidBuffer {
+    /// share of the DP supply to hold. zero keeps everything in the market
+    pub target: Decimal,
+    /// stable the pool holds for it
+    pub held: Uint128,
+}
+
+pub fn liquid_buffer_store(storage: &mut dyn Storage, data: &LiquidBuffer) -> StdResult<()> {
+    singleton(storage, LIQUID_BUFFER_KEY).save(data)
+}
+
+/// pools from before the buffer hold nothing back
+pub fn liquid_buffer_read(storage: &dyn Storage) -> StdResult<LiquidBuffer> {
+    Ok(singleton_read(storage, LIQUID_BUFFER_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// a slice of each earn held in the pool to cover losses before they reach depositors
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct InsuranceReserve {
+    /// share of each earn taken, before the beneficiary's share
+    pub ratio: Decimal,
+    /// no slice is taken once the reserve holds this much. zero for no cap
+    pub cap: Uint128,
+    /// stable the pool holds for it, outside the market and the pool's value
+    pub held: Uint128,
+    pub total_funded: Uint128,
+    /// moved into the pool's value to make up a loss
+    pub total_used: Uint128,
+}
+
+pub fn insurance_reserve_store(
+    storage: &mut dyn Storage,
+    data: &InsuranceReserve,
+) -> StdResult<()> {
+    singleton(storage, INSURANCE_RESERVE_KEY).save(data)
+}
+
+/// pools from before the reserve take nothing
+pub fn insurance_reserve_read(storage: &dyn Storage) -> StdResult<InsuranceReserve> {
+    Ok(singleton_read(storage, INSURANCE_RESERVE_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// how much DP the pool, and each address, can hold
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, JsonSchema)]
+pub struct DepositCaps {
+    /// most DP supply. zero for no cap
+    pub tvl_cap: Uint128,
+    /// most DP one address can hold. zero for no cap
+    pub address_cap: Uint128,
+}
+
+pub fn deposit_caps_store(storage: &mut dyn Storage, data: &DepositCaps) -> StdResult<()> {
+    singleton(storage, DEPOSIT_CAPS_KEY).save(data)
+}
+
+/// pools from before the caps are uncapped
+pub fn deposit_caps_read(storage: &dyn Storage) -> StdResult<DepositCaps> {
+    Ok(singleton_read(storage, DEPOSIT_CAPS_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn deposits_paused_store(storage: &mut dyn Storage, paused: bool) -> StdResult<()> {
+    singleton(storage, DEPOSITS_PAUSED_KEY).save(&paused)
+}
+
+pub fn deposits_paused_read(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(singleton_read(storage, DEPOSITS_PAUSED_KEY)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+/// the beneficiary that asked for its payouts through the YieldReceived hook.
+/// a new beneficiary gets bank sends until it opts in itself
+pub fn yield_hook_store(storage: &mut dyn Storage, beneficiary: &CanonicalAddr) -> StdResult<()> {
+    singleton(storage, YIELD_HOOK_KEY).save(beneficiary)
+}
+
+pub fn yield_hook_read(storage: &dyn Storage) -> StdResult<Option<CanonicalAddr>> {
+    singleton_read(storage, YIELD_HOOK_KEY).may_load()
+}
+
+pub fn yield_hook_remove(storage: &mut dyn Storage) {
+    singleton::<CanonicalAddr>(storage, YIELD_HOOK_KEY).remove()
+}
+
+/// whether the current beneficiary gets its payouts through the hook
+pub fn yield_hook_enabled(storage: &dyn Storage, config: &Config) -> StdResult<bool> {
+    Ok(yield_hook_read(storage)? == Some(config.beneficiary.clone()))
+}
+
+/// like the hook, it only applies while the beneficiary that set it is the beneficiary
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcPayoutSetting {
+    pub beneficiary: CanonicalAddr,
+    pub ibc_payout: IbcPayout,
+}
+
+pub fn ibc_payout_store(storage: &mut dyn Storage, data: &IbcPayoutSetting) -> StdResult<()> {
+    singleton(storage, IBC_PAYOUT_KEY).save(data)
+}
+
+pub fn ibc_payout_remove(storage: &mut dyn Storage) {
+    singleton::<IbcPayoutSetting>(storage, IBC_PAYOUT_KEY).remove()
+}
+
+/// the current beneficiary's ibc payout, if it set one
+pub fn ibc_payout_read(storage: &dyn Storage, config: &Config) -> StdResult<Option<IbcPayout>> {
+    Ok(singleton_read::<IbcPayoutSetting>(storage, IBC_PAYOUT_KEY)
+        .may_load()?
+        .filter(|setting| setting.beneficiary == config.beneficiary)
+        .map(|setting| setting.ibc_payout))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SwapPayoutSetting {
+    pub beneficiary: CanonicalAddr,
+    pub swap_payout: SwapPayout,
+}
+
+pub fn swap_payout_store(storage: &mut dyn Storage, data: &SwapPayoutSetting) -> StdResult<()> {
+    singleton(storage, SWAP_PAYOUT_KEY).save(data)
+}
+
+pub fn swap_payout_remove(storage: &mut dyn Storage) {
+    singleton::<SwapPayoutSetting>(storage, SWAP_PAYOUT_KEY).remove()
+}
+
+/// the current beneficiary's swap, if it set one
+pub fn swap_payout_read(storage: &dyn Storage, config: &Config) -> StdResult<Option<SwapPayout>> {
+    Ok(
+        singleton_read::<SwapPayoutSetting>(storage, SWAP_PAYOUT_KEY)
+            .may_load()?
+            .filter(|setting| setting.beneficiary == config.beneficiary)
+            .map(|setting| setting.swap_payout),
+    )
+}
+
+/// set at instantiate for pools whose stable is a cw20 rather than `stable_denom`
+pub fn stable_token_store(storage: &mut dyn Storage, token: &CanonicalAddr) -> StdResult<()> {
+    singleton(storage, STABLE_TOKEN_KEY).save(token)
+}
+
+pub fn stable_token_read(storage: &dyn Storage) -> StdResult<Option<CanonicalAddr>> {
+    singleton_read(storage, STABLE_TOKEN_KEY).may_load()
+}
+
+/// the collection of `nft_contract` donors get an NFT from
+pub fn donor_collection_store(storage: &mut dyn Storage, collection_id: u64) -> StdResult<()> {
+    singleton(storage, DONOR_COLLECTION_KEY).save(&collection_id)
+}
+
+pub fn donor_collection_read(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    singleton_read(storage, DONOR_COLLECTION_KEY).may_load()
+}
+
+pub fn donor_collection_remove(storage: &mut dyn Storage) {
+    singleton::<u64>(storage, DONOR_COLLECTION_KEY).remove()
+}
+
+/// a singleton that should have been written at instantiate isn't there
+fn missing(err: StdError, item: &str) -> ContractError {
+    match err {
+        StdError::NotFound { .. } => ContractError::ConfigMissing(item.to_string()),
+        err => ContractError::Std(err),
+    }
+}
+
+/// an owner/beneficiary handover waiting on the proposed address to accept it
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingTransfer {
+    pub proposed: CanonicalAddr,
+    /// seconds since epoch
+    pub expires_at: u64,
+}
+
+pub fn pending_owner_store(storage: &mut dyn Storage, data: &PendingTransfer) -> StdResult<()> {
+    singleton(storage, PENDING_OWNER_KEY).save(data)
+}
+
+pub fn pending_owner_read(storage: &dyn Storage) -> StdResult<Option<PendingTransfer>> {
+    singleton_read(storage, PENDING_OWNER_KEY).may_load()
+}
+
+pub fn pending_owner_remove(storage: &mut dyn Storage) {
+    singleton::<PendingTransfer>(storage, PENDING_OWNER_KEY).remove()
+}
+
+pub fn pending_beneficiary_store(
+    storage: &mut dyn Storage,
+    data: &PendingTransfer,
+) -> StdResult<()> {
+    singleton(storage, PENDING_BENEFICIARY_KEY).save(data)
+}
+
+pub fn pending_beneficiary_read(storage: &dyn Storage) -> StdResult<Option<PendingTransfer>> {
+    singleton_read(storage, PENDING_BENEFICIARY_KEY).may_load()
+}
+
+pub fn pending_beneficiary_remove(storage: &mut dyn Storage) {
+    singleton::<PendingTransfer>(storage, PENDING_BENEFICIARY_KEY).remove()
+}
+
+/// an accepted beneficiary handover, waiting out the depositor exit window
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct BeneficiaryChange {
+    pub beneficiary: CanonicalAddr,
+    /// seconds since epoch
+    pub effective_at: u64,
+}
+
+pub fn beneficiary_change_store(
+    storage: &mut dyn Storage,
+    data: &BeneficiaryChange,
+) -> StdResult<()> {
+    singleton(storage, BENEFICIARY_CHANGE_KEY).save(data)
+}
+
+pub fn beneficiary_change_read(storage: &dyn Storage) -> StdResult<Option<BeneficiaryChange>> {
+    singleton_read(storage, BENEFICIARY_CHANGE_KEY).may_load()
+}
+
+pub fn beneficiary_change_remove(storage: &mut dyn Storage) {
+    singleton::<BeneficiaryChange>(storage, BENEFICIARY_CHANGE_KEY).remove()
+}
+
+pub fn beneficiary_change_delay_store(storage: &mut dyn Storage, delay: u64) -> StdResult<()> {
+    singleton(storage, BENEFICIARY_CHANGE_DELAY_KEY).save(&delay)
+}
+
+pub fn beneficiary_change_delay_read(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(singleton_read(storage, BENEFICIARY_CHANGE_DELAY_KEY)
+        .may_load()?
+        .unwrap_or(DEFAULT_BENEFICIARY_CHANGE_DELAY))
+}
+
+/// a shorter delay, waiting out the current one
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct BeneficiaryChangeDelayChange {
+    pub delay: u64,
+    /// seconds since epoch
+    pub effective_at: u64,
+}
+
+pub fn beneficiary_change_delay_change_store(
+    storage: &mut dyn Storage,
+    data: &BeneficiaryChangeDelayChange,
+) -> StdResult<()> {
+    singleton(storage, BENEFICIARY_CHANGE_DELAY_CHANGE_KEY).save(data)
+}
+
+pub fn beneficiary_change_delay_change_read(
+    storage: &dyn Storage,
+) -> StdResult<Option<BeneficiaryChangeDelayChange>> {
+    singleton_read(storage, BENEFICIARY_CHANGE_DELAY_CHANGE_KEY).may_load()
+}
+
+pub fn beneficiary_change_delay_change_remove(storage: &mut dyn Storage) {
+    singleton::<BeneficiaryChangeDelayChange>(storage, BENEFICIARY_CHANGE_DELAY_CHANGE_KEY).remove()
+}
+
+/// the delay in force at `now`, counting a reduction once it is due
+pub fn beneficiary_change_delay_at(storage: &dyn Storage, now: u64) -> StdResult<u64> {
+    match beneficiary_change_delay_change_read(storage)? {
+        Some(change) if now >= change.effective_at => Ok(change.delay),
+        _ => beneficiary_change_delay_read(storage),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct GovernanceConfig {
+    /// seconds
+    pub voting_period: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        GovernanceConfig {
+            voting_period: 7 * 86_400,
+            quorum: Decimal::percent(10),
+            threshold: Decimal::percent(50),
+        }
+    }
+}
+
+pub fn governance_config_store(
+    storage: &mut dyn Storage,
+    data: &GovernanceConfig,
+) -> StdResult<()> {
+    singleton(storage, GOVERNANCE_CONFIG_KEY).save(data)
+}
+
+pub fn governance_config_read(storage: &dyn Storage) -> StdResult<GovernanceConfig> {
+    Ok(singleton_read(storage, GOVERNANCE_CONFIG_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn fund_closed_store(storage: &mut dyn Storage, closed: bool) -> StdResult<()> {
+    singleton(storage, FUND_CLOSED_KEY).save(&closed)
+}
+
+pub fn fund_closed_read(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(singleton_read(storage, FUND_CLOSED_KEY)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+/// a slice of each payout voted to someone other than the beneficiary
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutShare {
+    pub recipient: CanonicalAddr,
+    pub share: Decimal,
+}
+
+pub fn payout_split_store(storage: &mut dyn Storage, data: &[PayoutShare]) -> StdResult<()> {
+    singleton(storage, PAYOUT_SPLIT_KEY).save(&data.to_vec())
+}
+
+pub fn payout_split_read(storage: &dyn Storage) -> StdResult<Vec<PayoutShare>> {
+    Ok(singleton_read(storage, PAYOUT_SPLIT_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// a voted payout split, waiting out the same exit window as a beneficiary change
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutSplitChange {
+    pub split: Vec<PayoutShare>,
+    /// seconds since epoch
+    pub effective_at: u64,
+}
+
+pub fn payout_split_change_store(
+    storage: &mut dyn Storage,
+    data: &PayoutSplitChange,
+) -> StdResult<()> {
+    singleton(storage, PAYOUT_SPLIT_CHANGE_KEY).save(data)
+}
+
+pub fn payout_split_change_read(storage: &dyn Storage) -> StdResult<Option<PayoutSplitChange>> {
+    singleton_read(storage, PAYOUT_SPLIT_CHANGE_KEY).may_load()
+}
+
+pub fn payout_split_change_remove(storage: &mut dyn Storage) {
+    singleton::<PayoutSplitChange>(storage, PAYOUT_SPLIT_CHANGE_KEY).remove()
 }
@@ -1,14 +1,13 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use std::str::FromStr;
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "gofundust-pool-anchor";
 /// Contract version that is used for migration.
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 use cosmwasm_std::{
-    to_binary, Addr, Binary, CanonicalAddr, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
-    ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
+    to_binary, Addr, Api, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Reply, ReplyOn,
+    Response, StdError, Storage, SubMsg, WasmMsg,
 };
 
 use cw2::{get_contract_version, set_contract_version};
@@ -17,16 +16,33 @@ use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
 
 use protobuf::Message;
 use yieldpay_core::pool_anchor_msg::{InstantiateMsg, MigrateMsg};
-use yieldpay_core::pool_msg::{ExecuteMsg, QueryMsg};
+use yieldpay_core::pool_msg::{ExecuteMsg, QueryMsg, SudoMsg};
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
 
 #[allow(unused_imports)]
 use crate::config::{last_claimed_store, read, LastClaimed};
 use crate::error::ContractError;
+use crate::handler::buffer as BufferHandler;
+use crate::handler::caps as CapsHandler;
+use crate::handler::convert as ConvertHandler;
+use crate::handler::convert::CONVERT_REPLY_ID;
 use crate::handler::core as CoreHandler;
+use crate::handler::donate as DonateHandler;
+use crate::handler::governance as GovernanceHandler;
+use crate::handler::ibc as IbcHandler;
+use crate::handler::ibc::IBC_TRANSFER_REPLY_ID;
+use crate::handler::insurance as InsuranceHandler;
+use crate::handler::matching as MatchingHandler;
+use crate::handler::ownership as OwnershipHandler;
 use crate::handler::query as QueryHandler;
-use crate::migrations::ConfigV100;
+use crate::handler::strategy as StrategyHandler;
+use crate::handler::swap as SwapHandler;
+use crate::handler::swap::SWAP_REPLY_ID;
+use crate::handler::sweep as SweepHandler;
+use crate::migrations::{ConfigV100, ConfigV104, ConfigV105, LastClaimedV1};
 use crate::response::MsgInstantiateContractResponse;
-use crate::{config, querier};
+use crate::{addr, config, querier};
 
 const INSTANTIATE_REPLY_ID: u64 = 1;
 
@@ -44,25 +60,27 @@ pub fn instantiate(
     if symbol_name.len() > 9 {
         return Err(ContractError::PoolNameTooLarge);
     }
+    if !msg.fee_model.is_valid() || !msg.fee_period.is_valid() {
+        return Err(ContractError::InvalidFeeModel);
+    }
 
     let nft_contract_addr = if let Some(contract) = msg.nft_contract {
         if deps.api.addr_validate(&contract).is_err() {
             return Err(ContractError::NftContractInvalid);
         }
 
-        Some(deps.api.addr_canonicalize(&contract)?)
+        Some(addr::canonicalize(deps.api, &contract)?)
     } else {
         None
     };
     let mut config = config::Config {
-        this: deps.api.addr_canonicalize(env.contract.address.as_str())?,
-        owner: deps.api.addr_canonicalize(info.sender.as_str())?,
-        beneficiary: deps.api.addr_canonicalize(msg.beneficiary.as_str())?,
-        fee_collector: deps.api.addr_canonicalize(msg.fee_collector.as_str())?,
-        fee_amount: Decimal::from_str(&msg.fee_amount)?,
-        fee_max: msg.fee_max,
-        fee_reset_every_num_blocks: msg.fee_reset_every_num_blocks,
-        money_market: deps.api.addr_canonicalize(msg.money_market.as_str())?,
+        this: addr::canonicalize(deps.api, env.contract.address.as_str())?,
+        owner: addr::canonicalize(deps.api, info.sender.as_str())?,
+        beneficiary: addr::canonicalize(deps.api, msg.beneficiary.as_str())?,
+        fee_collector: addr::canonicalize(deps.api, msg.fee_collector.as_str())?,
+        fee_model: msg.fee_model,
+        fee_period: msg.fee_period,
+        money_market: addr::canonicalize(deps.api, msg.money_market.as_str())?,
         stable_denom: String::default(),
         atoken: CanonicalAddr::from(vec![]),
         dp_token: CanonicalAddr::from(vec![]),
@@ -92,9 +110,23 @@ pub fn instantiate(
         })?;
 
     config::store(deps.storage, &config)?;
+    if let Some(stable_token) = msg.stable_token {
+        config::stable_token_store(deps.storage, &addr::canonicalize(deps.api, &stable_token)?)?;
+    }
+    seed_roles(deps.storage, deps.api, &config)?;
+    let beneficiary_change_delay = msg
+        .beneficiary_change_delay
+        .unwrap_or(config::DEFAULT_BENEFICIARY_CHANGE_DELAY);
+    if beneficiary_change_delay < config::MIN_BENEFICIARY_CHANGE_DELAY {
+        return Err(ContractError::BeneficiaryChangeDelayTooShort {
+            min: config::MIN_BENEFICIARY_CHANGE_DELAY,
+        });
+    }
+    config::beneficiary_change_delay_store(deps.storage, beneficiary_change_delay)?;
 
+    let now = env.block.time.seconds();
     let last_claimed = LastClaimed {
-        last_claimed_at_block_height: env.block.height,
+        period_start: config.fee_period.start_of(now, now),
         fees_collected: Default::default(),
         total_earned_at_last_claimed: Default::default(),
     };
@@ -144,13 +176,124 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => CoreHandler::receive(deps, env, info, msg),
-        ExecuteMsg::Deposit {} => CoreHandler::deposit(deps, env, info),
+        ExecuteMsg::Deposit { min_received } => CoreHandler::deposit(deps, env, info, min_received),
+        ExecuteMsg::Donate { direct } => DonateHandler::donate(deps, env, info, direct),
+        ExecuteMsg::PledgeMatch {
+            ratio,
+            cap,
+            expires_at,
+        } => MatchingHandler::pledge(deps, env, info, ratio, cap, expires_at),
+        ExecuteMsg::ReclaimMatch {} => MatchingHandler::reclaim(deps, env, info),
 
         ExecuteMsg::Earn {} => CoreHandler::earn(deps, env, info),
+        ExecuteMsg::CollectFees {} => CoreHandler::collect_fees(deps, env, info),
+        ExecuteMsg::Harvest {} => CoreHandler::harvest(deps, env, info),
+        ExecuteMsg::SetHarvestConfig { harvest } => {
+            CoreHandler::set_harvest_config(deps, env, info, harvest)
+        }
+        ExecuteMsg::SetIbcPayout { ibc_payout } => {
+            IbcHandler::set_ibc_payout(deps, env, info, ibc_payout)
+        }
+        ExecuteMsg::WhitelistPair { pair, whitelisted } => {
+            ConvertHandler::whitelist_pair(deps, env, info, pair, whitelisted)
+        }
+        ExecuteMsg::SetDepositRoute { denom, route } => {
+            ConvertHandler::set_deposit_route(deps, env, info, denom, route)
+        }
+        ExecuteMsg::SetDonorCollection { collection_id } => {
+            DonateHandler::set_donor_collection(deps, env, info, collection_id)
+        }
+        ExecuteMsg::SetSwapPayout { swap_payout } => {
+            SwapHandler::set_swap_payout(deps, env, info, swap_payout)
+        }
+        ExecuteMsg::SetYieldHook { enabled } => {
+            CoreHandler::set_yield_hook(deps, env, info, enabled)
+        }
+        ExecuteMsg::SetLiquidBuffer { target } => {
+            BufferHandler::set_liquid_buffer(deps, env, info, target)
+        }
+        ExecuteMsg::Rebalance {} => BufferHandler::rebalance(deps, env, info),
+        ExecuteMsg::SetDepositCaps {
+            tvl_cap,
+            address_cap,
+        } => CapsHandler::set_deposit_caps(deps, env, info, tvl_cap, address_cap),
+        ExecuteMsg::PauseDeposits { paused } => {
+            CapsHandler::pause_deposits(deps, env, info, paused)
+        }
+        ExecuteMsg::Sweep {
+            denom_or_cw20,
+            recipient,
+        } => SweepHandler::sweep(deps, env, info, denom_or_cw20, recipient),
+        ExecuteMsg::SetInsuranceReserve { ratio, cap } => {
+            InsuranceHandler::set_insurance_reserve(deps, env, info, ratio, cap)
+        }
+        ExecuteMsg::WhitelistMarket {
+            money_market,
+            whitelisted,
+        } => StrategyHandler::whitelist_market(deps, env, info, money_market, whitelisted),
+        ExecuteMsg::SetStrategy {
+            money_market,
+            weight,
+        } => StrategyHandler::set_strategy(deps, env, info, money_market, weight),
+        ExecuteMsg::SetRetainRatio { retain_ratio } => {
+            CoreHandler::set_retain_ratio(deps, env, info, retain_ratio)
+        }
+        ExecuteMsg::SetEarnLimits {
+            min_earn_amount,
+            min_blocks_between_earn,
+        } => {
+            CoreHandler::set_earn_limits(deps, env, info, min_earn_amount, min_blocks_between_earn)
+        }
         ExecuteMsg::Configure {
             beneficiary,
             fee_collector,
         } => CoreHandler::configure(deps, env, info, beneficiary, fee_collector),
+        ExecuteMsg::DropBeneficiaryProposal {} => {
+            OwnershipHandler::drop_beneficiary_proposal(deps, env, info)
+        }
+        ExecuteMsg::AcceptBeneficiary {} => OwnershipHandler::accept_beneficiary(deps, env, info),
+        ExecuteMsg::ApplyBeneficiaryChange {} => {
+            OwnershipHandler::apply_beneficiary_change(deps, env)
+        }
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay } => {
+            OwnershipHandler::set_beneficiary_change_delay(deps, env, info, delay)
+        }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            OwnershipHandler::propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            OwnershipHandler::drop_ownership_proposal(deps, env, info)
+        }
+        ExecuteMsg::AcceptOwnership {} => OwnershipHandler::accept_ownership(deps, env, info),
+        ExecuteMsg::Unstake { amount } => GovernanceHandler::unstake(deps, env, info, amount),
+        ExecuteMsg::Propose {
+            action,
+            description,
+        } => GovernanceHandler::propose(deps, env, info, action, description),
+        ExecuteMsg::CastVote { proposal_id, vote } => {
+            GovernanceHandler::cast_vote(deps, env, info, proposal_id, vote)
+        }
+        ExecuteMsg::ExecuteProposal { proposal_id } => {
+            GovernanceHandler::execute_proposal(deps, env, proposal_id)
+        }
+        ExecuteMsg::SetGovernanceConfig {
+            voting_period,
+            quorum,
+            threshold,
+        } => GovernanceHandler::set_governance_config(
+            deps,
+            env,
+            info,
+            voting_period,
+            quorum,
+            threshold,
+        ),
+        ExecuteMsg::GrantRole { role, address } => {
+            OwnershipHandler::grant_role(deps, env, info, role, address)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            OwnershipHandler::revoke_role(deps, env, info, role, address)
+        }
         ExecuteMsg::ConfigDetails { title, description } => {
             CoreHandler::configure_details(deps, env, info, title, description)
         }
@@ -181,26 +324,43 @@ pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractEr
     match msg.id {
         INSTANTIATE_REPLY_ID => {
             // get new token's contract address
-            let res: MsgInstantiateContractResponse = Message::parse_from_bytes(
-                msg.result.unwrap().data.unwrap().as_slice(),
-            )
-            .map_err(|_| {
-                ContractError::Std(StdError::parse_err(
-                    "MsgInstantiateContractResponse",
-                    "failed to parse data",
-                ))
-            })?;
+            let data = msg
+                .result
+                .into_result()
+                .map_err(StdError::generic_err)?
+                .data
+                .ok_or_else(|| {
+                    StdError::parse_err("MsgInstantiateContractResponse", "no reply data")
+                })?;
+            let res: MsgInstantiateContractResponse = Message::parse_from_bytes(data.as_slice())
+                .map_err(|_| {
+                    ContractError::Std(StdError::parse_err(
+                        "MsgInstantiateContractResponse",
+                        "failed to parse data",
+                    ))
+                })?;
             let token_addr = Addr::unchecked(res.get_contract_address());
 
             CoreHandler::register_dp_token(deps, env, token_addr)
         }
+        IBC_TRANSFER_REPLY_ID => IbcHandler::transfer_sent(deps, msg.result),
+        SWAP_REPLY_ID => SwapHandler::swap_replied(deps, msg.result),
+        CONVERT_REPLY_ID => ConvertHandler::converted(deps, env),
         _ => Err(ContractError::InvalidReplyId { id: msg.id }),
     }
 }
 
 #[allow(dead_code)]
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(msg) => IbcHandler::lifecycle_complete(deps, env, msg),
+    }
+}
+
+#[allow(dead_code)]
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::DepositAmountOf { owner } => QueryHandler::deposit_amount(deps, env, owner), // dp_token.balanceOf(msg.sender)
         QueryMsg::TotalDepositAmount {} => QueryHandler::total_deposit_amount(deps, env), // dp_token.totalSupply()
@@ -208,20 +368,69 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Claimable {} => QueryHandler::claimable(deps, env), // config.strategy.reward()
         QueryMsg::LastClaimed {} => QueryHandler::last_claimed(deps, env),
         QueryMsg::Fee {} => QueryHandler::fee(deps, env),
-        //QueryMsg::DebugRedeem { owner, amount } =>             QueryHandler::debug_redeem(deps, env, owner, Uin256::from(amount)),
-        /*
-        QueryMsg::DebugAnchorEpoch {} => QueryHandler::debug_anchor_epoch_state(deps, env),
-        QueryMsg::DebugATokenBalance {} => QueryHandler::debug_atoken_balance(deps, env),
-        QueryMsg::DebugDPTotalSupply {} => QueryHandler::debug_dp_total_supply(deps, env),
-        QueryMsg::DebugPoolValueLocked {} => QueryHandler::debug_pool_value_locked(deps, env),
-        QueryMsg::DebugEarnable {} => QueryHandler::debug_earnable(deps, env),
-           */
+        QueryMsg::PendingTransfers {} => QueryHandler::pending_transfers(deps, env),
+        QueryMsg::BeneficiaryChange {} => QueryHandler::beneficiary_change(deps, env),
+        QueryMsg::GovernanceConfig {} => QueryHandler::governance_config(deps, env),
+        QueryMsg::HarvestConfig {} => QueryHandler::harvest_config(deps, env),
+        QueryMsg::Endowment {} => QueryHandler::endowment(deps, env),
+        QueryMsg::LiquidBuffer {} => QueryHandler::liquid_buffer(deps, env),
+        QueryMsg::Strategies {} => QueryHandler::strategies(deps, env),
+        QueryMsg::WhitelistedMarkets { start_after, limit } => {
+            QueryHandler::whitelisted_markets(deps, env, start_after, limit)
+        }
+        QueryMsg::InsuranceReserve {} => QueryHandler::insurance_reserve(deps, env),
+        QueryMsg::DepositCapacity { address } => QueryHandler::deposit_capacity(deps, env, address),
+        QueryMsg::YieldHook {} => QueryHandler::yield_hook(deps, env),
+        QueryMsg::IbcPayout {} => QueryHandler::ibc_payout(deps, env),
+        QueryMsg::SwapPayout {} => QueryHandler::swap_payout(deps, env),
+        QueryMsg::DepositRoutes { start_after, limit } => {
+            QueryHandler::deposit_routes(deps, env, start_after, limit)
+        }
+        QueryMsg::WhitelistedPairs { start_after, limit } => {
+            QueryHandler::whitelisted_pairs(deps, env, start_after, limit)
+        }
+        QueryMsg::Matches { start_after, limit } => {
+            QueryHandler::matches(deps, env, start_after, limit)
+        }
+        QueryMsg::Donor { address } => QueryHandler::donor(deps, env, address),
+        QueryMsg::Donors { start_after, limit } => {
+            QueryHandler::donors(deps, env, start_after, limit)
+        }
+        QueryMsg::IbcTransfers { start_after, limit } => {
+            QueryHandler::ibc_transfers(deps, env, start_after, limit)
+        }
+        QueryMsg::Proposal { proposal_id } => QueryHandler::proposal(deps, env, proposal_id),
+        QueryMsg::Proposals { start_after, limit } => {
+            QueryHandler::proposals(deps, env, start_after, limit)
+        }
+        QueryMsg::Vote { proposal_id, voter } => QueryHandler::vote(deps, env, proposal_id, voter),
+        QueryMsg::Staked { address } => QueryHandler::staked(deps, env, address),
+        QueryMsg::PayoutSplit {} => QueryHandler::payout_split(deps, env),
+        QueryMsg::Roles { address } => QueryHandler::roles(deps, env, address),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => QueryHandler::role_members(deps, env, role, start_after, limit),
+        QueryMsg::EarnHistory { start_after, limit } => {
+            QueryHandler::earn_history(deps, env, start_after, limit)
+        }
+        QueryMsg::FeeHistory { start_after, limit } => {
+            QueryHandler::fee_history(deps, env, start_after, limit)
+        } //QueryMsg::DebugRedeem { owner, amount } =>             QueryHandler::debug_redeem(deps, env, owner, Uin256::from(amount)),
+          /*
+          QueryMsg::DebugAnchorEpoch {} => QueryHandler::debug_anchor_epoch_state(deps, env),
+          QueryMsg::DebugATokenBalance {} => QueryHandler::debug_atoken_balance(deps, env),
+          QueryMsg::DebugDPTotalSupply {} => QueryHandler::debug_dp_total_supply(deps, env),
+          QueryMsg::DebugPoolValueLocked {} => QueryHandler::debug_pool_value_locked(deps, env),
+          QueryMsg::DebugEarnable {} => QueryHandler::debug_earnable(deps, env),
+             */
     }
 }
 
 #[allow(dead_code)]
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let contract_version = get_contract_version(deps.storage)?;
 
     match contract_version.contract.as_ref() {
@@ -242,6 +451,34 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         }
     }
 
+    // pools from before the fee model
+    if let Some(config_v104) = ConfigV104::may_load(deps.storage)? {
+        config::store(deps.storage, &config_v104.migrate_from())?;
+        ConfigV104::remove(deps.storage);
+    }
+    // and from before fee periods were in seconds
+    if let Some(config_v105) = ConfigV105::may_load(deps.storage)? {
+        config::store(deps.storage, &config_v105.migrate_from())?;
+        ConfigV105::remove(deps.storage);
+    }
+    if let Some(last_claimed_v1) = LastClaimedV1::may_load(deps.storage)? {
+        last_claimed_store(
+            deps.storage,
+            &last_claimed_v1.migrate_from(env.block.height, env.block.time.seconds()),
+        )?;
+        LastClaimedV1::remove(deps.storage);
+    }
+
+    // pools from before roles existed
+    let config = config::read(deps.storage)?;
+    if !roles::has_role(
+        deps.storage,
+        Role::Owner,
+        &addr::humanize(deps.api, &config.owner)?,
+    ) {
+        seed_roles(deps.storage, deps.api, &config)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     Ok(Response::new()
@@ -250,3 +487,23 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         .add_attribute("new_contract_name", CONTRACT_NAME)
         .add_attribute("new_contract_version", CONTRACT_VERSION))
 }
+
+/// owner, beneficiary and fee collector come from the config
+fn seed_roles(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    config: &config::Config,
+) -> Result<(), ContractError> {
+    roles::set_holder(storage, Role::Owner, &addr::humanize(api, &config.owner)?)?;
+    roles::set_holder(
+        storage,
+        Role::Beneficiary,
+        &addr::humanize(api, &config.beneficiary)?,
+    )?;
+    roles::set_holder(
+        storage,
+        Role::FeeCollector,
+        &addr::humanize(api, &config.fee_collector)?,
+    )?;
+    Ok(())
+}
@@ -1,5 +1,8 @@
-use cosmwasm_std::{DecimalRangeExceeded, OverflowError, StdError};
+use cosmwasm_std::{
+    Decimal, DecimalRangeExceeded, DivideByZeroError, OverflowError, StdError, Uint128,
+};
 use thiserror::Error;
+use yieldpay_core::roles::RolesError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
@@ -10,6 +13,8 @@ pub enum ContractError {
     Overflow(#[from] OverflowError),
     #[error("DecimalRangeExceeded:{0}")]
     DecimalRangeExceeded(#[from] DecimalRangeExceeded),
+    #[error("DivideByZero:{0}")]
+    DivideByZero(#[from] DivideByZeroError),
 
     #[error(
         "Core/Pool: Unauthorized (action: {action:?}, expected: {expected:?}, actual: {actual:?})"
@@ -19,11 +24,84 @@ pub enum ContractError {
         expected: String,
         actual: String,
     },
+    #[error("Core/Pool: {0} can't be granted or revoked directly")]
+    RoleNotGrantable(String),
     #[error(transparent)]
     ParseIntError(#[from] ::std::num::ParseIntError),
     #[error("Core/Pool: Invalid reply ID (ID: {id:?}")]
     InvalidReplyId { id: u64 },
 
+    #[error("Core/Pool: invalid address {0:?}")]
+    InvalidAddress(String),
+    #[error("Core/Pool: {0} has not been stored")]
+    ConfigMissing(String),
+    #[error("Core/Pool: DP token has not been registered yet")]
+    DpTokenNotRegistered,
+    #[error("Core/Pool: no fees have accrued")]
+    NoFeesAccrued,
+    #[error("Core/Pool: permissionless harvesting is off for this fund")]
+    HarvestDisabled,
+    #[error("Core/Pool: harvest not due until {due_at}, or enough has been earned")]
+    HarvestNotDue { due_at: u64 },
+    #[error("Core/Pool: nothing to harvest")]
+    NothingToHarvest,
+    #[error("Core/Pool: nothing to earn")]
+    NothingToEarn,
+    #[error("Core/Pool: {amount} is below the minimum of {minimum}")]
+    EarnBelowMinimum { amount: Uint128, minimum: Uint128 },
+    #[error("Core/Pool: can't earn again until block {next_earn_allowed_at}")]
+    EarnTooSoon { next_earn_allowed_at: u64 },
+    #[error("Core/Pool: an ibc payout needs a channel, remote address and timeout")]
+    InvalidIbcPayout {},
+    #[error("Core/Pool: ibc payouts need a native stable")]
+    IbcPayoutNeedsNativeStable {},
+    #[error("Core/Pool: the ibc transfer didn't return a sequence")]
+    IbcTransferSequenceMissing {},
+    #[error("Core/Pool: swap payouts can't allow a spread of more than {max}")]
+    InvalidSwapPayout { max: Decimal },
+    #[error("Core/Pool: swap payouts need a belief price")]
+    SwapPayoutNeedsBeliefPrice {},
+    #[error("Core/Pool: deposit routes can't allow a spread of more than {max}")]
+    InvalidDepositRoute { max: Decimal },
+    #[error("Core/Pool: deposit came to {received}, less than the {minimum} asked for")]
+    DepositBelowMinimum { received: Uint128, minimum: Uint128 },
+    #[error("Core/Pool: a swapped deposit needs a min_received")]
+    MinReceivedRequired {},
+    #[error("Core/Pool: a match needs a ratio above zero and an expiry in the future")]
+    InvalidMatch {},
+    #[error("Core/Pool: no match pledged by {0}")]
+    NoMatch(String),
+    #[error("Core/Pool: match can't be reclaimed until {expires_at}")]
+    MatchNotExpired { expires_at: u64 },
+    #[error("Core/Pool: liquid buffer target can be at most 1")]
+    InvalidBufferTarget,
+    #[error("Core/Pool: liquid buffer and strategies are already at their targets")]
+    NothingToRebalance,
+    #[error("Core/Pool: strategy weights can add up to at most 1")]
+    InvalidStrategyWeights,
+    #[error("Core/Pool: {0} is already the pool's money market")]
+    InvalidStrategy(String),
+    #[error("Core/Pool: {0} hasn't been whitelisted by the owner")]
+    MarketNotWhitelisted(String),
+    #[error("Core/Pool: pair {0} hasn't been whitelisted by the owner")]
+    PairNotWhitelisted(String),
+    #[error("Core/Pool: a pool can have at most {max} strategies")]
+    TooManyStrategies { max: usize },
+    #[error("Core/Pool: retain ratio can be at most 1")]
+    InvalidRetainRatio,
+    #[error("Core/Pool: insurance ratio can be at most 1")]
+    InvalidInsuranceRatio,
+    #[error("Core/Pool: {0} holds depositors' funds and can't be swept")]
+    CannotSweep(String),
+    #[error("Core/Pool: nothing to sweep")]
+    NothingToSweep,
+    #[error("Core/Pool: the pool is capped at {cap}, only {remaining} more can be deposited")]
+    TvlCapReached { cap: Uint128, remaining: Uint128 },
+    #[error("Core/Pool: deposits are capped at {cap} per address, only {remaining} more can be deposited")]
+    AddressCapReached { cap: Uint128, remaining: Uint128 },
+    #[error("Core/Pool: harvest bounty can be at most {max}")]
+    InvalidHarvestConfig { max: Decimal },
+
     #[error("Core/Pool: Zero amount not allowed")]
     NotAllowZeroAmount {},
 
@@ -46,9 +124,63 @@ pub enum ContractError {
     NftContractInvalid,
     #[error("Core/Pool: NFT attempted to set collection id {0} to an invalid option {1}")]
     NftCollectionInvalidOption(u64, u64),
+    #[error("Core/Pool: no pending {0} transfer")]
+    NoPendingTransfer(String),
+    #[error("Core/Pool: {0} transfer proposal has expired")]
+    TransferExpired(String),
+    #[error("Core/Pool: beneficiary change is not due until {effective_at}")]
+    BeneficiaryChangeNotDue { effective_at: u64 },
+    #[error("Core/Pool: beneficiary change delay must be at least {min} seconds")]
+    BeneficiaryChangeDelayTooShort { min: u64 },
+    #[error("Core/Pool: transfer expiry must be between 1 and {max} seconds")]
+    TransferExpiryInvalid { max: u64 },
+    #[error("Core/Pool: fund is closed to new deposits")]
+    FundClosed,
+    #[error("Core/Pool: deposits are paused")]
+    DepositsPaused,
+    #[error("Core/Pool: no DP staked at the proposal height")]
+    NoVotingPower,
+    #[error("Core/Pool: can't unstake {requested}, only {staked} staked")]
+    InsufficientStake { staked: Uint128, requested: Uint128 },
+    #[error("Core/Pool: proposal {0} not found")]
+    ProposalNotFound(u64),
+    #[error("Core/Pool: proposal {0} is no longer open for voting")]
+    VotingClosed(u64),
+    #[error("Core/Pool: proposal {0} is still open for voting")]
+    VotingOpen(u64),
+    #[error("Core/Pool: already voted on proposal {0}")]
+    AlreadyVoted(u64),
+    #[error("Core/Pool: proposal {0} did not pass")]
+    ProposalNotPassed(u64),
+    #[error("Core/Pool: proposal {0} has already been executed")]
+    ProposalExecuted(u64),
+    #[error("Core/Pool: payout shares must add up to at most 1")]
+    InvalidPayoutSplit,
+    #[error("Core/Pool: quorum and threshold must be at most 1, voting period above 0")]
+    GovernanceConfigInvalid,
+    #[error("Core/Pool: fee rates must be at most 1, with tiers in increasing threshold order and a non zero period")]
+    InvalidFeeModel,
     #[error("Core/Pool: Contract can't be migrated! {current_name:?} {current_version:?}")]
     MigrationError {
         current_name: String,
         current_version: String,
     },
 }
+
+impl From<RolesError> for ContractError {
+    fn from(err: RolesError) -> Self {
+        match err {
+            RolesError::Std(err) => ContractError::Std(err),
+            RolesError::Unauthorized {
+                action,
+                expected,
+                actual,
+            } => ContractError::Unauthorized {
+                action,
+                expected,
+                actual,
+            },
+            RolesError::NotGrantable(role) => ContractError::RoleNotGrantable(role),
+        }
+    }
+}
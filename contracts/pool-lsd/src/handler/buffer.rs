@@ -0,0 +1,154 @@
+use cosmwasm_std::*;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+use yieldpay_core::token;
+
+use crate::addr;
+use crate::config;
+use crate::config::{liquid_buffer_read, liquid_buffer_store, LiquidBuffer};
+use crate::error::ContractError;
+use crate::handler::core::{config_editors, redemption};
+use crate::handler::insurance;
+use crate::handler::strategy;
+use crate::querier::anchor;
+use crate::stable;
+
+pub fn set_liquid_buffer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    target: Decimal,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_liquid_buffer",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary, Role::FeeCollector]),
+    )?;
+    if target > Decimal::one() {
+        return Err(ContractError::InvalidBufferTarget);
+    }
+
+    let mut buffer = liquid_buffer_read(deps.storage)?;
+    buffer.target = target;
+    liquid_buffer_store(deps.storage, &buffer)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_liquid_buffer")
+        .add_attribute("target", target.to_string()))
+}
+
+/// what the buffer should hold with `supply` of DP out
+pub fn target_amount(buffer: &LiquidBuffer, supply: Uint128) -> Uint128 {
+    supply * buffer.target
+}
+
+pub fn dp_supply(deps: Deps, config: &config::Config) -> Result<Uint128, ContractError> {
+    Ok(token::total_supply(
+        deps,
+        addr::dp_token(deps.api, config)?.to_string(),
+    )?)
+}
+
+/// how much of a deposit of `received` to hold back, adding it to the buffer
+pub fn keep(
+    deps: Deps,
+    config: &config::Config,
+    buffer: &mut LiquidBuffer,
+    received: Uint128,
+) -> Result<Uint128, ContractError> {
+    if buffer.target.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let target = target_amount(buffer, dp_supply(deps, config)? + received);
+    let kept = target.saturating_sub(buffer.held).min(received);
+    buffer.held += kept;
+    Ok(kept)
+}
+
+/// what the buffer is short once `redeemed` of DP is gone
+pub fn shortfall(
+    deps: Deps,
+    config: &config::Config,
+    buffer: &LiquidBuffer,
+    redeemed: Uint128,
+) -> Result<Uint128, ContractError> {
+    if buffer.target.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let supply = dp_supply(deps, config)?.saturating_sub(redeemed);
+    Ok(target_amount(buffer, supply).saturating_sub(buffer.held))
+}
+
+/// anyone can move the buffer and the strategies back to their targets. what is over its target
+/// is redeemed first, then goes to the buffer and whatever is under its weight
+pub fn rebalance(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    // making up a loss out of the insurance reserve comes first
+    let insurance_used = insurance::cover(deps.branch(), &env, &config)?;
+    let mut buffer = liquid_buffer_read(deps.storage)?;
+    let target = target_amount(&buffer, dp_supply(deps.as_ref(), &config)?);
+    let positions = strategy::positions(deps.as_ref(), &env, &config)?;
+    let invested = positions
+        .iter()
+        .fold(Uint128::zero(), |total, position| total + position.value());
+    // what should be in the markets once the buffer is at its target
+    let to_invest = (invested + buffer.held).saturating_sub(target);
+
+    let mut msgs = vec![];
+    let mut freed = buffer.held.saturating_sub(target);
+    buffer.held = buffer.held.min(target);
+    let mut wanted = vec![];
+    for position in &positions {
+        let goal = to_invest * position.weight;
+        let value = position.value();
+        if value > goal && !position.exchange_rate.is_zero() {
+            let (atokens, redeemed) = redemption(value - goal, position.exchange_rate)?;
+            if !atokens.is_zero() {
+                msgs.extend(anchor::redeem_stable_msg(
+                    deps.as_ref(),
+                    &position.money_market,
+                    &position.atoken,
+                    atokens,
+                )?);
+                freed += redeemed;
+            }
+        }
+        wanted.push(goal.saturating_sub(value));
+    }
+
+    let refill = (target - buffer.held).min(freed);
+    buffer.held += refill;
+    freed -= refill;
+    let stable = stable::read(deps.as_ref(), &config)?;
+    let mut deposited = Uint128::zero();
+    for (position, wanted) in positions.iter().zip(wanted) {
+        let amount = wanted.min(freed - deposited);
+        if !amount.is_zero() {
+            msgs.extend(anchor::deposit_stable_msg(
+                deps.as_ref(),
+                &position.money_market,
+                &stable,
+                amount,
+            )?);
+            deposited += amount;
+        }
+    }
+    if msgs.is_empty() && insurance_used.is_zero() {
+        return Err(ContractError::NothingToRebalance);
+    }
+    // anything the redemptions rounded past what was wanted stays liquid
+    buffer.held += freed - deposited;
+    liquid_buffer_store(deps.storage, &buffer)?;
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "rebalance")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("deposited", deposited.to_string())
+        .add_attribute("insurance_used", insurance_used.to_string())
+        .add_attribute("held", buffer.held.to_string()))
+}
@@ -0,0 +1,124 @@
+use cosmwasm_std::*;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+use yieldpay_core::token;
+
+use crate::addr;
+use crate::config;
+use crate::config::{
+    deposit_caps_read, deposit_caps_store, deposits_paused_read, deposits_paused_store, DepositCaps,
+};
+use crate::error::ContractError;
+use crate::handler::buffer::dp_supply;
+use crate::state::STAKED;
+
+pub fn set_deposit_caps(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    tvl_cap: Uint128,
+    address_cap: Uint128,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "set_deposit_caps",
+        &info.sender,
+        &[Role::Owner, Role::Admin],
+    )?;
+    deposit_caps_store(
+        deps.storage,
+        &DepositCaps {
+            tvl_cap,
+            address_cap,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set_deposit_caps")
+        .add_attribute("tvl_cap", tvl_cap.to_string())
+        .add_attribute("address_cap", address_cap.to_string()))
+}
+
+/// a guardian can stop deposits in an emergency, but only the owner or an admin can let them
+/// in again
+pub fn pause_deposits(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let editors: &[Role] = if paused {
+        &[Role::Owner, Role::Admin, Role::Guardian]
+    } else {
+        &[Role::Owner, Role::Admin]
+    };
+    roles::ensure_any(deps.storage, "pause_deposits", &info.sender, editors)?;
+    deposits_paused_store(deps.storage, paused)?;
+    Ok(Response::new()
+        .add_attribute("action", "pause_deposits")
+        .add_attribute("paused", paused.to_string()))
+}
+
+/// fails if a guardian (or the owner/an admin) has paused deposits
+pub fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if deposits_paused_read(storage)? {
+        return Err(ContractError::DepositsPaused);
+    }
+    Ok(())
+}
+
+/// None if the pool isn't capped
+pub fn tvl_remaining(caps: &DepositCaps, supply: Uint128) -> Option<Uint128> {
+    (!caps.tvl_cap.is_zero()).then(|| caps.tvl_cap.saturating_sub(supply))
+}
+
+/// None if addresses aren't capped. DP staked for governance still counts as held
+pub fn address_remaining(
+    deps: Deps,
+    config: &config::Config,
+    caps: &DepositCaps,
+    address: &Addr,
+) -> Result<Option<Uint128>, ContractError> {
+    if caps.address_cap.is_zero() {
+        return Ok(None);
+    }
+    let balance = token::balance_of(
+        deps,
+        addr::dp_token(deps.api, config)?.to_string(),
+        address.to_string(),
+    )?;
+    let staked = STAKED.may_load(deps.storage, address)?.unwrap_or_default();
+    Ok(Some(caps.address_cap.saturating_sub(balance + staked)))
+}
+
+/// fails with what can still be deposited if `received` would take the pool or any of
+/// `depositors` (whoever gets the DP, and whoever paid for it) over their cap
+pub fn ensure_capacity(
+    deps: Deps,
+    config: &config::Config,
+    depositors: &[&Addr],
+    received: Uint128,
+) -> Result<(), ContractError> {
+    let caps = deposit_caps_read(deps.storage)?;
+    if caps.tvl_cap.is_zero() && caps.address_cap.is_zero() {
+        return Ok(());
+    }
+    if let Some(remaining) = tvl_remaining(&caps, dp_supply(deps, config)?) {
+        if received > remaining {
+            return Err(ContractError::TvlCapReached {
+                cap: caps.tvl_cap,
+                remaining,
+            });
+        }
+    }
+    for depositor in depositors {
+        if let Some(remaining) = address_remaining(deps, config, &caps, depositor)? {
+            if received > remaining {
+                return Err(ContractError::AddressCapReached {
+                    cap: caps.address_cap,
+                    remaining,
+                });
+            }
+        }
+    }
+    Ok(())
+}
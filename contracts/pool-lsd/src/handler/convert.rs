@@ -0,0 +1,174 @@
+use cosmwasm_std::*;
+use yieldpay_core::dex::{Asset, AssetInfo, PairExecuteMsg};
+use yieldpay_core::pool_msg::DepositRoute;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::addr;
+use crate::config;
+use crate::config::{fund_closed_read, MAX_SWAP_SPREAD};
+use crate::error::ContractError;
+use crate::handler::caps;
+use crate::handler::core::{config_editors, deposit_stable};
+use crate::stable;
+use crate::state::{PendingConversion, DEPOSIT_ROUTES, PAIR_WHITELIST, PENDING_CONVERSION};
+
+pub const CONVERT_REPLY_ID: u64 = 4;
+
+pub fn set_deposit_route(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    route: Option<DepositRoute>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_deposit_route",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
+    )?;
+
+    let response = Response::new()
+        .add_attribute("action", "set_deposit_route")
+        .add_attribute("denom", denom.clone());
+    match route {
+        Some(route) => {
+            if route.max_spread > MAX_SWAP_SPREAD {
+                return Err(ContractError::InvalidDepositRoute {
+                    max: MAX_SWAP_SPREAD,
+                });
+            }
+            // the stable is deposited as is
+            if denom.is_empty() || denom == config.stable_denom {
+                return Err(ContractError::NotAllowOtherDenoms { denom });
+            }
+            deps.api.addr_validate(&route.pair)?;
+            if !PAIR_WHITELIST.has(deps.storage, &route.pair) {
+                return Err(ContractError::PairNotWhitelisted(route.pair));
+            }
+            let response = response
+                .add_attribute("pair", route.pair.clone())
+                .add_attribute("max_spread", route.max_spread.to_string());
+            DEPOSIT_ROUTES.save(deps.storage, &denom, &route)?;
+            Ok(response)
+        }
+        None => {
+            DEPOSIT_ROUTES.remove(deps.storage, &denom);
+            Ok(response.add_attribute("deposit_route", "off"))
+        }
+    }
+}
+
+/// owner/admin only. the pairs deposit routes can swap through. taking a pair off drops the
+/// routes using it
+pub fn whitelist_pair(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    pair: String,
+    whitelisted: bool,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "whitelist_pair",
+        &info.sender,
+        &[Role::Owner, Role::Admin],
+    )?;
+    let pair = addr::validate(deps.api, &pair)?;
+
+    if whitelisted {
+        PAIR_WHITELIST.save(deps.storage, pair.as_str(), &Empty {})?;
+    } else {
+        PAIR_WHITELIST.remove(deps.storage, pair.as_str());
+        let routes = DEPOSIT_ROUTES
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (denom, route) in routes {
+            if route.pair == pair.as_str() {
+                DEPOSIT_ROUTES.remove(deps.storage, &denom);
+            }
+        }
+    }
+    Ok(Response::new()
+        .add_attribute("action", "whitelist_pair")
+        .add_attribute("pair", pair)
+        .add_attribute("whitelisted", whitelisted.to_string()))
+}
+
+/// swaps a whitelisted deposit into the stable. the depositor's DP is minted in the reply,
+/// on what the swap actually returned
+pub fn deposit(
+    deps: DepsMut,
+    env: Env,
+    depositor: Addr,
+    offer: Coin,
+    route: DepositRoute,
+    min_received: Uint128,
+) -> Result<Response, ContractError> {
+    if fund_closed_read(deps.storage)? {
+        return Err(ContractError::FundClosed);
+    }
+    caps::ensure_not_paused(deps.storage)?;
+    if offer.amount.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+    let config = config::read(deps.storage)?;
+    let stable = stable::read(deps.as_ref(), &config)?;
+    let balance_before = stable::balance(&deps.querier, &stable, &env.contract.address)?;
+    PENDING_CONVERSION.save(
+        deps.storage,
+        &PendingConversion {
+            depositor: depositor.clone(),
+            denom: offer.denom.clone(),
+            offer_amount: offer.amount,
+            min_received,
+            balance_before,
+        },
+    )?;
+
+    let swap = WasmMsg::Execute {
+        contract_addr: route.pair.clone(),
+        msg: to_binary(&PairExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: AssetInfo::NativeToken {
+                    denom: offer.denom.clone(),
+                },
+                amount: offer.amount,
+            },
+            belief_price: None,
+            max_spread: Some(route.max_spread),
+            to: None,
+        })?,
+        funds: vec![offer.clone()],
+    };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(swap, CONVERT_REPLY_ID))
+        .add_attribute("action", "deposit_convert")
+        .add_attribute("sender", depositor.to_string())
+        .add_attribute("offer_denom", offer.denom)
+        .add_attribute("offer_amount", offer.amount.to_string())
+        .add_attribute("pair", route.pair))
+}
+
+/// deposits what the swap brought in, unless that's below the depositor's minimum
+pub fn converted(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_CONVERSION.load(deps.storage)?;
+    PENDING_CONVERSION.remove(deps.storage);
+    let config = config::read(deps.storage)?;
+    let stable = stable::read(deps.as_ref(), &config)?;
+    let received = stable::balance(&deps.querier, &stable, &env.contract.address)?
+        .checked_sub(pending.balance_before)?;
+    if received < pending.min_received {
+        return Err(ContractError::DepositBelowMinimum {
+            received,
+            minimum: pending.min_received,
+        });
+    }
+    let response = deposit_stable(deps, env, pending.depositor, received)?;
+    Ok(response
+        .add_attribute("offer_denom", pending.denom)
+        .add_attribute("offer_amount", pending.offer_amount.to_string())
+        .add_attribute("converted_amount", received.to_string()))
+}
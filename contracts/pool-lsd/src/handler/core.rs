@@ -1,20 +1,47 @@
 use cosmwasm_std::*;
 use cw20::BalanceResponse as Cw20BalanceResponse;
 use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use yieldpay_core::fee::{FeeModel, FeePeriod};
 use yieldpay_core::pool_msg::{
-    Cw20HookMsg, NftCallback, NFT_REPLY_COLLECTION_ACTIVE, NFT_REPLY_COLLECTION_REDEEMED,
+    Cw20HookMsg, HarvestConfig, NftCallback, NFT_REPLY_COLLECTION_ACTIVE,
+    NFT_REPLY_COLLECTION_REDEEMED,
 };
 
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
 use yieldpay_core::token;
+use yieldpay_core::yield_receiver::{YieldPeriod, YieldReceiverMsg};
 
-use std::ops::{Div, Mul, Sub};
-use std::str::FromStr;
+use std::ops::Mul;
 
+use crate::addr;
 use crate::config;
-use crate::config::{last_claimed_read, last_claimed_store, LastClaimed};
+use crate::config::{
+    accrued_read, accrued_store, earn_limits_read, earn_limits_store, endowment_read,
+    endowment_store, fund_closed_read, harvest_config_read, harvest_config_remove,
+    harvest_config_store, ibc_payout_read, insurance_reserve_read, insurance_reserve_store,
+    last_claimed_read, last_claimed_store, liquid_buffer_read, liquid_buffer_store,
+    payout_split_read, stable_token_read, swap_payout_read, yield_hook_enabled, yield_hook_remove,
+    yield_hook_store, Accrued, EarnLimits, LastClaimed, MAX_HARVEST_BOUNTY,
+};
 use crate::error::ContractError;
-use crate::querier::anchor;
+use crate::handler::buffer;
+use crate::handler::caps;
+use crate::handler::convert;
+use crate::handler::donate;
+use crate::handler::governance;
+use crate::handler::ibc;
+use crate::handler::insurance;
+use crate::handler::matching;
+use crate::handler::ownership::{propose_new_beneficiary, settle_beneficiary_change};
+use crate::handler::strategy;
+use crate::handler::strategy::Position;
+use crate::handler::swap;
 use crate::querier::nft::{nft_exists, quick_mint_msg, switch_collection_msg};
+use crate::stable;
+use crate::state::{
+    EarnRecord, FeeRecord, Payout, DEPOSIT_ROUTES, EARN_COUNT, EARN_HISTORY, FEE_COUNT, FEE_HISTORY,
+};
 
 pub fn receive(
     deps: DepsMut,
@@ -25,29 +52,136 @@ pub fn receive(
     match from_binary(&cw20_msg.msg) {
         Ok(Cw20HookMsg::Redeem {}) => {
             // only asset contract can execute this message
-            let config: config::Config = config::read(deps.storage).unwrap();
-            if deps.api.addr_canonicalize(info.sender.as_str()).unwrap() != config.dp_token {
+            let config: config::Config = config::read(deps.storage)?;
+            let dp_token = addr::dp_token(deps.api, &config)?;
+            if info.sender != dp_token {
                 return Err(ContractError::Unauthorized {
                     action: "receive".to_string(),
-                    expected: deps
-                        .api
-                        .addr_humanize(&config.dp_token)
-                        .unwrap()
-                        .to_string(),
+                    expected: dp_token.to_string(),
                     actual: info.sender.to_string(),
                 });
             }
 
             redeem(deps, env, info, cw20_msg.sender, cw20_msg.amount)
         }
+        Ok(Cw20HookMsg::Deposit { recipient }) => {
+            // only the pool's own stable
+            let config: config::Config = config::read(deps.storage)?;
+            let stable_token = match stable_token_read(deps.storage)? {
+                Some(token) => addr::humanize(deps.api, &token)?,
+                None => {
+                    return Err(ContractError::NotAllowOtherDenoms {
+                        denom: config.stable_denom,
+                    })
+                }
+            };
+            if info.sender != stable_token {
+                return Err(ContractError::Unauthorized {
+                    action: "deposit".to_string(),
+                    expected: stable_token.to_string(),
+                    actual: info.sender.to_string(),
+                });
+            }
+
+            // depositing for someone else doesn't get the sender past their own cap
+            let sender = addr::validate(deps.api, &cw20_msg.sender)?;
+            let depositor = match recipient {
+                Some(recipient) => addr::validate(deps.api, &recipient)?,
+                None => sender.clone(),
+            };
+            if depositor != sender {
+                caps::ensure_capacity(deps.as_ref(), &config, &[&sender], cw20_msg.amount)?;
+            }
+            deposit_stable(deps, env, depositor, cw20_msg.amount)
+        }
+        Ok(Cw20HookMsg::Donate { direct }) => {
+            // only the pool's own stable
+            let stable_token = match stable_token_read(deps.storage)? {
+                Some(token) => addr::humanize(deps.api, &token)?,
+                None => {
+                    return Err(ContractError::NotAllowOtherDenoms {
+                        denom: config::read(deps.storage)?.stable_denom,
+                    })
+                }
+            };
+            if info.sender != stable_token {
+                return Err(ContractError::Unauthorized {
+                    action: "donate".to_string(),
+                    expected: stable_token.to_string(),
+                    actual: info.sender.to_string(),
+                });
+            }
+
+            let donor = addr::validate(deps.api, &cw20_msg.sender)?;
+            donate::donate_stable(deps, env, donor, cw20_msg.amount, direct)
+        }
+        Ok(Cw20HookMsg::PledgeMatch {
+            ratio,
+            cap,
+            expires_at,
+        }) => {
+            // only the pool's own stable
+            let stable_token = match stable_token_read(deps.storage)? {
+                Some(token) => addr::humanize(deps.api, &token)?,
+                None => {
+                    return Err(ContractError::NotAllowOtherDenoms {
+                        denom: config::read(deps.storage)?.stable_denom,
+                    })
+                }
+            };
+            if info.sender != stable_token {
+                return Err(ContractError::Unauthorized {
+                    action: "pledge_match".to_string(),
+                    expected: stable_token.to_string(),
+                    actual: info.sender.to_string(),
+                });
+            }
+
+            let sponsor = addr::validate(deps.api, &cw20_msg.sender)?;
+            matching::pledge_stable(deps, env, sponsor, cw20_msg.amount, ratio, cap, expires_at)
+        }
+        Ok(Cw20HookMsg::Stake {}) => {
+            // only DP can be staked
+            let config: config::Config = config::read(deps.storage)?;
+            let dp_token = addr::dp_token(deps.api, &config)?;
+            if info.sender != dp_token {
+                return Err(ContractError::Unauthorized {
+                    action: "stake".to_string(),
+                    expected: dp_token.to_string(),
+                    actual: info.sender.to_string(),
+                });
+            }
+
+            governance::stake(deps, env, cw20_msg.sender, cw20_msg.amount)
+        }
         _ => Err(ContractError::NotAllowOtherCw20ReceiveAction {
             action: "redeem".to_string(),
         }),
     }
 }
 
-pub fn deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage).unwrap();
+pub fn deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_received: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    if let [offer] = info.funds.as_slice() {
+        if let Some(route) = DEPOSIT_ROUTES.may_load(deps.storage, &offer.denom)? {
+            // the pair's spread check alone doesn't stop a sandwich
+            let min_received = min_received
+                .filter(|min_received| !min_received.is_zero())
+                .ok_or(ContractError::MinReceivedRequired {})?;
+            return convert::deposit(deps, env, info.sender, offer.clone(), route, min_received);
+        }
+    }
+    // cw20 pools take deposits through the Receive hook
+    if let Some(token) = stable_token_read(deps.storage)? {
+        return Err(ContractError::NotAllowOtherDenoms {
+            denom: addr::humanize(deps.api, &token)?.to_string(),
+        });
+    }
 
     // check deposit
     let received: Uint128 = info
@@ -66,40 +200,60 @@ pub fn deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, C
         });
     }
 
+    deposit_stable(deps, env, info.sender, received)
+}
+
+/// mints DP to `depositor` for `received` of the stable, which the pool already holds
+pub fn deposit_stable(
+    deps: DepsMut,
+    env: Env,
+    depositor: Addr,
+    received: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = config::read(deps.storage)?;
+    let beneficiary_change = settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+    if fund_closed_read(deps.storage)? {
+        return Err(ContractError::FundClosed);
+    }
+    caps::ensure_not_paused(deps.storage)?;
+    if received.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+    caps::ensure_capacity(deps.as_ref(), &config, &[&depositor], received)?;
+
     let dp_mint_amount = received;
+    let mut buffer = liquid_buffer_read(deps.storage)?;
+    let kept = buffer::keep(deps.as_ref(), &config, &mut buffer, received)?;
+    if !kept.is_zero() {
+        liquid_buffer_store(deps.storage, &buffer)?;
+    }
 
     // If there are NFTs. give them an 'active' one, potentially switching a 'inactive' one if it's there
-    let nft_msg = if let Some(nft_contract) = config.nft_contract {
+    let nft_msg = if let Some(ref nft_contract) = config.nft_contract {
         if let Some(active) = config.nft_collection_active {
             if let Some(redeemed) = config.nft_collection_redeemed {
-                let exists_active = nft_exists(deps.as_ref(), &nft_contract, &info.sender, active)?;
+                let exists_active = nft_exists(deps.as_ref(), nft_contract, &depositor, active)?;
                 if exists_active.tokens.is_empty() {
                     let exists_redeemed =
-                        nft_exists(deps.as_ref(), &nft_contract, &info.sender, redeemed)?;
+                        nft_exists(deps.as_ref(), nft_contract, &depositor, redeemed)?;
                     if exists_redeemed.tokens.is_empty() {
                         let mint_msg = quick_mint_msg(
                             &format!("{}/{}", config.pool_name, env.block.height),
-                            &info.sender,
+                            &depositor,
                             active,
                         );
                         Some(CosmosMsg::Wasm(WasmMsg::Execute {
-                            contract_addr: deps
-                                .api
-                                .addr_humanize(&nft_contract)
-                                .unwrap()
-                                .to_string(),
+                            contract_addr: addr::humanize(deps.api, nft_contract)?.to_string(),
                             msg: to_binary(&mint_msg)?,
                             funds: vec![],
                         }))
                     } else {
-                        let token = exists_redeemed.tokens.first().unwrap();
-                        let switch_msg = switch_collection_msg(token, active);
+                        let switch_msg = match exists_redeemed.tokens.first() {
+                            Some(token) => switch_collection_msg(token, active),
+                            None => return Err(ContractError::NftContractInvalid),
+                        };
                         Some(CosmosMsg::Wasm(WasmMsg::Execute {
-                            contract_addr: deps
-                                .api
-                                .addr_humanize(&nft_contract)
-                                .unwrap()
-                                .to_string(),
+                            contract_addr: addr::humanize(deps.api, nft_contract)?.to_string(),
                             msg: to_binary(&switch_msg)?,
                             funds: vec![],
                         }))
@@ -118,27 +272,35 @@ pub fn deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, C
     };
 
     let response = Response::new()
-        .add_messages(anchor::deposit_stable_msg(
+        .add_messages(strategy::deposit_msgs(
             deps.as_ref(),
-            &config.money_market,
-            &config.stable_denom,
-            received,
+            &config,
+            received - kept,
         )?)
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps
-                .api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
+            contract_addr: addr::dp_token(deps.api, &config)?.to_string(),
             msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: info.sender.to_string(),
+                recipient: depositor.to_string(),
                 amount: dp_mint_amount,
             })?,
             funds: vec![],
         }))
         .add_attribute("action", "deposit")
-        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("sender", depositor.to_string())
         .add_attribute("amount", dp_mint_amount.to_string());
+    // let depositors know their yield is about to go to someone else
+    let response = if let Some(change) = beneficiary_change {
+        response.add_event(
+            Event::new("beneficiary_change_pending")
+                .add_attribute(
+                    "new_beneficiary",
+                    addr::humanize(deps.api, &change.beneficiary)?,
+                )
+                .add_attribute("effective_at", change.effective_at.to_string()),
+        )
+    } else {
+        response
+    };
 
     if let Some(nft_mint) = nft_msg {
         Ok(response.add_message(nft_mint))
@@ -148,30 +310,46 @@ pub fn deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, C
 }
 
 pub fn redeem(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     sender: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let config = config::read(deps.storage).unwrap();
+    let config = config::read(deps.storage)?;
 
-    let sender_addr = deps.api.addr_validate(&sender).unwrap();
+    let sender_addr = addr::validate(deps.api, &sender)?;
     if amount.is_zero() {
         return Err(ContractError::RedeemZero {});
     }
 
-    let epoch_state = anchor::epoch_state(deps.as_ref(), &config.money_market)?;
-    if epoch_state.exchange_rate.is_zero() {
-        return Err(ContractError::RedeemEpochIsZero {});
-    }
-    let thousand_x_exchange = epoch_state.exchange_rate.mul(Uint128::from(1000u128));
-
-    let market_redeem_amount = amount.div(thousand_x_exchange).mul(Uint128::from(1000u64));
-    let user_redeem_amount = market_redeem_amount.mul(epoch_state.exchange_rate);
+    // a loss is made up out of the insurance reserve before anyone redeems at it
+    let insurance_used = insurance::cover(deps.branch(), &env, &config)?;
+    // paid out of the liquid buffer if it can be, without touching the market
+    let mut buffer = liquid_buffer_read(deps.storage)?;
+    let (market_redeem, user_redeem_amount) = if amount <= buffer.held {
+        buffer.held -= amount;
+        (vec![], amount)
+    } else {
+        let positions = strategy::positions(deps.as_ref(), &env, &config)?;
+        if positions
+            .iter()
+            .all(|position| position.exchange_rate.is_zero())
+        {
+            return Err(ContractError::RedeemEpochIsZero {});
+        }
+        // topping the buffer up on the way
+        let shortfall = buffer::shortfall(deps.as_ref(), &config, &buffer, amount)?;
+        let (market_redeem, withdrawn) =
+            strategy::withdraw(deps.as_ref(), &positions, amount + shortfall)?;
+        let user_redeem_amount = withdrawn.min(amount);
+        buffer.held += withdrawn - user_redeem_amount;
+        (market_redeem, user_redeem_amount)
+    };
+    liquid_buffer_store(deps.storage, &buffer)?;
     let adjusted_amount = user_redeem_amount;
 
-    let nft_msg = if let Some(nft_contract) = config.nft_contract {
+    let nft_msg = if let Some(ref nft_contract) = config.nft_contract {
         if let Some(active) = config.nft_collection_active {
             if let Some(redeemed) = config.nft_collection_redeemed {
                 let balance_qry_msg = &Cw20QueryMsg::Balance {
@@ -180,22 +358,18 @@ pub fn redeem(
                 let balance_qry =
                     deps.querier
                         .query::<Cw20BalanceResponse>(&QueryRequest::Wasm(WasmQuery::Smart {
-                            contract_addr: deps
-                                .api
-                                .addr_humanize(&config.dp_token)
-                                .unwrap()
-                                .to_string(),
+                            contract_addr: addr::dp_token(deps.api, &config)?.to_string(),
                             msg: to_binary(&balance_qry_msg)?,
                         }))?;
                 // anything less than 10c is dust
                 if balance_qry.balance <= Uint128::from(100_000u64) {
                     let exists_redeemed =
-                        nft_exists(deps.as_ref(), &nft_contract, &sender_addr, redeemed)?;
+                        nft_exists(deps.as_ref(), nft_contract, &sender_addr, redeemed)?;
                     // if there is a redeemed token, then don't add another
                     // this might leave a 'active' one, but we don't burn
                     if exists_redeemed.tokens.is_empty() {
                         let exists_active =
-                            nft_exists(deps.as_ref(), &nft_contract, &sender_addr, active)?;
+                            nft_exists(deps.as_ref(), nft_contract, &sender_addr, active)?;
                         // this shouldn't really occur.
                         if exists_active.tokens.is_empty() {
                             let mint_msg = quick_mint_msg(
@@ -204,23 +378,17 @@ pub fn redeem(
                                 redeemed,
                             );
                             Some(CosmosMsg::Wasm(WasmMsg::Execute {
-                                contract_addr: deps
-                                    .api
-                                    .addr_humanize(&nft_contract)
-                                    .unwrap()
-                                    .to_string(),
+                                contract_addr: addr::humanize(deps.api, nft_contract)?.to_string(),
                                 msg: to_binary(&mint_msg)?,
                                 funds: vec![],
                             }))
                         } else {
-                            let token = exists_active.tokens.first().unwrap();
-                            let switch_msg = switch_collection_msg(token, redeemed);
+                            let switch_msg = match exists_active.tokens.first() {
+                                Some(token) => switch_collection_msg(token, redeemed),
+                                None => return Err(ContractError::NftContractInvalid),
+                            };
                             Some(CosmosMsg::Wasm(WasmMsg::Execute {
-                                contract_addr: deps
-                                    .api
-                                    .addr_humanize(&nft_contract)
-                                    .unwrap()
-                                    .to_string(),
+                                contract_addr: addr::humanize(deps.api, nft_contract)?.to_string(),
                                 msg: to_binary(&switch_msg)?,
                                 funds: vec![],
                             }))
@@ -240,26 +408,19 @@ pub fn redeem(
     } else {
         None
     };
-    let resp = Response::new()
-        .add_messages(anchor::redeem_stable_msg(
-            deps.as_ref(),
-            &config.money_market,
-            &config.atoken,
-            market_redeem_amount,
+    let mut resp = Response::new();
+    if !insurance_used.is_zero() {
+        resp = resp.add_attribute("insurance_used", insurance_used.to_string());
+    }
+    let resp = resp
+        .add_messages(market_redeem)
+        .add_message(stable::transfer_msg(
+            &stable::read(deps.as_ref(), &config)?,
+            &sender_addr,
+            user_redeem_amount,
         )?)
-        .add_message(CosmosMsg::Bank(BankMsg::Send {
-            to_address: sender.clone(),
-            amount: vec![coin(
-                u128::from(user_redeem_amount),
-                config.stable_denom.clone(),
-            )],
-        }))
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps
-                .api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
+            contract_addr: addr::dp_token(deps.api, &config)?.to_string(),
             msg: to_binary(&Cw20ExecuteMsg::Burn {
                 amount: adjusted_amount,
             })?,
@@ -282,203 +443,665 @@ pub fn redeem(
     }
 }
 
+/// what the pool holds above its deposits, and the positions it was valued from
+pub fn pool_earnings(
+    deps: Deps,
+    env: &Env,
+    config: &config::Config,
+) -> Result<Earnings, ContractError> {
+    let positions = strategy::positions(deps, env, config)?;
+    let dp_total_supply = token::total_supply(deps, addr::dp_token(deps.api, config)?.to_string())?;
+
+    // the liquid buffer is still deposits
+    let pool_value_locked = positions
+        .iter()
+        .fold(Uint128::zero(), |total, position| total + position.value())
+        + liquid_buffer_read(deps.storage)?.held;
+    let endowment = endowment_read(deps.storage)?.principal;
+    Ok(Earnings {
+        exchange_rate: positions[0].exchange_rate,
+        positions,
+        dp_total_supply,
+        pool_value_locked,
+        earnable: pool_value_locked.saturating_sub(dp_total_supply + endowment),
+    })
+}
+
+pub struct Earnings {
+    /// of the config's money market
+    pub exchange_rate: Decimal,
+    pub positions: Vec<Position>,
+    pub dp_total_supply: Uint128,
+    pub pool_value_locked: Uint128,
+    /// everything above the deposits and endowment, including what has accrued but not been paid yet
+    pub earnable: Uint128,
+}
+
+/// charges the fee on whatever has been earned since the last accrual.
+/// returns: the yield that was new, the fee charged on it, and the updated state
+pub fn accrue(
+    earnable: Uint128,
+    config: &config::Config,
+    now: u64,
+    last_claimed: LastClaimed,
+    mut accrued: Accrued,
+) -> (Uint128, Uint128, LastClaimed, Accrued) {
+    let new_yield = earnable.saturating_sub(accrued.total());
+    let (fee, last_claimed) = calc_fee(
+        new_yield,
+        &config.fee_model,
+        &config.fee_period,
+        now,
+        last_claimed,
+    );
+    accrued.fees += fee;
+    accrued.beneficiary += new_yield - fee;
+    (new_yield, fee, last_claimed, accrued)
+}
+
+/// `accrue` against the pool's current holdings, saving the result
+fn accrue_pool(
+    deps: DepsMut,
+    env: &Env,
+    config: &config::Config,
+) -> Result<(Earnings, Uint128, Uint128, Accrued), ContractError> {
+    let earnings = pool_earnings(deps.as_ref(), env, config)?;
+    let (new_yield, fee, last_claimed, accrued) = accrue(
+        earnings.earnable,
+        config,
+        env.block.time.seconds(),
+        last_claimed_read(deps.storage)?,
+        accrued_read(deps.storage)?,
+    );
+    last_claimed_store(deps.storage, &last_claimed)?;
+    accrued_store(deps.storage, &accrued)?;
+    Ok((earnings, new_yield, fee, accrued))
+}
+
+/// aTokens to redeem for at most `amount` of stable, and the stable they will return.
+/// redeeming rounds down, so that can be a little short of `amount`
+pub fn redemption(
+    amount: Uint128,
+    exchange_rate: Decimal,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let redeemable_d: Decimal = exchange_rate
+        .inv()
+        .unwrap_or_else(Decimal::zero)
+        .mul(Decimal::from_atomics(amount, 0)?);
+    let places = redeemable_d.decimal_places();
+    let redeemable = redeemable_d
+        .atomics()
+        .checked_div(Uint128::from(10u32).pow(places))?;
+    Ok((redeemable, redeemable.mul(exchange_rate).min(amount)))
+}
+
+/// pays the beneficiary (and payout split) what has accrued to them. the fee stays in the pool
+/// for the fee collector to take with CollectFees
 pub fn earn(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    // calculate deduct(total_aust_amount * exchange_rate) - (total_dp_balance)
-    let config = config::read(deps.storage).unwrap();
-    let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-    if config.beneficiary != sender_canon && config.fee_collector != sender_canon {
-        return Err(ContractError::Unauthorized {
-            action: "earn".to_string(),
-            expected: deps
-                .api
-                .addr_humanize(&config.beneficiary)
-                .unwrap()
-                .to_string(),
-            actual: info.sender.to_string(),
+    let mut config = config::read(deps.storage)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+    roles::ensure_any(
+        deps.storage,
+        "earn",
+        &info.sender,
+        &[Role::Beneficiary, Role::FeeCollector, Role::Operator],
+    )?;
+
+    pay_out(deps, &env, &config, &info.sender, None)
+}
+
+/// an earn anyone can call once it is due, for a bounty
+pub fn harvest(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = config::read(deps.storage)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+    let harvest = harvest_config_read(deps.storage)?.ok_or(ContractError::HarvestDisabled)?;
+
+    pay_out(deps, &env, &config, &info.sender, Some(&harvest))
+}
+
+/// the most recent payout to the beneficiary
+fn last_payout(storage: &dyn Storage) -> StdResult<Option<EarnRecord>> {
+    match EARN_COUNT.may_load(storage)? {
+        Some(id) => EARN_HISTORY.may_load(storage, id),
+        None => Ok(None),
+    }
+}
+
+/// when the beneficiary was last paid, seconds since epoch
+pub fn last_payout_at(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    Ok(last_payout(storage)?.map(|record| record.time))
+}
+
+/// first block height the earn limits allow a payout at
+pub fn next_earn_allowed_at(storage: &dyn Storage, limits: &EarnLimits) -> StdResult<u64> {
+    Ok(last_payout(storage)?
+        .map(|record| record.height + limits.min_blocks_between_earn)
+        .unwrap_or_default())
+}
+
+fn ensure_min_earn_amount(amount: Uint128, limits: &EarnLimits) -> Result<(), ContractError> {
+    if amount < limits.min_earn_amount {
+        return Err(ContractError::EarnBelowMinimum {
+            amount,
+            minimum: limits.min_earn_amount,
+        });
+    }
+    Ok(())
+}
+
+/// shared by earn and harvest. with a harvest config, the sender is a keeper who gets the bounty
+/// once the fund is due
+fn pay_out(
+    mut deps: DepsMut,
+    env: &Env,
+    config: &config::Config,
+    sender: &Addr,
+    harvest: Option<&HarvestConfig>,
+) -> Result<Response, ContractError> {
+    let (earnings, new_yield, fee, mut accrued) = accrue_pool(deps.branch(), env, config)?;
+    // never pay out what the fee collector is owed, even if the exchange rate dipped
+    let owed = accrued
+        .beneficiary
+        .min(earnings.earnable.saturating_sub(accrued.fees));
+    let limits = earn_limits_read(deps.storage)?;
+    let next_earn_allowed_at = next_earn_allowed_at(deps.storage, &limits)?;
+    if env.block.height < next_earn_allowed_at {
+        return Err(ContractError::EarnTooSoon {
+            next_earn_allowed_at,
         });
     }
+    ensure_min_earn_amount(owed, &limits)?;
+    // the insurance reserve's slice comes off the top
+    let mut reserve = insurance_reserve_read(deps.storage)?;
+    let insured = insurance::slice(&reserve, owed);
+    // the endowment's share is just left in the market
+    let mut endowment = endowment_read(deps.storage)?;
+    let retained = (owed - insured) * endowment.retain_ratio;
+    if !retained.is_zero() {
+        endowment.principal += retained;
+        endowment.last_retained = retained;
+        endowment.first_retained_at = endowment
+            .first_retained_at
+            .or(Some(env.block.time.seconds()));
+        endowment.last_retained_at = Some(env.block.time.seconds());
+        endowment_store(deps.storage, &endowment)?;
+    }
+    // whatever the redemption rounds away stays owed for next time
+    let (redeem, withdrawn) =
+        strategy::withdraw(deps.as_ref(), &earnings.positions, owed - retained)?;
+    let insured = insured.min(withdrawn);
+    let mut payout = withdrawn - insured;
+    let paid = payout;
+    // an empty record would still push back the next earn and harvest
+    if harvest.is_none() && withdrawn.is_zero() && retained.is_zero() {
+        return Err(ContractError::NothingToEarn);
+    }
 
-    // assets
-    let epoch_state = anchor::epoch_state(deps.as_ref(), &config.money_market)?;
-    let atoken_balance = token::balance_of(
-        deps.as_ref(),
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
-        env.contract.address.to_string(),
+    let keeper_bounty = match harvest {
+        Some(harvest) => {
+            if payout.is_zero() {
+                return Err(ContractError::NothingToHarvest);
+            }
+            let due_at = last_payout_at(deps.storage)?
+                .map(|last| last + harvest.min_interval)
+                .unwrap_or_default();
+            let enough_earned = !harvest.min_earnable.is_zero() && payout >= harvest.min_earnable;
+            if env.block.time.seconds() < due_at && !enough_earned {
+                return Err(ContractError::HarvestNotDue { due_at });
+            }
+            let mut bounty = payout * harvest.bounty;
+            if !harvest.bounty_cap.is_zero() {
+                bounty = bounty.min(harvest.bounty_cap);
+            }
+            payout -= bounty;
+            Some(Payout {
+                recipient: sender.clone(),
+                amount: bounty,
+            })
+        }
+        None => None,
+    };
+    accrued.beneficiary -= paid + retained + insured;
+    accrued_store(deps.storage, &accrued)?;
+    if !insured.is_zero() {
+        reserve.held += insured;
+        reserve.total_funded += insured;
+        insurance_reserve_store(deps.storage, &reserve)?;
+    }
+
+    governance::settle_payout_split_change(deps.storage, env)?;
+    let mut payouts = payouts(deps.as_ref(), config, payout)?;
+    let matched = matching::release(deps.storage, env, paid)?;
+    let hook_period = if yield_hook_enabled(deps.storage, config)? {
+        Some(YieldPeriod {
+            start: last_payout_at(deps.storage)?,
+            end: env.block.time.seconds(),
+        })
+    } else {
+        None
+    };
+    let ibc_payout = ibc_payout_read(deps.storage, config)?;
+    let swap_payout = swap_payout_read(deps.storage, config)?;
+    let earn_id = record_earn(
+        deps.storage,
+        env,
+        earnings.exchange_rate,
+        new_yield,
+        fee,
+        &payouts,
+        keeper_bounty.clone(),
+        matched,
+        retained,
+        insured,
     )?;
-    let dp_total_supply: Uint128 = Uint128::from_str(
-        &token::total_supply(
-            deps.as_ref(),
-            deps.api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
-        )?
-        .to_string(),
+    // the beneficiary is always first
+    payouts[0].amount += matched;
+
+    let mut response = Response::new().add_messages(redeem);
+    let recipients = payouts
+        .into_iter()
+        .enumerate()
+        .map(|(i, payout)| (i == 0, payout))
+        .chain(keeper_bounty.clone().map(|bounty| (false, bounty)))
+        .filter(|(_, payout)| !payout.amount.is_zero());
+    let stable = stable::read(deps.as_ref(), config)?;
+    for (to_beneficiary, payout) in recipients {
+        response = match (to_beneficiary, &ibc_payout, &swap_payout, &hook_period) {
+            (true, Some(ibc_payout), _, _) => response.add_submessage(ibc::payout(
+                deps.storage,
+                env,
+                &config.stable_denom,
+                earn_id,
+                payout.recipient,
+                payout.amount,
+                ibc_payout.clone(),
+            )?),
+            (true, None, Some(swap_payout), _) => response.add_submessage(swap::payout(
+                deps.storage,
+                &stable,
+                earn_id,
+                payout.recipient,
+                payout.amount,
+                swap_payout.clone(),
+            )?),
+            (true, None, None, Some(period)) => response.add_message(stable::execute_msg(
+                &stable,
+                &payout.recipient,
+                to_binary(&YieldReceiverMsg::YieldReceived {
+                    pool: env.contract.address.to_string(),
+                    amount: payout.amount,
+                    period: period.clone(),
+                })?,
+                payout.amount,
+            )?),
+            _ => response.add_message(stable::transfer_msg(
+                &stable,
+                &payout.recipient,
+                payout.amount,
+            )?),
+        };
+    }
+    response = response
+        .add_attribute("action", if harvest.is_some() { "harvest" } else { "earn" })
+        .add_attribute("sender", sender.to_string())
+        .add_attribute("amount", payout.to_string())
+        .add_attribute("fee", fee.to_string());
+    if !matched.is_zero() {
+        response = response.add_attribute("matched", matched.to_string());
+    }
+    if !retained.is_zero() {
+        response = response.add_attribute("retained", retained.to_string());
+    }
+    if let Some(bounty) = keeper_bounty {
+        response = response.add_attribute("bounty", bounty.amount.to_string());
+    }
+    Ok(response)
+}
+
+pub fn set_harvest_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    harvest: Option<HarvestConfig>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_harvest_config",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
     )?;
 
-    let pool_value_locked = atoken_balance.mul(epoch_state.exchange_rate);
+    let response = Response::new().add_attribute("action", "set_harvest_config");
+    match harvest {
+        Some(harvest) => {
+            if harvest.bounty > MAX_HARVEST_BOUNTY {
+                return Err(ContractError::InvalidHarvestConfig {
+                    max: MAX_HARVEST_BOUNTY,
+                });
+            }
+            harvest_config_store(deps.storage, &harvest)?;
+            Ok(response
+                .add_attribute("min_interval", harvest.min_interval.to_string())
+                .add_attribute("min_earnable", harvest.min_earnable.to_string())
+                .add_attribute("bounty", harvest.bounty.to_string()))
+        }
+        None => {
+            harvest_config_remove(deps.storage);
+            Ok(response.add_attribute("harvest", "off"))
+        }
+    }
+}
 
-    let earnable = pool_value_locked.sub(dp_total_supply);
-    // fee = 0 means use fee_max as a fixed_fee.
+pub fn set_yield_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_yield_hook",
+        &info.sender,
+        &[Role::Beneficiary],
+    )?;
 
-    let last_claimed = last_claimed_read(deps.storage).unwrap();
-    let (fee, updated_last_claimed) = calc_fee(
-        earnable,
-        config.fee_amount,
-        config.fee_max,
-        config.fee_reset_every_num_blocks,
-        env.block.height,
-        last_claimed,
+    if enabled {
+        yield_hook_store(deps.storage, &config.beneficiary)?;
+    } else {
+        yield_hook_remove(deps.storage);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "set_yield_hook")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+pub fn set_retain_ratio(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    retain_ratio: Decimal,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_retain_ratio",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
+    )?;
+    if retain_ratio > Decimal::one() {
+        return Err(ContractError::InvalidRetainRatio);
+    }
+
+    let mut endowment = endowment_read(deps.storage)?;
+    endowment.retain_ratio = retain_ratio;
+    endowment_store(deps.storage, &endowment)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_retain_ratio")
+        .add_attribute("retain_ratio", retain_ratio.to_string()))
+}
+
+pub fn set_earn_limits(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    min_earn_amount: Option<Uint128>,
+    min_blocks_between_earn: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_earn_limits",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary, Role::FeeCollector]),
+    )?;
+
+    let mut limits = earn_limits_read(deps.storage)?;
+    if let Some(min_earn_amount) = min_earn_amount {
+        limits.min_earn_amount = min_earn_amount;
+    }
+    if let Some(min_blocks_between_earn) = min_blocks_between_earn {
+        limits.min_blocks_between_earn = min_blocks_between_earn;
+    }
+    earn_limits_store(deps.storage, &limits)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_earn_limits")
+        .add_attribute("min_earn_amount", limits.min_earn_amount.to_string())
+        .add_attribute(
+            "min_blocks_between_earn",
+            limits.min_blocks_between_earn.to_string(),
+        ))
+}
+
+/// sends the fee collector the fees that have accrued, without paying the beneficiary
+pub fn collect_fees(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "collect_fees",
+        &info.sender,
+        &[Role::FeeCollector],
+    )?;
+
+    let (earnings, _, _, mut accrued) = accrue_pool(deps.branch(), &env, &config)?;
+    let owed = accrued.fees.min(earnings.earnable);
+    if owed.is_zero() {
+        return Err(ContractError::NoFeesAccrued);
+    }
+    // whatever the redemption rounds away stays owed for next time
+    let (redeem, collected) = strategy::withdraw(deps.as_ref(), &earnings.positions, owed)?;
+    accrued.fees -= collected;
+    accrued_store(deps.storage, &accrued)?;
+    let fee_collector = addr::humanize(deps.api, &config.fee_collector)?;
+    record_fee(deps.storage, &env, collected, &fee_collector)?;
+
+    let mut response = Response::new().add_messages(redeem);
+    if !collected.is_zero() {
+        response = response.add_message(stable::transfer_msg(
+            &stable::read(deps.as_ref(), &config)?,
+            &fee_collector,
+            collected,
+        )?);
+    }
+    Ok(response
+        .add_attribute("action", "collect_fees")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("fee_collector", fee_collector)
+        .add_attribute("amount", collected.to_string()))
+}
+
+/// appends the earn to the history
+#[allow(clippy::too_many_arguments)]
+fn record_earn(
+    storage: &mut dyn Storage,
+    env: &Env,
+    exchange_rate: Decimal,
+    earnable: Uint128,
+    fee: Uint128,
+    payouts: &[Payout],
+    keeper_bounty: Option<Payout>,
+    matched: Uint128,
+    retained: Uint128,
+    insured: Uint128,
+) -> StdResult<u64> {
+    let id = EARN_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    let (beneficiary, split) = match payouts.split_first() {
+        Some((beneficiary, split)) => (beneficiary.clone(), split.to_vec()),
+        None => return Ok(0),
+    };
+    EARN_HISTORY.save(
+        storage,
+        id,
+        &EarnRecord {
+            id,
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            exchange_rate,
+            earnable,
+            fee,
+            beneficiary: beneficiary.recipient,
+            beneficiary_amount: beneficiary.amount,
+            split,
+            keeper_bounty,
+            swap: None,
+            matched: (!matched.is_zero()).then_some(matched),
+            retained: (!retained.is_zero()).then_some(retained),
+            insured: (!insured.is_zero()).then_some(insured),
+        },
+    )?;
+    EARN_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+/// appends a fee collection to the fee history
+pub fn record_fee(
+    storage: &mut dyn Storage,
+    env: &Env,
+    fee: Uint128,
+    fee_collector: &Addr,
+) -> StdResult<()> {
+    let id = FEE_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    FEE_HISTORY.save(
+        storage,
+        id,
+        &FeeRecord {
+            id,
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            fee,
+            fee_collector: fee_collector.clone(),
+        },
+    )?;
+    FEE_COUNT.save(storage, &id)
+}
+
+/// roles that can change the fund's details. the owner only if the fund allows it
+pub fn config_editors(config: &config::Config, roles: &[Role]) -> Vec<Role> {
+    let mut roles = roles.to_vec();
+    if config.owner_can_change_config {
+        roles.push(Role::Owner);
+    }
+    roles
+}
+
+/// the payout to the beneficiary, less any shares depositors have voted to others.
+/// the beneficiary always comes first
+fn payouts(
+    deps: Deps,
+    config: &config::Config,
+    amount: Uint128,
+) -> Result<Vec<Payout>, ContractError> {
+    let mut payouts = vec![];
+    let mut remaining = amount;
+    for share in payout_split_read(deps.storage)? {
+        let share_amount = amount.mul(share.share);
+        if share_amount.is_zero() {
+            continue;
+        }
+        remaining = remaining.checked_sub(share_amount)?;
+        payouts.push(Payout {
+            recipient: addr::humanize(deps.api, &share.recipient)?,
+            amount: share_amount,
+        });
+    }
+    payouts.insert(
+        0,
+        Payout {
+            recipient: addr::humanize(deps.api, &config.beneficiary)?,
+            amount: remaining,
+        },
     );
-    last_claimed_store(deps.storage, &updated_last_claimed).unwrap();
+    Ok(payouts)
+}
 
-    let redeemable_earned_d: Decimal = (if epoch_state.exchange_rate.is_zero() {
-        Decimal::zero()
-    } else {
-        epoch_state.exchange_rate.inv().unwrap()
-    })
-    .mul(Decimal::from_atomics(earnable, 0)?);
-    let places = redeemable_earned_d.decimal_places();
-    let redeemable_earned: Uint128 = redeemable_earned_d
-        .atomics()
-        .checked_div(Uint128::from(10u32).pow(places))
-        .unwrap();
-    // if there is no fee. then don't do a send to the fee collection.
-    if fee.is_zero() {
-        Ok(Response::new()
-            .add_messages(anchor::redeem_stable_msg(
-                deps.as_ref(),
-                &config.money_market,
-                &config.atoken,
-                redeemable_earned,
-            )?)
-            .add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: deps
-                    .api
-                    .addr_humanize(&config.beneficiary)
-                    .unwrap()
-                    .to_string(),
-                amount: vec![Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: earnable.sub(fee),
-                }],
-            }))
-            .add_attribute("action", "earn")
-            .add_attribute("sender", info.sender.to_string())
-            .add_attribute("amount", earnable.sub(fee).to_string())
-            .add_attribute("fee", fee.to_string()))
-    } else {
-        let fee_minus_one = fee.sub(Uint128::from(1u64));
-        Ok(Response::new()
-            .add_messages(anchor::redeem_stable_msg(
-                deps.as_ref(),
-                &config.money_market,
-                &config.atoken,
-                redeemable_earned,
-            )?)
-            .add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: deps
-                    .api
-                    .addr_humanize(&config.beneficiary)
-                    .unwrap()
-                    .to_string(),
-                amount: vec![Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: earnable.sub(fee),
-                }],
-            }))
-            .add_message(CosmosMsg::Bank(BankMsg::Send {
-                to_address: deps
-                    .api
-                    .addr_humanize(&config.fee_collector)
-                    .unwrap()
-                    .to_string(),
-                amount: vec![Coin {
-                    denom: config.stable_denom.clone(),
-                    amount: fee_minus_one,
-                }],
-            }))
-            .add_attribute("action", "earn")
-            .add_attribute("sender", info.sender.to_string())
-            .add_attribute("amount", earnable.sub(fee).to_string())
-            .add_attribute("fee", fee_minus_one.to_string()))
-    }
-}
-/// Calculate fee to charge, taking into account monthly caps
+/// Calculate fee to charge, taking into account the cap per fee period.
 /// returns: fee to charge, and fees collected in the current period
 pub fn calc_fee(
     earnable: Uint128,
-    fee_amount: Decimal,
-    fee_max: Uint128,
-    _fee_reset_every_num_blocks: u64,
-    _current_block_height: u64,
+    fee_model: &FeeModel,
+    fee_period: &FeePeriod,
+    now: u64,
     last_claimed_current: LastClaimed,
 ) -> (Uint128, LastClaimed) {
-    let mut fee: Uint128;
-    if fee_amount > Decimal::zero() {
-        fee = earnable.mul(fee_amount);
-        if !fee_max.is_zero() && fee > fee_max {
-            fee = fee_max;
-        }
-    } else {
-        fee = fee_max;
+    let (period_start, fees_collected) =
+        if fee_period.is_new_period(last_claimed_current.period_start, now) {
+            (
+                fee_period.start_of(last_claimed_current.period_start, now),
+                Uint128::zero(),
+            )
+        } else {
+            (
+                last_claimed_current.period_start,
+                last_claimed_current.fees_collected,
+            )
+        };
+
+    let mut fee = fee_model.fee(earnable);
+    if let Some(cap) = fee_model.period_cap() {
+        fee = fee.min(cap.saturating_sub(fees_collected));
     }
     (
         fee,
         LastClaimed {
-            last_claimed_at_block_height: last_claimed_current.last_claimed_at_block_height,
-            fees_collected: last_claimed_current.fees_collected + fee,
+            period_start,
+            fees_collected: fees_collected + fee,
             total_earned_at_last_claimed: last_claimed_current.total_earned_at_last_claimed
-                + earnable.sub(fee),
+                + earnable.saturating_sub(fee),
         },
     )
 }
 
 pub fn configure(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     beneficiary: Option<String>,
     fee_collector: Option<String>,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-    if config.beneficiary != sender_canon
-        && config.fee_collector != sender_canon
-        && !(config.owner_can_change_config && config.owner == sender_canon)
-    {
-        return Err(ContractError::Unauthorized {
-            action: "configure".to_string(),
-            expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-            actual: info.sender.to_string(),
-        });
+    let mut config = config::read(deps.storage)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+    let mut editors = config_editors(&config, &[Role::Beneficiary, Role::FeeCollector]);
+    // the owner (the factory, for funds it created) can always move the fee collector, but
+    // nothing else unless it can change the config
+    if fee_collector.is_some() {
+        editors.push(Role::Owner);
     }
+    roles::ensure_any(deps.storage, "configure", &info.sender, &editors)?;
 
+    let mut response = Response::new().add_attribute("action", "configure");
     if let Some(beneficiary) = beneficiary {
-        if config.owner == sender_canon || config.beneficiary == sender_canon {
-            config.beneficiary = deps.api.addr_canonicalize(beneficiary.as_str()).unwrap();
-        } else {
-            return Err(ContractError::Unauthorized {
-                action: "configure_beneficiary".to_string(),
-                expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-                actual: info.sender.to_string(),
-            });
-        }
+        roles::ensure_any(
+            deps.storage,
+            "configure_beneficiary",
+            &info.sender,
+            &config_editors(&config, &[Role::Beneficiary]),
+        )?;
+        // the new beneficiary has to accept before any yield is sent their way
+        let expires_at = propose_new_beneficiary(deps.branch(), &env, &beneficiary)?;
+        response = response
+            .add_attribute("proposed_beneficiary", beneficiary)
+            .add_attribute("expires_at", expires_at.to_string());
     }
     if let Some(fee_collector) = fee_collector {
-        if config.owner == sender_canon || config.fee_collector == sender_canon {
-            config.fee_collector = deps.api.addr_canonicalize(fee_collector.as_str()).unwrap();
-        } else {
-            return Err(ContractError::Unauthorized {
-                action: "configure_fee_collector".to_string(),
-                expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-                actual: info.sender.to_string(),
-            });
-        }
+        roles::ensure_any(
+            deps.storage,
+            "configure_fee_collector",
+            &info.sender,
+            &[Role::Owner, Role::FeeCollector],
+        )?;
+        let fee_collector = addr::validate(deps.api, &fee_collector)?;
+        roles::set_holder(deps.storage, Role::FeeCollector, &fee_collector)?;
+        config.fee_collector = addr::canonicalize(deps.api, fee_collector.as_str())?;
     }
     config::store(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(response)
 }
 
 pub fn configure_details(
@@ -488,17 +1111,13 @@ pub fn configure_details(
     title: Option<String>,
     description: Option<String>,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-    if config.beneficiary != sender_canon
-        && !(config.owner_can_change_config && config.owner == sender_canon)
-    {
-        return Err(ContractError::Unauthorized {
-            action: "configure_details".to_string(),
-            expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
+    let mut config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "configure_details",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
+    )?;
 
     if let Some(title) = title {
         config.pool_title = title;
@@ -516,23 +1135,15 @@ pub fn clear_nft_contract(
     _env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-    if config.beneficiary != sender_canon
-        && !(config.owner == sender_canon && config.owner_can_change_config)
-    {
-        return Err(ContractError::Unauthorized {
-            action: "clear_nft_contract".to_string(),
-            expected: deps
-                .api
-                .addr_humanize(&config.beneficiary)
-                .unwrap()
-                .to_string(),
-            actual: info.sender.to_string(),
-        });
-    }
+    let mut config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "clear_nft_contract",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
+    )?;
     let old_nft_contract = if let Some(c) = config.nft_contract {
-        deps.api.addr_humanize(&c)?.to_string()
+        addr::humanize(deps.api, &c)?.to_string()
     } else {
         "-None-".to_string()
     };
@@ -566,36 +1177,15 @@ pub fn set_nft_contract(
     nft_collection_active: Option<u64>,
     nft_collection_redeemed: Option<u64>,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
-    if config.beneficiary != sender_canon {
-        if config.owner == sender_canon {
-            if !(config.owner_can_change_config || config.nft_contract.is_none()) {
-                return Err(ContractError::Unauthorized {
-                    action: "clear_nft_contract/owner can't change".to_string(),
-                    expected: deps
-                        .api
-                        .addr_humanize(&config.beneficiary)
-                        .unwrap()
-                        .to_string(),
-                    actual: info.sender.to_string(),
-                });
-            }
-        } else {
-            return Err(ContractError::Unauthorized {
-                action: "clear_nft_contract".to_string(),
-                expected: deps
-                    .api
-                    .addr_humanize(&config.beneficiary)
-                    .unwrap()
-                    .to_string(),
-                actual: info.sender.to_string(),
-            });
-        }
+    let mut config = config::read(deps.storage)?;
+    // owner can set the NFT contract if it is blank
+    let mut roles = config_editors(&config, &[Role::Beneficiary]);
+    if config.nft_contract.is_none() {
+        roles.push(Role::Owner);
     }
-
+    roles::ensure_any(deps.storage, "set_nft_contract", &info.sender, &roles)?;
     let old_nft_contract = if let Some(c) = config.nft_contract {
-        deps.api.addr_humanize(&c)?.to_string()
+        addr::humanize(deps.api, &c)?.to_string()
     } else {
         "-None-".to_string()
     };
@@ -609,7 +1199,7 @@ pub fn set_nft_contract(
     } else {
         "-None-".to_string()
     };
-    config.nft_contract = Some(deps.api.addr_canonicalize(&nft_contract)?);
+    config.nft_contract = Some(addr::canonicalize(deps.api, &nft_contract)?);
     config.nft_collection_active = nft_collection_active;
     config.nft_collection_redeemed = nft_collection_redeemed;
     config::store(deps.storage, &config)?;
@@ -638,27 +1228,25 @@ pub fn set_nft_collection(
     collection_id: String,
     msg: Binary,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
+    let mut config = config::read(deps.storage)?;
 
     if let Some(ref nft_addr) = config.nft_contract {
         // 1. NFT contract should be directly calling us. so info.sender should be there
-        let sender_canon = deps.api.addr_canonicalize(info.sender.as_str()).unwrap();
+        let sender_canon = addr::canonicalize(deps.api, info.sender.as_str())?;
         if nft_addr != &sender_canon {
             return Err(ContractError::Unauthorized {
                 action: "set_nft_collection only can come from the NFT contract".to_string(),
-                expected: deps.api.addr_humanize(nft_addr).unwrap().to_string(),
+                expected: addr::humanize(deps.api, nft_addr)?.to_string(),
                 actual: info.sender.to_string(),
             });
         }
         // 2. we trust the NFT contract to pass us who called them, and that should be the 'owner' of the fund
-        let owner_sender = deps.api.addr_canonicalize(&sender).unwrap();
-        if owner_sender != config.owner {
-            return Err(ContractError::Unauthorized {
-                action: "set_nft_collection only can originate from the fund owner".to_string(),
-                expected: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-                actual: sender,
-            });
-        }
+        roles::ensure_any(
+            deps.storage,
+            "set_nft_collection only can originate from the fund owner",
+            &addr::validate(deps.api, &sender)?,
+            &[Role::Owner],
+        )?;
 
         let new_collection_id: u64 = collection_id.parse::<u64>()?;
         let callback: NftCallback = from_binary(&msg)?;
@@ -706,20 +1294,16 @@ pub fn register_dp_token(
     _env: Env,
     address: Addr,
 ) -> Result<Response, ContractError> {
-    let mut config = config::read(deps.storage).unwrap();
-    if config.dp_token != CanonicalAddr::from(vec![]) {
+    let mut config = config::read(deps.storage)?;
+    if !config.dp_token.is_empty() {
         return Err(ContractError::Unauthorized {
             action: "register_dp_token".to_string(),
             expected: "<empty>".to_string(),
-            actual: deps
-                .api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
+            actual: addr::dp_token(deps.api, &config)?.to_string(),
         });
     }
 
-    config.dp_token = deps.api.addr_canonicalize(address.as_str()).unwrap();
+    config.dp_token = addr::canonicalize(deps.api, address.as_str())?;
     config::store(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("dp_token", address.to_string()))
@@ -0,0 +1,135 @@
+use cosmwasm_std::*;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::addr;
+use crate::config;
+use crate::config::{
+    donor_collection_read, donor_collection_remove, donor_collection_store, fund_closed_read,
+    last_claimed_read, last_claimed_store,
+};
+use crate::error::ContractError;
+use crate::handler::core::{calc_fee, config_editors, record_fee};
+use crate::handler::ownership::settle_beneficiary_change;
+use crate::handler::strategy;
+use crate::querier::nft::{nft_exists, quick_mint_msg};
+use crate::stable;
+use crate::state::{DONORS, TOTAL_DONATED};
+
+pub fn set_donor_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_donor_collection",
+        &info.sender,
+        &config_editors(&config, &[Role::Beneficiary]),
+    )?;
+
+    let response = Response::new().add_attribute("action", "set_donor_collection");
+    match collection_id {
+        Some(collection_id) => {
+            if config.nft_contract.is_none() {
+                return Err(ContractError::NftContractInvalid);
+            }
+            donor_collection_store(deps.storage, collection_id)?;
+            Ok(response.add_attribute("donor_collection", collection_id.to_string()))
+        }
+        None => {
+            donor_collection_remove(deps.storage);
+            Ok(response.add_attribute("donor_collection", "-"))
+        }
+    }
+}
+
+pub fn donate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    direct: bool,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    let received = stable::native_received(deps.as_ref(), &config, &info.funds)?;
+
+    donate_stable(deps, env, info.sender, received, direct)
+}
+
+/// `received` of the stable, which the pool already holds, as a gift from `donor`
+pub fn donate_stable(
+    deps: DepsMut,
+    env: Env,
+    donor: Addr,
+    received: Uint128,
+    direct: bool,
+) -> Result<Response, ContractError> {
+    let mut config = config::read(deps.storage)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+    if fund_closed_read(deps.storage)? {
+        return Err(ContractError::FundClosed);
+    }
+    if received.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+
+    let total = DONORS.may_load(deps.storage, &donor)?.unwrap_or_default() + received;
+    DONORS.save(deps.storage, &donor, &total)?;
+    let pool_total = TOTAL_DONATED.may_load(deps.storage)?.unwrap_or_default() + received;
+    TOTAL_DONATED.save(deps.storage, &pool_total)?;
+
+    let stable = stable::read(deps.as_ref(), &config)?;
+    let mut response = Response::new()
+        .add_attribute("action", "donate")
+        .add_attribute("sender", donor.to_string())
+        .add_attribute("amount", received.to_string());
+    if direct {
+        // charged like yield, so it counts towards the fee period's cap
+        let (fee, last_claimed) = calc_fee(
+            received,
+            &config.fee_model,
+            &config.fee_period,
+            env.block.time.seconds(),
+            last_claimed_read(deps.storage)?,
+        );
+        last_claimed_store(deps.storage, &last_claimed)?;
+        if !fee.is_zero() {
+            let fee_collector = addr::humanize(deps.api, &config.fee_collector)?;
+            record_fee(deps.storage, &env, fee, &fee_collector)?;
+            response = response.add_message(stable::transfer_msg(&stable, &fee_collector, fee)?);
+        }
+        response = response
+            .add_message(stable::transfer_msg(
+                &stable,
+                &addr::humanize(deps.api, &config.beneficiary)?,
+                received - fee,
+            )?)
+            .add_attribute("fee", fee.to_string());
+    } else {
+        // the fee is taken when it accrues with the yield
+        response = response.add_messages(strategy::deposit_msgs(deps.as_ref(), &config, received)?);
+    }
+
+    if let (Some(nft_contract), Some(collection)) =
+        (&config.nft_contract, donor_collection_read(deps.storage)?)
+    {
+        if nft_exists(deps.as_ref(), nft_contract, &donor, collection)?
+            .tokens
+            .is_empty()
+        {
+            let mint_msg = quick_mint_msg(
+                &format!("{}/{}-d", config.pool_name, env.block.height),
+                &donor,
+                collection,
+            );
+            response = response.add_message(WasmMsg::Execute {
+                contract_addr: addr::humanize(deps.api, nft_contract)?.to_string(),
+                msg: to_json_binary(&mint_msg)?,
+                funds: vec![],
+            });
+        }
+    }
+    Ok(response.add_attribute("direct", direct.to_string()))
+}
@@ -0,0 +1,318 @@
+use cosmwasm_std::*;
+use cw20::Cw20ExecuteMsg;
+use yieldpay_core::pool_msg::{GovernanceAction, PayoutShare, VoteOption};
+use yieldpay_core::pool_resp::ProposalStatus;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::addr;
+use crate::config;
+use crate::config::{
+    beneficiary_change_delay_at, beneficiary_change_store, fund_closed_store,
+    governance_config_read, governance_config_store, payout_split_change_read,
+    payout_split_change_remove, payout_split_change_store, payout_split_store,
+    pending_beneficiary_remove, BeneficiaryChange, PayoutSplitChange,
+};
+use crate::error::ContractError;
+use crate::handler::ownership::settle_beneficiary_change;
+use crate::state::{Proposal, PROPOSALS, PROPOSAL_COUNT, STAKED, TOTAL_STAKED, VOTES};
+
+/// called from `receive` once the sender has been checked to be the DP token
+pub fn stake(
+    deps: DepsMut,
+    env: Env,
+    staker: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+    let staker = addr::validate(deps.api, &staker)?;
+    let height = env.block.height;
+    STAKED.update(deps.storage, &staker, height, |staked| -> StdResult<_> {
+        Ok(staked.unwrap_or_default() + amount)
+    })?;
+    TOTAL_STAKED.update(deps.storage, height, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + amount)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "stake")
+        .add_attribute("staker", staker)
+        .add_attribute("amount", amount.to_string()))
+}
+
+pub fn unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+    let config = config::read(deps.storage)?;
+    let height = env.block.height;
+    let staked = STAKED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if staked < amount {
+        return Err(ContractError::InsufficientStake {
+            staked,
+            requested: amount,
+        });
+    }
+    STAKED.save(deps.storage, &info.sender, &(staked - amount), height)?;
+    TOTAL_STAKED.update(deps.storage, height, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_sub(amount)?)
+    })?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: addr::dp_token(deps.api, &config)?.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }))
+        .add_attribute("action", "unstake")
+        .add_attribute("staker", info.sender)
+        .add_attribute("amount", amount.to_string()))
+}
+
+pub fn propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: GovernanceAction,
+    description: String,
+) -> Result<Response, ContractError> {
+    let staked = STAKED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if staked.is_zero() {
+        return Err(ContractError::NoVotingPower);
+    }
+    validate_action(deps.as_ref(), &action)?;
+
+    let governance_config = governance_config_read(deps.storage)?;
+    let id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    // the stake as it stood at the start of this block is what counts, for the votes and for
+    // the quorum they are measured against. DP that isn't staked can't vote, so it isn't counted
+    let start_height = env.block.height;
+    let proposal = Proposal {
+        id,
+        proposer: info.sender.clone(),
+        action,
+        description,
+        start_height,
+        voting_ends_at: env.block.time.seconds() + governance_config.voting_period,
+        yes_votes: Uint128::zero(),
+        no_votes: Uint128::zero(),
+        total_weight: TOTAL_STAKED
+            .may_load_at_height(deps.storage, start_height)?
+            .unwrap_or_default(),
+        quorum: governance_config.quorum,
+        threshold: governance_config.threshold,
+        executed: false,
+    };
+    PROPOSALS.save(deps.storage, id, &proposal)?;
+    PROPOSAL_COUNT.save(deps.storage, &id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose")
+        .add_attribute("proposer", info.sender)
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("voting_ends_at", proposal.voting_ends_at.to_string()))
+}
+
+pub fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: VoteOption,
+) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS
+        .may_load(deps.storage, proposal_id)?
+        .ok_or(ContractError::ProposalNotFound(proposal_id))?;
+    if proposal.status(env.block.time.seconds()) != ProposalStatus::Open {
+        return Err(ContractError::VotingClosed(proposal_id));
+    }
+    if VOTES.has(deps.storage, (proposal_id, &info.sender)) {
+        return Err(ContractError::AlreadyVoted(proposal_id));
+    }
+    let weight = STAKED
+        .may_load_at_height(deps.storage, &info.sender, proposal.start_height)?
+        .unwrap_or_default();
+    if weight.is_zero() {
+        return Err(ContractError::NoVotingPower);
+    }
+
+    match vote {
+        VoteOption::Yes => proposal.yes_votes += weight,
+        VoteOption::No => proposal.no_votes += weight,
+    }
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+    VOTES.save(deps.storage, (proposal_id, &info.sender), &vote)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cast_vote")
+        .add_attribute("voter", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("vote", format!("{:?}", vote).to_lowercase())
+        .add_attribute("weight", weight.to_string()))
+}
+
+pub fn execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS
+        .may_load(deps.storage, proposal_id)?
+        .ok_or(ContractError::ProposalNotFound(proposal_id))?;
+    match proposal.status(env.block.time.seconds()) {
+        ProposalStatus::Passed => {}
+        ProposalStatus::Open => return Err(ContractError::VotingOpen(proposal_id)),
+        ProposalStatus::Rejected => return Err(ContractError::ProposalNotPassed(proposal_id)),
+        ProposalStatus::Executed => return Err(ContractError::ProposalExecuted(proposal_id)),
+    }
+
+    let mut response = Response::new()
+        .add_attribute("action", "execute_proposal")
+        .add_attribute("proposal_id", proposal_id.to_string());
+    match &proposal.action {
+        GovernanceAction::ChangeBeneficiary { beneficiary } => {
+            // supporters voted for it, so there is nobody left to accept.
+            // depositors still get the exit window
+            let now = env.block.time.seconds();
+            let effective_at = now + beneficiary_change_delay_at(deps.storage, now)?;
+            pending_beneficiary_remove(deps.storage);
+            beneficiary_change_store(
+                deps.storage,
+                &BeneficiaryChange {
+                    beneficiary: addr::canonicalize(deps.api, beneficiary)?,
+                    effective_at,
+                },
+            )?;
+            let mut config = config::read(deps.storage)?;
+            settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+            response = response
+                .add_attribute("new_beneficiary", beneficiary)
+                .add_attribute("effective_at", effective_at.to_string());
+        }
+        GovernanceAction::CloseFund {} => {
+            fund_closed_store(deps.storage, true)?;
+            response = response.add_attribute("fund_closed", "true");
+        }
+        GovernanceAction::ChangePayoutSplit { split } => {
+            let split = split
+                .iter()
+                .map(|share| {
+                    Ok(config::PayoutShare {
+                        recipient: addr::canonicalize(deps.api, &share.recipient)?,
+                        share: share.share,
+                    })
+                })
+                .collect::<Result<Vec<_>, ContractError>>()?;
+            // depositors get the same exit window as for a new beneficiary
+            let now = env.block.time.seconds();
+            let effective_at = now + beneficiary_change_delay_at(deps.storage, now)?;
+            payout_split_change_store(
+                deps.storage,
+                &PayoutSplitChange {
+                    split: split.clone(),
+                    effective_at,
+                },
+            )?;
+            settle_payout_split_change(deps.storage, &env)?;
+            response = response
+                .add_attribute("payout_split", split.len().to_string())
+                .add_attribute("effective_at", effective_at.to_string());
+        }
+    }
+    proposal.executed = true;
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(response)
+}
+
+pub fn set_governance_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    voting_period: Option<u64>,
+    quorum: Option<Decimal>,
+    threshold: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "set_governance_config",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    let mut governance_config = governance_config_read(deps.storage)?;
+    if let Some(voting_period) = voting_period {
+        governance_config.voting_period = voting_period;
+    }
+    if let Some(quorum) = quorum {
+        governance_config.quorum = quorum;
+    }
+    if let Some(threshold) = threshold {
+        governance_config.threshold = threshold;
+    }
+    if governance_config.voting_period == 0
+        || governance_config.quorum > Decimal::one()
+        || governance_config.threshold > Decimal::one()
+    {
+        return Err(ContractError::GovernanceConfigInvalid);
+    }
+    governance_config_store(deps.storage, &governance_config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_governance_config")
+        .add_attribute("voting_period", governance_config.voting_period.to_string())
+        .add_attribute("quorum", governance_config.quorum.to_string())
+        .add_attribute("threshold", governance_config.threshold.to_string()))
+}
+
+/// applies a voted payout split once its exit window has passed. returns the change if it is
+/// still pending
+pub fn settle_payout_split_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+) -> Result<Option<PayoutSplitChange>, ContractError> {
+    match payout_split_change_read(storage)? {
+        Some(change) if env.block.time.seconds() >= change.effective_at => {
+            payout_split_store(storage, &change.split)?;
+            payout_split_change_remove(storage);
+            Ok(None)
+        }
+        pending => Ok(pending),
+    }
+}
+
+fn validate_action(deps: Deps, action: &GovernanceAction) -> Result<(), ContractError> {
+    match action {
+        GovernanceAction::ChangeBeneficiary { beneficiary } => {
+            addr::validate(deps.api, beneficiary)?;
+        }
+        GovernanceAction::CloseFund {} => {}
+        GovernanceAction::ChangePayoutSplit { split } => {
+            let mut total = Decimal::zero();
+            for PayoutShare { recipient, share } in split {
+                addr::validate(deps.api, recipient)?;
+                if share.is_zero() {
+                    return Err(ContractError::InvalidPayoutSplit);
+                }
+                total = total.checked_add(*share)?;
+            }
+            if total > Decimal::one() {
+                return Err(ContractError::InvalidPayoutSplit);
+            }
+        }
+    }
+    Ok(())
+}
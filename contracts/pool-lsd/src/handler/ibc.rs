@@ -0,0 +1,253 @@
+use cosmwasm_std::*;
+use protobuf::{CodedInputStream, CodedOutputStream};
+use yieldpay_core::pool_msg::{IbcLifecycleComplete, IbcPayout};
+use yieldpay_core::pool_resp::IbcTransferStatus;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::config;
+use crate::config::{ibc_payout_remove, ibc_payout_store, stable_token_read, IbcPayoutSetting};
+use crate::error::ContractError;
+use crate::state::{
+    IbcTransfer, IBC_SEQUENCES, IBC_TRANSFERS, IBC_TRANSFER_COUNT, IBC_UNSETTLED,
+    PENDING_IBC_TRANSFER,
+};
+
+pub const IBC_TRANSFER_REPLY_ID: u64 = 2;
+pub const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+pub fn set_ibc_payout(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    ibc_payout: Option<IbcPayout>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_ibc_payout",
+        &info.sender,
+        &[Role::Beneficiary],
+    )?;
+
+    let response = Response::new().add_attribute("action", "set_ibc_payout");
+    match ibc_payout {
+        // ics20 only moves native coins
+        Some(_) if stable_token_read(deps.storage)?.is_some() => {
+            Err(ContractError::IbcPayoutNeedsNativeStable {})
+        }
+        Some(ibc_payout) => {
+            if ibc_payout.channel_id.is_empty()
+                || ibc_payout.remote_address.is_empty()
+                || ibc_payout.timeout_seconds == 0
+            {
+                return Err(ContractError::InvalidIbcPayout {});
+            }
+            let response = response
+                .add_attribute("channel_id", ibc_payout.channel_id.clone())
+                .add_attribute("remote_address", ibc_payout.remote_address.clone());
+            ibc_payout_store(
+                deps.storage,
+                &IbcPayoutSetting {
+                    beneficiary: config.beneficiary,
+                    ibc_payout,
+                },
+            )?;
+            Ok(response)
+        }
+        None => {
+            ibc_payout_remove(deps.storage);
+            Ok(response.add_attribute("ibc_payout", "off"))
+        }
+    }
+}
+
+/// records the beneficiary's payout for an earn and sends it over ibc
+pub fn payout(
+    storage: &mut dyn Storage,
+    env: &Env,
+    stable_denom: &str,
+    earn_id: u64,
+    beneficiary: Addr,
+    amount: Uint128,
+    ibc_payout: IbcPayout,
+) -> StdResult<SubMsg> {
+    let id = IBC_TRANSFER_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    IBC_TRANSFER_COUNT.save(storage, &id)?;
+    let transfer = IbcTransfer {
+        id,
+        earn_id,
+        beneficiary,
+        channel_id: ibc_payout.channel_id,
+        remote_address: ibc_payout.remote_address,
+        amount,
+        timeout_seconds: ibc_payout.timeout_seconds,
+        attempts: 0,
+        max_retries: ibc_payout.max_retries,
+        sequence: None,
+        status: IbcTransferStatus::Pending,
+    };
+    let unsettled = IBC_UNSETTLED.may_load(storage)?.unwrap_or_default();
+    IBC_UNSETTLED.save(storage, &(unsettled + amount))?;
+    send(storage, env, stable_denom, transfer)
+}
+
+fn send(
+    storage: &mut dyn Storage,
+    env: &Env,
+    stable_denom: &str,
+    mut transfer: IbcTransfer,
+) -> StdResult<SubMsg> {
+    transfer.attempts += 1;
+    transfer.sequence = None;
+    IBC_TRANSFERS.save(storage, transfer.id, &transfer)?;
+    PENDING_IBC_TRANSFER.save(storage, &transfer.id)?;
+    Ok(SubMsg::reply_on_success(
+        transfer_msg(
+            env,
+            &transfer.channel_id,
+            &transfer.remote_address,
+            &Coin {
+                denom: stable_denom.to_string(),
+                amount: transfer.amount,
+            },
+            env.block.time.plus_seconds(transfer.timeout_seconds),
+        )?,
+        IBC_TRANSFER_REPLY_ID,
+    ))
+}
+
+/// an ics20 MsgTransfer from the pool. IbcMsg::Transfer can't carry a memo, and without the
+/// `ibc_callback` one the ibc hooks middleware never calls the pool back with the ack or timeout
+pub fn transfer_msg(
+    env: &Env,
+    channel_id: &str,
+    receiver: &str,
+    token: &Coin,
+    timeout: Timestamp,
+) -> StdResult<CosmosMsg> {
+    let coin = encode(|output| {
+        output.write_string(1, &token.denom)?;
+        output.write_string(2, &token.amount.to_string())
+    })?;
+    let memo = format!(r#"{{"ibc_callback":"{}"}}"#, env.contract.address);
+    let value = encode(|output| {
+        output.write_string(1, "transfer")?;
+        output.write_string(2, channel_id)?;
+        output.write_bytes(3, &coin)?;
+        output.write_string(4, env.contract.address.as_str())?;
+        output.write_string(5, receiver)?;
+        output.write_uint64(7, timeout.nanos())?;
+        output.write_string(8, &memo)
+    })?;
+    Ok(CosmosMsg::Stargate {
+        type_url: MSG_TRANSFER_TYPE_URL.to_string(),
+        value: Binary::from(value),
+    })
+}
+
+fn encode(
+    write: impl FnOnce(&mut CodedOutputStream) -> protobuf::ProtobufResult<()>,
+) -> StdResult<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut output = CodedOutputStream::vec(&mut bytes);
+    write(&mut output)
+        .and_then(|_| output.flush())
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    drop(output);
+    Ok(bytes)
+}
+
+/// keeps the packet sequence from MsgTransferResponse so the callback can find the transfer
+/// without a sequence no ack or timeout could ever be matched to the transfer, leaving it
+/// unsettled for good, so the payout is reverted instead
+pub fn transfer_sent(deps: DepsMut, result: SubMsgResult) -> Result<Response, ContractError> {
+    let sequence = result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .and_then(|data| parse_sequence(data.as_slice()))
+        .ok_or(ContractError::IbcTransferSequenceMissing {})?;
+    let id = PENDING_IBC_TRANSFER.load(deps.storage)?;
+    PENDING_IBC_TRANSFER.remove(deps.storage);
+
+    let mut transfer = IBC_TRANSFERS.load(deps.storage, id)?;
+    transfer.sequence = Some(sequence);
+    IBC_TRANSFERS.save(deps.storage, id, &transfer)?;
+    IBC_SEQUENCES.save(deps.storage, (&transfer.channel_id, sequence), &id)?;
+    Ok(Response::new()
+        .add_attribute("ibc_transfer", id.to_string())
+        .add_attribute("sequence", sequence.to_string()))
+}
+
+/// MsgTransferResponse is just `uint64 sequence = 1`
+fn parse_sequence(data: &[u8]) -> Option<u64> {
+    let mut input = CodedInputStream::from_bytes(data);
+    while !input.eof().ok()? {
+        let (field, wire_type) = input.read_tag_unpack().ok()?;
+        if field == 1 {
+            return input.read_uint64().ok();
+        }
+        input.skip_field(wire_type).ok()?;
+    }
+    None
+}
+
+/// the ack or timeout for a transfer. failed ones have been refunded to the pool by then,
+/// so they are resent, or paid to the beneficiary here once out of retries
+pub fn lifecycle_complete(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcLifecycleComplete,
+) -> Result<Response, ContractError> {
+    let (channel, sequence, delivered) = match msg {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+    };
+    let response = Response::new().add_attribute("action", "ibc_lifecycle_complete");
+    // not one of the payouts
+    let id = match IBC_SEQUENCES.may_load(deps.storage, (&channel, sequence))? {
+        Some(id) => id,
+        None => return Ok(response),
+    };
+    IBC_SEQUENCES.remove(deps.storage, (&channel, sequence));
+    let mut transfer = IBC_TRANSFERS.load(deps.storage, id)?;
+    let response = response.add_attribute("ibc_transfer", id.to_string());
+
+    if delivered {
+        transfer.status = IbcTransferStatus::Delivered;
+        IBC_TRANSFERS.save(deps.storage, id, &transfer)?;
+        settle(deps.storage, transfer.amount)?;
+        return Ok(response.add_attribute("status", "delivered"));
+    }
+    let config = config::read(deps.storage)?;
+    if transfer.attempts <= transfer.max_retries {
+        let retry = send(deps.storage, &env, &config.stable_denom, transfer)?;
+        return Ok(response
+            .add_submessage(retry)
+            .add_attribute("status", "retrying"));
+    }
+    transfer.status = IbcTransferStatus::PaidLocally;
+    IBC_TRANSFERS.save(deps.storage, id, &transfer)?;
+    settle(deps.storage, transfer.amount)?;
+    Ok(response
+        .add_message(BankMsg::Send {
+            to_address: transfer.beneficiary.to_string(),
+            amount: vec![Coin {
+                denom: config.stable_denom,
+                amount: transfer.amount,
+            }],
+        })
+        .add_attribute("status", "paid_locally"))
+}
+
+/// a transfer that has been delivered or paid locally no longer needs its stable kept back
+fn settle(storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+    let unsettled = IBC_UNSETTLED.may_load(storage)?.unwrap_or_default();
+    IBC_UNSETTLED.save(storage, &unsettled.saturating_sub(amount))
+}
@@ -0,0 +1,71 @@
+use cosmwasm_std::*;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::config;
+use crate::config::{
+    insurance_reserve_read, insurance_reserve_store, liquid_buffer_read, liquid_buffer_store,
+    InsuranceReserve,
+};
+use crate::error::ContractError;
+use crate::handler::core::pool_earnings;
+
+pub fn set_insurance_reserve(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    ratio: Decimal,
+    cap: Uint128,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "set_insurance_reserve",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    if ratio > Decimal::one() {
+        return Err(ContractError::InvalidInsuranceRatio);
+    }
+
+    let mut reserve = insurance_reserve_read(deps.storage)?;
+    reserve.ratio = ratio;
+    reserve.cap = cap;
+    insurance_reserve_store(deps.storage, &reserve)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_insurance_reserve")
+        .add_attribute("ratio", ratio.to_string())
+        .add_attribute("cap", cap.to_string()))
+}
+
+/// the reserve's slice of `owed`, up to its cap
+pub fn slice(reserve: &InsuranceReserve, owed: Uint128) -> Uint128 {
+    let slice = owed * reserve.ratio;
+    if reserve.cap.is_zero() {
+        slice
+    } else {
+        slice.min(reserve.cap.saturating_sub(reserve.held))
+    }
+}
+
+/// if the pool is worth less than the DP supply, moves what the reserve can spare into the liquid
+/// buffer, where it counts towards the pool's value and pays redemptions. returns what was used
+pub fn cover(deps: DepsMut, env: &Env, config: &config::Config) -> Result<Uint128, ContractError> {
+    let mut reserve = insurance_reserve_read(deps.storage)?;
+    if reserve.held.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let earnings = pool_earnings(deps.as_ref(), env, config)?;
+    let used = earnings
+        .dp_total_supply
+        .saturating_sub(earnings.pool_value_locked)
+        .min(reserve.held);
+    if !used.is_zero() {
+        reserve.held -= used;
+        reserve.total_used += used;
+        insurance_reserve_store(deps.storage, &reserve)?;
+        let mut buffer = liquid_buffer_read(deps.storage)?;
+        buffer.held += used;
+        liquid_buffer_store(deps.storage, &buffer)?;
+    }
+    Ok(used)
+}
@@ -0,0 +1,117 @@
+use cosmwasm_std::*;
+
+use crate::config;
+use crate::error::ContractError;
+use crate::stable;
+use crate::state::{Matching, MATCHES};
+
+pub fn pledge(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ratio: Decimal,
+    cap: Uint128,
+    expires_at: u64,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    let received = stable::native_received(deps.as_ref(), &config, &info.funds)?;
+
+    pledge_stable(deps, env, info.sender, received, ratio, cap, expires_at)
+}
+
+/// adds `received` of the stable, which the pool already holds, to the sponsor's budget
+pub fn pledge_stable(
+    deps: DepsMut,
+    env: Env,
+    sponsor: Addr,
+    received: Uint128,
+    ratio: Decimal,
+    cap: Uint128,
+    expires_at: u64,
+) -> Result<Response, ContractError> {
+    if ratio.is_zero() || expires_at <= env.block.time.seconds() {
+        return Err(ContractError::InvalidMatch {});
+    }
+    if received.is_zero() {
+        return Err(ContractError::NotAllowZeroAmount {});
+    }
+    let (budget, released) = match MATCHES.may_load(deps.storage, &sponsor)? {
+        Some(matching) => (matching.budget + received, matching.released),
+        None => (received, Uint128::zero()),
+    };
+    MATCHES.save(
+        deps.storage,
+        &sponsor,
+        &Matching {
+            ratio,
+            cap,
+            expires_at,
+            budget,
+            released,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pledge_match")
+        .add_attribute("sponsor", sponsor.to_string())
+        .add_attribute("amount", received.to_string())
+        .add_attribute("budget", budget.to_string())
+        .add_attribute("ratio", ratio.to_string())
+        .add_attribute("cap", cap.to_string())
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+pub fn reclaim(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let matching = MATCHES
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NoMatch(info.sender.to_string()))?;
+    if env.block.time.seconds() < matching.expires_at {
+        return Err(ContractError::MatchNotExpired {
+            expires_at: matching.expires_at,
+        });
+    }
+    MATCHES.remove(deps.storage, &info.sender);
+
+    let config = config::read(deps.storage)?;
+    let mut response = Response::new()
+        .add_attribute("action", "reclaim_match")
+        .add_attribute("sponsor", info.sender.to_string())
+        .add_attribute("amount", matching.budget.to_string());
+    if !matching.budget.is_zero() {
+        response = response.add_message(stable::transfer_msg(
+            &stable::read(deps.as_ref(), &config)?,
+            &info.sender,
+            matching.budget,
+        )?);
+    }
+    Ok(response)
+}
+
+/// takes each live match's share of `earned` out of its budget.
+/// returns: the total to pay the beneficiary on top of the yield
+pub fn release(storage: &mut dyn Storage, env: &Env, earned: Uint128) -> StdResult<Uint128> {
+    let now = env.block.time.seconds();
+    let live = MATCHES
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(_, matching)| now < matching.expires_at)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut matched = Uint128::zero();
+    for (sponsor, mut matching) in live {
+        let mut amount = (earned * matching.ratio).min(matching.budget);
+        if !matching.cap.is_zero() {
+            amount = amount.min(matching.cap);
+        }
+        if amount.is_zero() {
+            continue;
+        }
+        matching.budget -= amount;
+        matching.released += amount;
+        MATCHES.save(storage, &sponsor, &matching)?;
+        matched += amount;
+    }
+    Ok(matched)
+}
@@ -1,2 +1,14 @@
+pub mod buffer;
+pub mod caps;
+pub mod convert;
 pub mod core;
+pub mod donate;
+pub mod governance;
+pub mod ibc;
+pub mod insurance;
+pub mod matching;
+pub mod ownership;
 pub mod query;
+pub mod strategy;
+pub mod swap;
+pub mod sweep;
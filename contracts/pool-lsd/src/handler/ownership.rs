@@ -0,0 +1,298 @@
+use cosmwasm_std::*;
+
+use crate::addr;
+use crate::config;
+use crate::config::{
+    beneficiary_change_delay_at, beneficiary_change_delay_change_remove,
+    beneficiary_change_delay_change_store, beneficiary_change_delay_store, beneficiary_change_read,
+    beneficiary_change_remove, beneficiary_change_store, pending_beneficiary_read,
+    pending_beneficiary_remove, pending_beneficiary_store, pending_owner_read,
+    pending_owner_remove, pending_owner_store, BeneficiaryChange, BeneficiaryChangeDelayChange,
+    Config, PendingTransfer, MIN_BENEFICIARY_CHANGE_DELAY,
+};
+use crate::error::ContractError;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+/// longest a proposed owner has to accept before the proposal lapses
+pub const MAX_PROPOSAL_EXPIRY: u64 = 14 * 86_400;
+/// how long a beneficiary proposed via Configure has to accept
+pub const BENEFICIARY_PROPOSAL_EXPIRY: u64 = 7 * 86_400;
+
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "propose_new_owner",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    if expires_in == 0 || expires_in > MAX_PROPOSAL_EXPIRY {
+        return Err(ContractError::TransferExpiryInvalid {
+            max: MAX_PROPOSAL_EXPIRY,
+        });
+    }
+    let new_owner = addr::validate(deps.api, &owner)?;
+    let expires_at = env.block.time.seconds() + expires_in;
+    pending_owner_store(
+        deps.storage,
+        &PendingTransfer {
+            proposed: addr::canonicalize(deps.api, new_owner.as_str())?,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("proposed_owner", new_owner)
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+pub fn drop_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "drop_ownership_proposal",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    pending_owner_remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_ownership_proposal"))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = pending_owner_read(deps.storage)?
+        .ok_or_else(|| ContractError::NoPendingTransfer("owner".to_string()))?;
+    if pending.proposed != addr::canonicalize(deps.api, info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {
+            action: "accept_ownership".to_string(),
+            expected: addr::humanize(deps.api, &pending.proposed)?.to_string(),
+            actual: info.sender.to_string(),
+        });
+    }
+    if env.block.time.seconds() > pending.expires_at {
+        return Err(ContractError::TransferExpired("owner".to_string()));
+    }
+
+    let mut config = config::read(deps.storage)?;
+    let previous_owner = addr::humanize(deps.api, &config.owner)?;
+    config.owner = pending.proposed;
+    config::store(deps.storage, &config)?;
+    roles::set_holder(deps.storage, Role::Owner, &info.sender)?;
+    pending_owner_remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", info.sender))
+}
+
+/// records a beneficiary handover. Called from `configure` once the sender has been authorized
+pub fn propose_new_beneficiary(
+    deps: DepsMut,
+    env: &Env,
+    beneficiary: &str,
+) -> Result<u64, ContractError> {
+    let new_beneficiary = addr::validate(deps.api, beneficiary)?;
+    let expires_at = env.block.time.seconds() + BENEFICIARY_PROPOSAL_EXPIRY;
+    pending_beneficiary_store(
+        deps.storage,
+        &PendingTransfer {
+            proposed: addr::canonicalize(deps.api, new_beneficiary.as_str())?,
+            expires_at,
+        },
+    )?;
+    Ok(expires_at)
+}
+
+pub fn drop_beneficiary_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "drop_beneficiary_proposal",
+        &info.sender,
+        &[Role::Beneficiary, Role::Owner],
+    )?;
+    pending_beneficiary_remove(deps.storage);
+    beneficiary_change_remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_beneficiary_proposal"))
+}
+
+pub fn accept_beneficiary(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = pending_beneficiary_read(deps.storage)?
+        .ok_or_else(|| ContractError::NoPendingTransfer("beneficiary".to_string()))?;
+    if pending.proposed != addr::canonicalize(deps.api, info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {
+            action: "accept_beneficiary".to_string(),
+            expected: addr::humanize(deps.api, &pending.proposed)?.to_string(),
+            actual: info.sender.to_string(),
+        });
+    }
+    if env.block.time.seconds() > pending.expires_at {
+        return Err(ContractError::TransferExpired("beneficiary".to_string()));
+    }
+
+    // depositors get `delay` seconds to leave before yield goes to the new beneficiary
+    let now = env.block.time.seconds();
+    let effective_at = now + beneficiary_change_delay_at(deps.storage, now)?;
+    beneficiary_change_store(
+        deps.storage,
+        &BeneficiaryChange {
+            beneficiary: pending.proposed,
+            effective_at,
+        },
+    )?;
+    pending_beneficiary_remove(deps.storage);
+
+    let mut config = config::read(deps.storage)?;
+    let previous_beneficiary = addr::humanize(deps.api, &config.beneficiary)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_beneficiary")
+        .add_attribute("previous_beneficiary", previous_beneficiary)
+        .add_attribute("beneficiary", info.sender)
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+pub fn apply_beneficiary_change(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let change = beneficiary_change_read(deps.storage)?
+        .ok_or_else(|| ContractError::NoPendingTransfer("beneficiary".to_string()))?;
+    if env.block.time.seconds() < change.effective_at {
+        return Err(ContractError::BeneficiaryChangeNotDue {
+            effective_at: change.effective_at,
+        });
+    }
+    let mut config = config::read(deps.storage)?;
+    let previous_beneficiary = addr::humanize(deps.api, &config.beneficiary)?;
+    settle_beneficiary_change(deps.storage, deps.api, &env, &mut config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "apply_beneficiary_change")
+        .add_attribute("previous_beneficiary", previous_beneficiary)
+        .add_attribute(
+            "beneficiary",
+            addr::humanize(deps.api, &config.beneficiary)?,
+        ))
+}
+
+/// a longer delay applies at once. a shorter one waits out the current delay, so nobody inside
+/// the exit window loses any of it
+pub fn set_beneficiary_change_delay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delay: u64,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(
+        deps.storage,
+        "set_beneficiary_change_delay",
+        &info.sender,
+        &[Role::Owner],
+    )?;
+    if delay < MIN_BENEFICIARY_CHANGE_DELAY {
+        return Err(ContractError::BeneficiaryChangeDelayTooShort {
+            min: MIN_BENEFICIARY_CHANGE_DELAY,
+        });
+    }
+
+    let now = env.block.time.seconds();
+    let current = beneficiary_change_delay_at(deps.storage, now)?;
+    beneficiary_change_delay_change_remove(deps.storage);
+    let effective_at = if delay >= current {
+        beneficiary_change_delay_store(deps.storage, delay)?;
+        now
+    } else {
+        beneficiary_change_delay_store(deps.storage, current)?;
+        beneficiary_change_delay_change_store(
+            deps.storage,
+            &BeneficiaryChangeDelayChange {
+                delay,
+                effective_at: now + current,
+            },
+        )?;
+        now + current
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "set_beneficiary_change_delay")
+        .add_attribute("delay", delay.to_string())
+        .add_attribute("effective_at", effective_at.to_string()))
+}
+
+/// applies a queued beneficiary change once it is due.
+/// returns the change if it is still waiting out the exit window
+pub fn settle_beneficiary_change(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    config: &mut Config,
+) -> Result<Option<BeneficiaryChange>, ContractError> {
+    match beneficiary_change_read(storage)? {
+        Some(change) if env.block.time.seconds() >= change.effective_at => {
+            roles::set_holder(
+                storage,
+                Role::Beneficiary,
+                &addr::humanize(api, &change.beneficiary)?,
+            )?;
+            config.beneficiary = change.beneficiary;
+            config::store(storage, config)?;
+            beneficiary_change_remove(storage);
+            Ok(None)
+        }
+        pending => Ok(pending),
+    }
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::grant(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    roles::revoke(deps.storage, &info.sender, role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
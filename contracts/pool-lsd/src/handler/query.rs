@@ -1,178 +1,176 @@
 //use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::*;
 //use gofund_ust_core::pool_resp as resp;
+use cw_storage_plus::Bound;
+use yieldpay_core::pool_msg::PayoutShare;
 use yieldpay_core::pool_resp::{
-    ClaimableRewardResponse, DepositAmountResponse, FeeResponse, TotalDepositAmountResponse,
+    BeneficiaryChangeResponse, ClaimableRewardResponse, DepositAmountResponse, FeeResponse,
+    GovernanceConfigResponse, PayoutSplitChangeResponse, PayoutSplitResponse, PendingTransfer,
+    PendingTransfersResponse, ProposalResponse, ProposalsResponse, StakedResponse,
+    TotalDepositAmountResponse,
 };
+use yieldpay_core::pool_resp::{
+    DepositCapacityResponse, DepositRouteResponse, DepositRoutesResponse, DonorResponse,
+    DonorsResponse, EarnHistoryResponse, EarnRecordResponse, EndowmentResponse, FeeHistoryResponse,
+    FeeRecordResponse, HarvestConfigResponse, IbcPayoutResponse, IbcTransferResponse,
+    IbcTransfersResponse, InsuranceReserveResponse, LiquidBufferResponse, MatchResponse,
+    MatchesResponse, PayoutResponse, StrategiesResponse, StrategyResponse, SwapPayoutResponse,
+    SwapRecordResponse, WhitelistedMarketResponse, WhitelistedMarketsResponse,
+    WhitelistedPairsResponse, YieldHookResponse,
+};
+use yieldpay_core::roles;
+use yieldpay_core::roles::{Role, RoleMembersResponse, RolesResponse};
 
 use std::ops::{Mul, Sub};
 use yieldpay_core::token;
 
+use crate::addr;
 use crate::config;
-use crate::config::last_claimed_read;
-use crate::handler::core::calc_fee;
+use crate::config::{
+    accrued_read, beneficiary_change_delay_at, beneficiary_change_read, deposit_caps_read,
+    deposits_paused_read, donor_collection_read, earn_limits_read, endowment_read,
+    fund_closed_read, governance_config_read, harvest_config_read, ibc_payout_read,
+    insurance_reserve_read, last_claimed_read, liquid_buffer_read, payout_split_change_read,
+    payout_split_read, pending_beneficiary_read, pending_owner_read, swap_payout_read,
+    yield_hook_enabled,
+};
+use crate::error::ContractError;
+use crate::handler::buffer::{dp_supply, target_amount};
+use crate::handler::caps::{address_remaining, tvl_remaining};
+use crate::handler::core::{accrue, last_payout_at, next_earn_allowed_at, pool_earnings};
+use crate::handler::insurance::slice;
+use crate::handler::strategy::positions;
 use crate::querier::anchor;
+use crate::state::{
+    EarnRecord, FeeRecord, Payout, Proposal, DEPOSIT_ROUTES, DONORS, EARN_HISTORY, FEE_HISTORY,
+    IBC_TRANSFERS, MARKET_WHITELIST, MATCHES, PAIR_WHITELIST, PROPOSALS, STAKED, TOTAL_DONATED,
+    TOTAL_STAKED, VOTES,
+};
 
-pub fn deposit_amount(deps: Deps, _env: Env, owner: String) -> StdResult<Binary> {
-    let config: config::Config = config::read(deps.storage).unwrap();
-
-    to_binary(&DepositAmountResponse {
-        amount: token::balance_of(
-            deps,
-            deps.api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
-            owner,
-        )?,
-    })
+pub fn deposit_amount(deps: Deps, _env: Env, owner: String) -> Result<Binary, ContractError> {
+    let config: config::Config = config::read(deps.storage)?;
+
+    Ok(to_binary(&DepositAmountResponse {
+        amount: token::balance_of(deps, addr::dp_token(deps.api, &config)?.to_string(), owner)?,
+    })?)
 }
 
-pub fn total_deposit_amount(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let config: config::Config = config::read(deps.storage).unwrap();
+pub fn total_deposit_amount(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config: config::Config = config::read(deps.storage)?;
 
-    to_binary(&TotalDepositAmountResponse {
-        amount: token::total_supply(
-            deps,
-            deps.api
-                .addr_humanize(&config.dp_token)
-                .unwrap()
-                .to_string(),
-        )?,
-    })
+    Ok(to_binary(&TotalDepositAmountResponse {
+        amount: token::total_supply(deps, addr::dp_token(deps.api, &config)?.to_string())?,
+    })?)
 }
 
-pub fn config(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let config: config::Config = config::read(deps.storage).unwrap();
+pub fn config(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let mut config: config::Config = config::read(deps.storage)?;
+    if let Some(change) = beneficiary_change_read(deps.storage)? {
+        if env.block.time.seconds() >= change.effective_at {
+            config.beneficiary = change.beneficiary;
+        }
+    }
     let dp_token_str = if config.dp_token == CanonicalAddr::from(vec![]) {
         "".to_string()
     } else {
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string()
+        addr::dp_token(deps.api, &config)?.to_string()
     };
     let nft_contract = if let Some(contract) = config.nft_contract {
-        Some(deps.api.addr_humanize(&contract)?.to_string())
+        Some(addr::humanize(deps.api, &contract)?.to_string())
     } else {
         None
     };
-    to_binary(&yieldpay_core::pool_anchor_response::ConfigResponse {
-        pool_name: config.pool_name,
-        pool_title: config.pool_title,
-        pool_description: config.pool_description,
-        beneficiary: deps
-            .api
-            .addr_humanize(&config.beneficiary)
-            .unwrap()
-            .to_string(),
-        fee_collector: deps
-            .api
-            .addr_humanize(&config.fee_collector)
-            .unwrap()
-            .to_string(),
-        money_market: deps
-            .api
-            .addr_humanize(&config.money_market)
-            .unwrap()
-            .to_string(),
-        stable_denom: config.stable_denom,
-        anchor_token: deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
-        dp_token: dp_token_str,
-        owner_can_change_config: config.owner_can_change_config,
-        owner: deps.api.addr_humanize(&config.owner).unwrap().to_string(),
-
-        nft_contract,
-        nft_collection_active: config.nft_collection_active,
-        nft_collection_redeemed: config.nft_collection_redeemed,
-    })
+    Ok(to_binary(
+        &yieldpay_core::pool_anchor_response::ConfigResponse {
+            pool_name: config.pool_name,
+            pool_title: config.pool_title,
+            pool_description: config.pool_description,
+            beneficiary: addr::humanize(deps.api, &config.beneficiary)?.to_string(),
+            fee_collector: addr::humanize(deps.api, &config.fee_collector)?.to_string(),
+            money_market: addr::humanize(deps.api, &config.money_market)?.to_string(),
+            stable_denom: config.stable_denom,
+            anchor_token: addr::humanize(deps.api, &config.atoken)?.to_string(),
+            dp_token: dp_token_str,
+            owner_can_change_config: config.owner_can_change_config,
+            owner: addr::humanize(deps.api, &config.owner)?.to_string(),
+
+            nft_contract,
+            nft_collection_active: config.nft_collection_active,
+            nft_collection_redeemed: config.nft_collection_redeemed,
+        },
+    )?)
 }
 #[allow(dead_code)]
-pub fn debug_anchor_epoch_state(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn debug_anchor_epoch_state(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
 
     // assets
     let epoch_state = anchor::epoch_state(deps, &config.money_market)?;
-    to_binary(&epoch_state)
+    Ok(to_binary(&epoch_state)?)
 }
 
 #[allow(dead_code)]
-pub fn debug_atoken_balance(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn debug_atoken_balance(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
 
     // assets
     let _epoch_state = anchor::epoch_state(deps, &config.money_market)?;
     let atoken_balance = token::balance_of(
         deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
+        addr::humanize(deps.api, &config.atoken)?.to_string(),
         env.contract.address.to_string(),
     )?;
-    to_binary(&atoken_balance)
+    Ok(to_binary(&atoken_balance)?)
 }
 
 #[allow(dead_code)]
-pub fn debug_dp_total_supply(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn debug_dp_total_supply(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
 
     // assets
     let _epoch_state = anchor::epoch_state(deps, &config.money_market)?;
     let _atoken_balance = token::balance_of(
         deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
+        addr::humanize(deps.api, &config.atoken)?.to_string(),
         env.contract.address.to_string(),
     )?;
-    let dp_total_supply = token::total_supply(
-        deps,
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string(),
-    )?;
-    to_binary(&dp_total_supply)
+    let dp_total_supply =
+        token::total_supply(deps, addr::dp_token(deps.api, &config)?.to_string())?;
+    Ok(to_binary(&dp_total_supply)?)
 }
 
 #[allow(dead_code)]
-pub fn debug_pool_value_locked(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn debug_pool_value_locked(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
 
     // assets
     let epoch_state = anchor::epoch_state(deps, &config.money_market)?;
     let atoken_balance = token::balance_of(
         deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
+        addr::humanize(deps.api, &config.atoken)?.to_string(),
         env.contract.address.to_string(),
     )?;
-    let _dp_total_supply = token::total_supply(
-        deps,
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string(),
-    )?;
+    let _dp_total_supply =
+        token::total_supply(deps, addr::dp_token(deps.api, &config)?.to_string())?;
 
     let pool_value_locked = atoken_balance.mul(epoch_state.exchange_rate);
 
-    to_binary(&pool_value_locked)
+    Ok(to_binary(&pool_value_locked)?)
 }
 
 #[allow(dead_code)]
-pub fn debug_earnable(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn debug_earnable(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
 
     // assets
     let epoch_state = anchor::epoch_state(deps, &config.money_market)?;
     let atoken_balance = token::balance_of(
         deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
+        addr::humanize(deps.api, &config.atoken)?.to_string(),
         env.contract.address.to_string(),
     )?;
-    let dp_total_supply = token::total_supply(
-        deps,
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string(),
-    )?;
+    let dp_total_supply =
+        token::total_supply(deps, addr::dp_token(deps.api, &config)?.to_string())?;
 
     let pool_value_locked = atoken_balance.mul(epoch_state.exchange_rate);
     let earnable = if dp_total_supply >= pool_value_locked {
@@ -181,11 +179,11 @@ pub fn debug_earnable(deps: Deps, env: Env) -> StdResult<Binary> {
         pool_value_locked.sub(dp_total_supply)
     };
 
-    to_binary(&earnable)
+    Ok(to_binary(&earnable)?)
 }
 /*
 pub fn debug_redeem(deps: Deps, _env: Env, _sender: String, amount: Uint128) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+    let config = config::read(deps.storage)?;
 
     if amount.is_zero() {
         return to_binary("amount zero");
@@ -205,103 +203,597 @@ pub fn debug_redeem(deps: Deps, _env: Env, _sender: String, amount: Uint128) ->
     })
 }
 */
-pub fn last_claimed(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let last_claimed = last_claimed_read(deps.storage).unwrap();
+pub fn last_claimed(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let last_claimed = last_claimed_read(deps.storage)?;
 
-    to_binary(&last_claimed)
+    Ok(to_binary(&last_claimed)?)
 }
 
-pub fn fee(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
-
-    // assets
-    let epoch_state = anchor::epoch_state(deps, &config.money_market)?;
-    let atoken_balance = token::balance_of(
-        deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
-        env.contract.address.to_string(),
-    )?;
-    let dp_total_supply = token::total_supply(
-        deps,
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string(),
-    )?;
-
-    let pool_value_locked = atoken_balance.mul(epoch_state.exchange_rate);
+pub fn fee(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let earnings = pool_earnings(deps, &env, &config)?;
+    let (_, fee, updated_last_claimed, accrued) = accrue(
+        earnings.earnable,
+        &config,
+        env.block.time.seconds(),
+        last_claimed_read(deps.storage)?,
+        accrued_read(deps.storage)?,
+    );
+    Ok(to_binary(&FeeResponse {
+        fee_model: config.fee_model,
+        fee_period: config.fee_period,
+        period_start: updated_last_claimed.period_start,
+        fees_collected: updated_last_claimed.fees_collected - fee,
+        fee,
+        accrued: accrued.fees.min(earnings.earnable),
+    })?)
+}
 
-    // let earnable = pool_value_locked.sub(dp_total_supply);
-    let earnable = if dp_total_supply >= pool_value_locked {
-        Uint128::zero()
-    } else {
-        pool_value_locked.sub(dp_total_supply)
-    };
-    let last_claimed = last_claimed_read(deps.storage).unwrap();
-
-    let (fee, _updated_last_claimed) = calc_fee(
-        earnable,
-        config.fee_amount,
-        config.fee_max,
-        config.fee_reset_every_num_blocks,
-        env.block.height,
-        last_claimed,
+pub fn claimable(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let limits = earn_limits_read(deps.storage)?;
+    let earnings = pool_earnings(deps, &env, &config)?;
+    let (_, fee, updated_last_claimed, accrued) = accrue(
+        earnings.earnable,
+        &config,
+        env.block.time.seconds(),
+        last_claimed_read(deps.storage)?,
+        accrued_read(deps.storage)?,
     );
-    to_binary(&FeeResponse {
-        fee_amount: config.fee_amount,
-        fee_max: config.fee_max,
-        fee_reset_every_num_blocks: config.fee_reset_every_num_blocks,
+    let owed = accrued
+        .beneficiary
+        .min(earnings.earnable.saturating_sub(accrued.fees));
+    let insured = slice(&insurance_reserve_read(deps.storage)?, owed);
+    Ok(to_binary(&ClaimableRewardResponse {
+        total_value: earnings.dp_total_supply,
+        pool_value: earnings.pool_value_locked,
+        earned: updated_last_claimed.total_earned_at_last_claimed,
+        claimable: owed - insured - (owed - insured) * endowment_read(deps.storage)?.retain_ratio,
         fee,
-    })
+        next_earn_allowed_at: next_earn_allowed_at(deps.storage, &limits)?,
+        min_earn_amount: limits.min_earn_amount,
+    })?)
 }
 
-pub fn claimable(deps: Deps, env: Env) -> StdResult<Binary> {
-    let config = config::read(deps.storage).unwrap();
+pub fn pending_transfers(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let humanize =
+        |pending: Option<config::PendingTransfer>| -> Result<Option<PendingTransfer>, ContractError> {
+            pending
+                .map(|p| {
+                    Ok(PendingTransfer {
+                        proposed: addr::humanize(deps.api, &p.proposed)?.to_string(),
+                        expires_at: p.expires_at,
+                    })
+                })
+                .transpose()
+        };
+    Ok(to_binary(&PendingTransfersResponse {
+        owner: humanize(pending_owner_read(deps.storage)?)?,
+        beneficiary: humanize(pending_beneficiary_read(deps.storage)?)?,
+    })?)
+}
 
-    // assets
-    let epoch_state = anchor::epoch_state(deps, &config.money_market)?;
-    let atoken_balance = token::balance_of(
-        deps,
-        deps.api.addr_humanize(&config.atoken).unwrap().to_string(),
-        env.contract.address.to_string(),
-    )?;
-    let dp_total_supply = token::total_supply(
-        deps,
-        deps.api
-            .addr_humanize(&config.dp_token)
-            .unwrap()
-            .to_string(),
-    )?;
+pub fn beneficiary_change(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let now = env.block.time.seconds();
+    // once due, the change is as good as made
+    let change = beneficiary_change_read(deps.storage)?
+        .filter(|change| now < change.effective_at)
+        .map(
+            |change| -> Result<BeneficiaryChangeResponse, ContractError> {
+                Ok(BeneficiaryChangeResponse {
+                    current_beneficiary: addr::humanize(deps.api, &config.beneficiary)?.to_string(),
+                    new_beneficiary: addr::humanize(deps.api, &change.beneficiary)?.to_string(),
+                    effective_at: change.effective_at,
+                    delay: beneficiary_change_delay_at(deps.storage, now)?,
+                })
+            },
+        )
+        .transpose()?;
+    Ok(to_binary(&change)?)
+}
 
-    let pool_value_locked = atoken_balance.mul(epoch_state.exchange_rate);
-    let earnable = if dp_total_supply >= pool_value_locked {
-        Uint128::zero()
-    } else {
-        pool_value_locked.sub(dp_total_supply)
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn governance_config(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let governance_config = governance_config_read(deps.storage)?;
+    Ok(to_binary(&GovernanceConfigResponse {
+        voting_period: governance_config.voting_period,
+        quorum: governance_config.quorum,
+        threshold: governance_config.threshold,
+        total_staked: TOTAL_STAKED.may_load(deps.storage)?.unwrap_or_default(),
+        fund_closed: fund_closed_read(deps.storage)?,
+    })?)
+}
+
+pub fn harvest_config(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    Ok(to_binary(&HarvestConfigResponse {
+        harvest: harvest_config_read(deps.storage)?,
+        last_payout_at: last_payout_at(deps.storage)?,
+    })?)
+}
+
+pub fn liquid_buffer(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let buffer = liquid_buffer_read(deps.storage)?;
+    Ok(to_binary(&LiquidBufferResponse {
+        target: buffer.target,
+        target_amount: target_amount(&buffer, dp_supply(deps, &config)?),
+        held: buffer.held,
+    })?)
+}
+
+pub fn strategies(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let mut strategies = vec![];
+    let mut total_value = Uint128::zero();
+    for position in positions(deps, &env, &config)? {
+        total_value += position.value();
+        strategies.push(StrategyResponse {
+            money_market: addr::humanize(deps.api, &position.money_market)?.to_string(),
+            atoken: addr::humanize(deps.api, &position.atoken)?.to_string(),
+            weight: position.weight,
+            exchange_rate: position.exchange_rate,
+            atokens: position.atokens,
+            value: position.value(),
+        });
+    }
+    Ok(to_binary(&StrategiesResponse {
+        strategies,
+        total_value,
+    })?)
+}
+
+pub fn deposit_capacity(
+    deps: Deps,
+    _env: Env,
+    address: Option<String>,
+) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let caps = deposit_caps_read(deps.storage)?;
+    let total_supply = dp_supply(deps, &config)?;
+    let address_remaining = match address {
+        Some(address) => {
+            address_remaining(deps, &config, &caps, &addr::validate(deps.api, &address)?)?
+        }
+        None => None,
     };
+    let remaining = match (tvl_remaining(&caps, total_supply), address_remaining) {
+        (Some(tvl), Some(address)) => Some(tvl.min(address)),
+        (tvl, address) => tvl.or(address),
+    };
+    Ok(to_binary(&DepositCapacityResponse {
+        tvl_cap: caps.tvl_cap,
+        address_cap: caps.address_cap,
+        total_supply,
+        remaining,
+        paused: deposits_paused_read(deps.storage)?,
+    })?)
+}
 
-    let last_claimed = last_claimed_read(deps.storage).unwrap();
+pub fn insurance_reserve(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let reserve = insurance_reserve_read(deps.storage)?;
+    let earnings = pool_earnings(deps, &env, &config)?;
+    Ok(to_binary(&InsuranceReserveResponse {
+        ratio: reserve.ratio,
+        cap: reserve.cap,
+        held: reserve.held,
+        total_funded: reserve.total_funded,
+        total_used: reserve.total_used,
+        shortfall: earnings
+            .dp_total_supply
+            .saturating_sub(earnings.pool_value_locked),
+    })?)
+}
 
-    let (fee, updated_last_claimed) = calc_fee(
-        earnable,
-        config.fee_amount,
-        config.fee_max,
-        config.fee_reset_every_num_blocks,
-        env.block.height,
-        last_claimed,
-    );
-    let claimable = if fee.is_zero() {
-        earnable
-    } else if earnable > fee {
-        earnable.sub(fee)
-    } else {
-        Uint128::zero()
+pub fn endowment(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let endowment = endowment_read(deps.storage)?;
+    Ok(to_binary(&EndowmentResponse {
+        retain_ratio: endowment.retain_ratio,
+        principal: endowment.principal,
+        last_retained: endowment.last_retained,
+        first_retained_at: endowment.first_retained_at,
+        last_retained_at: endowment.last_retained_at,
+    })?)
+}
+
+pub fn yield_hook(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    Ok(to_binary(&YieldHookResponse {
+        enabled: yield_hook_enabled(deps.storage, &config)?,
+    })?)
+}
+
+fn proposal_response(env: &Env, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        status: proposal.status(env.block.time.seconds()),
+        id: proposal.id,
+        proposer: proposal.proposer.to_string(),
+        action: proposal.action,
+        description: proposal.description,
+        start_height: proposal.start_height,
+        voting_ends_at: proposal.voting_ends_at,
+        yes_votes: proposal.yes_votes,
+        no_votes: proposal.no_votes,
+        total_weight: proposal.total_weight,
+    }
+}
+
+pub fn proposal(deps: Deps, env: Env, proposal_id: u64) -> Result<Binary, ContractError> {
+    let proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    Ok(to_binary(&proposal_response(&env, proposal))?)
+}
+
+pub fn proposals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let proposals = PROPOSALS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, proposal)| proposal_response(&env, proposal)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&ProposalsResponse { proposals })?)
+}
+
+fn payout_response(payout: Payout) -> PayoutResponse {
+    PayoutResponse {
+        recipient: payout.recipient.to_string(),
+        amount: payout.amount,
+    }
+}
+
+fn earn_record_response(record: EarnRecord) -> EarnRecordResponse {
+    EarnRecordResponse {
+        id: record.id,
+        height: record.height,
+        time: record.time,
+        exchange_rate: record.exchange_rate,
+        earnable: record.earnable,
+        fee: record.fee,
+        beneficiary: record.beneficiary.to_string(),
+        beneficiary_amount: record.beneficiary_amount,
+        split: record.split.into_iter().map(payout_response).collect(),
+        keeper_bounty: record.keeper_bounty.map(payout_response),
+        swap: record.swap.map(|swap| SwapRecordResponse {
+            pair: swap.pair.to_string(),
+            ask_asset: swap.ask_asset,
+            offer_amount: swap.offer_amount,
+            return_amount: swap.return_amount,
+            error: swap.error,
+        }),
+        matched: record.matched,
+        retained: record.retained,
+        insured: record.insured,
+    }
+}
+
+pub fn earn_history(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let earns = EARN_HISTORY
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, record)| earn_record_response(record)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&EarnHistoryResponse { earns })?)
+}
+
+fn fee_record_response(record: FeeRecord) -> FeeRecordResponse {
+    FeeRecordResponse {
+        id: record.id,
+        height: record.height,
+        time: record.time,
+        fee: record.fee,
+        fee_collector: record.fee_collector.to_string(),
+    }
+}
+
+pub fn fee_history(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let fees = FEE_HISTORY
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, record)| fee_record_response(record)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&FeeHistoryResponse { fees })?)
+}
+
+pub fn ibc_payout(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    Ok(to_binary(&IbcPayoutResponse {
+        ibc_payout: ibc_payout_read(deps.storage, &config)?,
+    })?)
+}
+
+pub fn swap_payout(deps: Deps, _env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    Ok(to_binary(&SwapPayoutResponse {
+        swap_payout: swap_payout_read(deps.storage, &config)?,
+    })?)
+}
+
+pub fn matches(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| addr::validate(deps.api, &address))
+        .transpose()?;
+    let now = env.block.time.seconds();
+    let remaining = MATCHES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, matching)| matching))
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|matching| now < matching.expires_at)
+        .map(|matching| matching.budget)
+        .sum();
+    let matches = MATCHES
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(sponsor, matching)| MatchResponse {
+                sponsor: sponsor.to_string(),
+                ratio: matching.ratio,
+                cap: matching.cap,
+                expires_at: matching.expires_at,
+                expired: now >= matching.expires_at,
+                budget: matching.budget,
+                released: matching.released,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&MatchesResponse { remaining, matches })?)
+}
+
+pub fn donor(deps: Deps, _env: Env, address: String) -> Result<Binary, ContractError> {
+    let donor = addr::validate(deps.api, &address)?;
+    Ok(to_binary(&DonorResponse {
+        total: DONORS.may_load(deps.storage, &donor)?.unwrap_or_default(),
+        donor: donor.to_string(),
+    })?)
+}
+
+pub fn donors(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|address| addr::validate(deps.api, &address))
+        .transpose()?;
+    let donors = DONORS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(donor, total)| DonorResponse {
+                donor: donor.to_string(),
+                total,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&DonorsResponse {
+        total: TOTAL_DONATED.may_load(deps.storage)?.unwrap_or_default(),
+        donors,
+        donor_collection: donor_collection_read(deps.storage)?,
+    })?)
+}
+
+pub fn deposit_routes(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let routes = DEPOSIT_ROUTES
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(denom, route)| DepositRouteResponse { denom, route }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&DepositRoutesResponse { routes })?)
+}
+
+pub fn whitelisted_pairs(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let pairs = PAIR_WHITELIST
+        .keys(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&WhitelistedPairsResponse { pairs })?)
+}
+
+pub fn whitelisted_markets(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let markets = MARKET_WHITELIST
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (money_market, atoken) = item?;
+            Ok(WhitelistedMarketResponse {
+                money_market,
+                atoken: addr::humanize(deps.api, &atoken)?.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    Ok(to_binary(&WhitelistedMarketsResponse { markets })?)
+}
+
+pub fn ibc_transfers(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let transfers = IBC_TRANSFERS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(_, transfer)| IbcTransferResponse {
+                id: transfer.id,
+                earn_id: transfer.earn_id,
+                channel_id: transfer.channel_id,
+                remote_address: transfer.remote_address,
+                amount: transfer.amount,
+                attempts: transfer.attempts,
+                sequence: transfer.sequence,
+                status: transfer.status,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_binary(&IbcTransfersResponse { transfers })?)
+}
+
+pub fn vote(
+    deps: Deps,
+    _env: Env,
+    proposal_id: u64,
+    voter: String,
+) -> Result<Binary, ContractError> {
+    let voter = addr::validate(deps.api, &voter)?;
+    Ok(to_binary(
+        &VOTES.may_load(deps.storage, (proposal_id, &voter))?,
+    )?)
+}
+
+pub fn staked(deps: Deps, _env: Env, address: String) -> Result<Binary, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    Ok(to_binary(&StakedResponse {
+        amount: STAKED.may_load(deps.storage, &address)?.unwrap_or_default(),
+    })?)
+}
+
+pub fn payout_split(deps: Deps, env: Env) -> Result<Binary, ContractError> {
+    let config = config::read(deps.storage)?;
+    let humanize = |split: Vec<config::PayoutShare>| {
+        split
+            .into_iter()
+            .map(|share| {
+                Ok(PayoutShare {
+                    recipient: addr::humanize(deps.api, &share.recipient)?.to_string(),
+                    share: share.share,
+                })
+            })
+            .collect::<Result<Vec<_>, ContractError>>()
     };
-    to_binary(&ClaimableRewardResponse {
-        total_value: dp_total_supply,
-        pool_value: pool_value_locked,
-        earned: updated_last_claimed.total_earned_at_last_claimed,
-        claimable,
-        fee,
-    })
+    // a change whose exit window has passed is the split the next payout uses
+    let (split, pending) = match payout_split_change_read(deps.storage)? {
+        Some(change) if env.block.time.seconds() >= change.effective_at => (change.split, None),
+        pending => (payout_split_read(deps.storage)?, pending),
+    };
+    Ok(to_binary(&PayoutSplitResponse {
+        beneficiary: addr::humanize(deps.api, &config.beneficiary)?.to_string(),
+        split: humanize(split)?,
+        pending: pending
+            .map(|change| -> Result<_, ContractError> {
+                Ok(PayoutSplitChangeResponse {
+                    split: humanize(change.split)?,
+                    effective_at: change.effective_at,
+                })
+            })
+            .transpose()?,
+    })?)
+}
+
+pub fn roles(deps: Deps, _env: Env, address: String) -> Result<Binary, ContractError> {
+    let address = addr::validate(deps.api, &address)?;
+    Ok(to_binary(&RolesResponse {
+        roles: roles::roles_of(deps.storage, &address),
+        address: address.to_string(),
+    })?)
+}
+
+pub fn role_members(
+    deps: Deps,
+    _env: Env,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let start_after = start_after
+        .map(|address| addr::validate(deps.api, &address))
+        .transpose()?;
+    Ok(to_binary(&RoleMembersResponse {
+        role,
+        members: roles::members(deps.storage, role, start_after, limit)?
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect(),
+    })?)
 }
@@ -0,0 +1,271 @@
+use cosmwasm_std::*;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+use yieldpay_core::token;
+
+use crate::addr;
+use crate::config;
+use crate::error::ContractError;
+use crate::handler::core::redemption;
+use crate::querier::anchor;
+use crate::stable;
+use crate::state::{MarketStrategy, MARKET_WHITELIST, STRATEGIES};
+
+/// besides the config's money market. each one is queried on every earn
+pub const MAX_STRATEGIES: usize = 4;
+
+/// what the pool holds in one money market
+pub struct Position {
+    pub money_market: CanonicalAddr,
+    pub atoken: CanonicalAddr,
+    pub weight: Decimal,
+    pub atokens: Uint128,
+    pub exchange_rate: Decimal,
+}
+
+impl Position {
+    pub fn value(&self) -> Uint128 {
+        self.atokens * self.exchange_rate
+    }
+}
+
+pub fn set_strategy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    money_market: String,
+    weight: Decimal,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    // the beneficiary only weighs the markets the owner has whitelisted
+    roles::ensure_any(
+        deps.storage,
+        "set_strategy",
+        &info.sender,
+        &[Role::Owner, Role::Admin, Role::Beneficiary],
+    )?;
+    let market_addr = addr::validate(deps.api, &money_market)?;
+    let market = addr::canonicalize(deps.api, market_addr.as_str())?;
+    if market == config.money_market {
+        return Err(ContractError::InvalidStrategy(market_addr.to_string()));
+    }
+    let whitelisted = MARKET_WHITELIST.may_load(deps.storage, market_addr.as_str())?;
+
+    let strategies = strategies(deps.storage)?;
+    let others = strategies
+        .iter()
+        .filter(|strategy| strategy.money_market != market)
+        .fold(Decimal::zero(), |total, strategy| total + strategy.weight);
+    if others + weight > Decimal::one() {
+        return Err(ContractError::InvalidStrategyWeights);
+    }
+
+    let existing = STRATEGIES.may_load(deps.storage, market_addr.as_str())?;
+    let atoken = match (existing, whitelisted) {
+        (Some(strategy), Some(_)) => strategy.atoken,
+        // a market taken off the whitelist can only be left at no weight to drain
+        (Some(strategy), None) if weight.is_zero() => strategy.atoken,
+        (None, Some(atoken)) => {
+            if weight.is_zero() {
+                return Err(ContractError::InvalidStrategy(market_addr.to_string()));
+            }
+            if strategies.len() >= MAX_STRATEGIES {
+                return Err(ContractError::TooManyStrategies {
+                    max: MAX_STRATEGIES,
+                });
+            }
+            atoken
+        }
+        _ => return Err(ContractError::MarketNotWhitelisted(market_addr.to_string())),
+    };
+
+    let removed = save_strategy(deps, &env, &market_addr, market, atoken, weight)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_strategy")
+        .add_attribute("money_market", market_addr)
+        .add_attribute("weight", weight.to_string())
+        .add_attribute("removed", removed.to_string()))
+}
+
+/// owner/admin only. what the beneficiary can spread deposits to. taking a market off the list
+/// drops its weight to zero, leaving it for Rebalance to drain
+pub fn whitelist_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    money_market: String,
+    whitelisted: bool,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "whitelist_market",
+        &info.sender,
+        &[Role::Owner, Role::Admin],
+    )?;
+    let market_addr = addr::validate(deps.api, &money_market)?;
+    let market = addr::canonicalize(deps.api, market_addr.as_str())?;
+    if market == config.money_market {
+        return Err(ContractError::InvalidStrategy(market_addr.to_string()));
+    }
+
+    if whitelisted {
+        let market_config = anchor::config(deps.as_ref(), &market)?;
+        let atoken = addr::canonicalize(deps.api, market_config.aterra_contract.as_str())?;
+        MARKET_WHITELIST.save(deps.storage, market_addr.as_str(), &atoken)?;
+    } else {
+        MARKET_WHITELIST.remove(deps.storage, market_addr.as_str());
+        if let Some(strategy) = STRATEGIES.may_load(deps.storage, market_addr.as_str())? {
+            save_strategy(
+                deps,
+                &env,
+                &market_addr,
+                market,
+                strategy.atoken,
+                Decimal::zero(),
+            )?;
+        }
+    }
+    Ok(Response::new()
+        .add_attribute("action", "whitelist_market")
+        .add_attribute("money_market", market_addr)
+        .add_attribute("whitelisted", whitelisted.to_string()))
+}
+
+/// a strategy still holding aTokens stays until Rebalance has drained it. returns whether it was
+/// removed
+fn save_strategy(
+    deps: DepsMut,
+    env: &Env,
+    market_addr: &Addr,
+    money_market: CanonicalAddr,
+    atoken: CanonicalAddr,
+    weight: Decimal,
+) -> Result<bool, ContractError> {
+    let atokens = token::balance_of(
+        deps.as_ref(),
+        addr::humanize(deps.api, &atoken)?.to_string(),
+        env.contract.address.to_string(),
+    )?;
+    let removed = weight.is_zero() && atokens.is_zero();
+    if removed {
+        STRATEGIES.remove(deps.storage, market_addr.as_str());
+    } else {
+        STRATEGIES.save(
+            deps.storage,
+            market_addr.as_str(),
+            &MarketStrategy {
+                money_market,
+                atoken,
+                weight,
+            },
+        )?;
+    }
+    Ok(removed)
+}
+
+pub fn strategies(storage: &dyn Storage) -> StdResult<Vec<MarketStrategy>> {
+    STRATEGIES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, strategy)| strategy))
+        .collect()
+}
+
+/// the config's money market first, with whatever weight the strategies leave it
+pub fn positions(
+    deps: Deps,
+    env: &Env,
+    config: &config::Config,
+) -> Result<Vec<Position>, ContractError> {
+    let strategies = strategies(deps.storage)?;
+    let weights = strategies
+        .iter()
+        .fold(Decimal::zero(), |total, strategy| total + strategy.weight);
+    let primary = MarketStrategy {
+        money_market: config.money_market.clone(),
+        atoken: config.atoken.clone(),
+        weight: Decimal::one() - weights,
+    };
+
+    let mut positions = vec![];
+    for strategy in std::iter::once(primary).chain(strategies) {
+        let exchange_rate = anchor::epoch_state(deps, &strategy.money_market)?.exchange_rate;
+        let atokens = token::balance_of(
+            deps,
+            addr::humanize(deps.api, &strategy.atoken)?.to_string(),
+            env.contract.address.to_string(),
+        )?;
+        positions.push(Position {
+            money_market: strategy.money_market,
+            atoken: strategy.atoken,
+            weight: strategy.weight,
+            atokens,
+            exchange_rate,
+        });
+    }
+    Ok(positions)
+}
+
+/// deposits `amount` of the stable across the money markets by weight
+pub fn deposit_msgs(
+    deps: Deps,
+    config: &config::Config,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let stable = stable::read(deps, config)?;
+    let mut msgs = vec![];
+    let mut rest = amount;
+    for strategy in strategies(deps.storage)? {
+        let share = amount * strategy.weight;
+        if !share.is_zero() {
+            msgs.extend(anchor::deposit_stable_msg(
+                deps,
+                &strategy.money_market,
+                &stable,
+                share,
+            )?);
+            rest -= share;
+        }
+    }
+    if !rest.is_zero() {
+        msgs.extend(anchor::deposit_stable_msg(
+            deps,
+            &config.money_market,
+            &stable,
+            rest,
+        )?);
+    }
+    Ok(msgs)
+}
+
+/// redeems at most `amount` of the stable, from the positions in order, and what that returns.
+/// redeeming rounds down, so that can be a little short of `amount`
+pub fn withdraw(
+    deps: Deps,
+    positions: &[Position],
+    amount: Uint128,
+) -> Result<(Vec<CosmosMsg>, Uint128), ContractError> {
+    let mut msgs = vec![];
+    let mut withdrawn = Uint128::zero();
+    for position in positions {
+        let wanted = amount - withdrawn;
+        if wanted.is_zero() {
+            break;
+        }
+        if position.exchange_rate.is_zero() {
+            continue;
+        }
+        let (atokens, stable) = redemption(wanted.min(position.value()), position.exchange_rate)?;
+        if atokens.is_zero() {
+            continue;
+        }
+        msgs.extend(anchor::redeem_stable_msg(
+            deps,
+            &position.money_market,
+            &position.atoken,
+            atokens,
+        )?);
+        withdrawn += stable;
+    }
+    Ok((msgs, withdrawn))
+}
@@ -0,0 +1,150 @@
+use std::str::FromStr;
+
+use cosmwasm_std::*;
+use yieldpay_core::dex::{Asset, AssetInfo, PairCw20HookMsg, PairExecuteMsg};
+use yieldpay_core::pool_msg::SwapPayout;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::config;
+use crate::config::{swap_payout_remove, swap_payout_store, SwapPayoutSetting, MAX_SWAP_SPREAD};
+use crate::error::ContractError;
+use crate::stable;
+use crate::state::{SwapRecord, EARN_HISTORY, PENDING_SWAP};
+
+pub const SWAP_REPLY_ID: u64 = 3;
+
+pub fn set_swap_payout(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    swap_payout: Option<SwapPayout>,
+) -> Result<Response, ContractError> {
+    let config = config::read(deps.storage)?;
+    roles::ensure_any(
+        deps.storage,
+        "set_swap_payout",
+        &info.sender,
+        &[Role::Beneficiary],
+    )?;
+
+    let response = Response::new().add_attribute("action", "set_swap_payout");
+    match swap_payout {
+        Some(swap_payout) => {
+            if swap_payout.max_spread > MAX_SWAP_SPREAD {
+                return Err(ContractError::InvalidSwapPayout {
+                    max: MAX_SWAP_SPREAD,
+                });
+            }
+            if swap_payout.belief_price.is_zero() {
+                return Err(ContractError::SwapPayoutNeedsBeliefPrice {});
+            }
+            deps.api.addr_validate(&swap_payout.pair)?;
+            if let AssetInfo::Token { contract_addr } = &swap_payout.ask_asset {
+                deps.api.addr_validate(contract_addr)?;
+            }
+            let response = response
+                .add_attribute("pair", swap_payout.pair.clone())
+                .add_attribute("belief_price", swap_payout.belief_price.to_string())
+                .add_attribute("max_spread", swap_payout.max_spread.to_string());
+            swap_payout_store(
+                deps.storage,
+                &SwapPayoutSetting {
+                    beneficiary: config.beneficiary,
+                    swap_payout,
+                },
+            )?;
+            Ok(response)
+        }
+        None => {
+            swap_payout_remove(deps.storage);
+            Ok(response.add_attribute("swap_payout", "off"))
+        }
+    }
+}
+
+/// swaps the beneficiary's payout for an earn, with the pair paying the beneficiary directly
+pub fn payout(
+    storage: &mut dyn Storage,
+    stable: &AssetInfo,
+    earn_id: u64,
+    beneficiary: Addr,
+    amount: Uint128,
+    swap_payout: SwapPayout,
+) -> StdResult<SubMsg> {
+    let pair = Addr::unchecked(swap_payout.pair);
+    let mut record = EARN_HISTORY.load(storage, earn_id)?;
+    record.swap = Some(SwapRecord {
+        pair: pair.clone(),
+        ask_asset: swap_payout.ask_asset,
+        offer_amount: amount,
+        return_amount: None,
+        error: None,
+    });
+    EARN_HISTORY.save(storage, earn_id, &record)?;
+    PENDING_SWAP.save(storage, &earn_id)?;
+
+    let to = Some(beneficiary.to_string());
+    let swap = match stable {
+        AssetInfo::NativeToken { .. } => to_binary(&PairExecuteMsg::Swap {
+            offer_asset: Asset {
+                info: stable.clone(),
+                amount,
+            },
+            belief_price: Some(swap_payout.belief_price),
+            max_spread: Some(swap_payout.max_spread),
+            to,
+        })?,
+        AssetInfo::Token { .. } => to_binary(&PairCw20HookMsg::Swap {
+            belief_price: Some(swap_payout.belief_price),
+            max_spread: Some(swap_payout.max_spread),
+            to,
+        })?,
+    };
+    Ok(SubMsg::reply_always(
+        stable::execute_msg(stable, &pair, swap, amount)?,
+        SWAP_REPLY_ID,
+    ))
+}
+
+/// records what the swap returned. if it failed the stable is back in the pool,
+/// so the beneficiary is paid that instead
+pub fn swap_replied(deps: DepsMut, result: SubMsgResult) -> Result<Response, ContractError> {
+    let earn_id = PENDING_SWAP.load(deps.storage)?;
+    PENDING_SWAP.remove(deps.storage);
+    let mut record = EARN_HISTORY.load(deps.storage, earn_id)?;
+    let mut swap = record
+        .swap
+        .take()
+        .ok_or_else(|| StdError::not_found("SwapRecord"))?;
+
+    let mut response = Response::new().add_attribute("earn", earn_id.to_string());
+    match result {
+        SubMsgResult::Ok(swapped) => {
+            swap.return_amount = swapped
+                .events
+                .iter()
+                .flat_map(|event| event.attributes.iter())
+                .find(|attr| attr.key == "return_amount")
+                .and_then(|attr| Uint128::from_str(&attr.value).ok());
+            response = response.add_attribute(
+                "return_amount",
+                swap.return_amount.unwrap_or_default().to_string(),
+            );
+        }
+        SubMsgResult::Err(err) => {
+            let config = config::read(deps.storage)?;
+            response = response
+                .add_message(stable::transfer_msg(
+                    &stable::read(deps.as_ref(), &config)?,
+                    &record.beneficiary,
+                    swap.offer_amount,
+                )?)
+                .add_attribute("swap_failed", err.clone());
+            swap.error = Some(err);
+        }
+    }
+    record.swap = Some(swap);
+    EARN_HISTORY.save(deps.storage, earn_id, &record)?;
+    Ok(response)
+}
@@ -0,0 +1,73 @@
+use cosmwasm_std::*;
+use yieldpay_core::dex::AssetInfo;
+use yieldpay_core::roles;
+use yieldpay_core::roles::Role;
+
+use crate::addr;
+use crate::config;
+use crate::config::{insurance_reserve_read, liquid_buffer_read};
+use crate::error::ContractError;
+use crate::handler::strategy::strategies;
+use crate::stable;
+use crate::state::{IBC_UNSETTLED, MATCHES};
+
+/// sends `recipient` whatever the pool holds of a token that was sent to it by mistake
+pub fn sweep(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom_or_cw20: AssetInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    roles::ensure_any(deps.storage, "sweep", &info.sender, &[Role::Owner])?;
+    let config = config::read(deps.storage)?;
+    let recipient = addr::validate(deps.api, &recipient)?;
+    if let AssetInfo::Token { contract_addr } = &denom_or_cw20 {
+        let token = addr::validate(deps.api, contract_addr)?;
+        if held_for_depositors(deps.as_ref(), &config)?.contains(&token) {
+            return Err(ContractError::CannotSweep(token.to_string()));
+        }
+    }
+
+    let balance = stable::balance(&deps.querier, &denom_or_cw20, &env.contract.address)?;
+    let amount = if denom_or_cw20 == stable::read(deps.as_ref(), &config)? {
+        balance.saturating_sub(escrowed(deps.storage)?)
+    } else {
+        balance
+    };
+    if amount.is_zero() {
+        return Err(ContractError::NothingToSweep);
+    }
+    Ok(Response::new()
+        .add_message(stable::transfer_msg(&denom_or_cw20, &recipient, amount)?)
+        .add_attribute("action", "sweep")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount.to_string()))
+}
+
+/// the DP token and the aTokens of every money market the pool is in
+fn held_for_depositors(deps: Deps, config: &config::Config) -> Result<Vec<Addr>, ContractError> {
+    let mut tokens = vec![
+        addr::dp_token(deps.api, config)?,
+        addr::humanize(deps.api, &config.atoken)?,
+    ];
+    for strategy in strategies(deps.storage)? {
+        tokens.push(addr::humanize(deps.api, &strategy.atoken)?);
+    }
+    Ok(tokens)
+}
+
+/// stable the pool holds on someone's behalf, outside the market, or may be refunded for an ibc
+/// payout
+fn escrowed(storage: &dyn Storage) -> StdResult<Uint128> {
+    let budgets = MATCHES
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |total, item| {
+            item.map(|(_, matching)| total + matching.budget)
+        })?;
+    let unsettled = IBC_UNSETTLED.may_load(storage)?.unwrap_or_default();
+    Ok(liquid_buffer_read(storage)?.held
+        + insurance_reserve_read(storage)?.held
+        + budgets
+        + unsettled)
+}
@@ -2,11 +2,14 @@ extern crate core;
 
 pub mod config;
 pub mod contract;
+pub mod state;
 
+mod addr;
 mod error;
 mod handler;
 mod querier;
 mod response;
+mod stable;
 
 mod migrations;
 #[cfg(test)]
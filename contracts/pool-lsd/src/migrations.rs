@@ -1,10 +1,12 @@
-use crate::config::Config;
+use crate::config::{Config, LastClaimed};
 use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
-use cosmwasm_storage::singleton_read;
+use cosmwasm_storage::{singleton, singleton_read};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use yieldpay_core::fee::{FeeModel, FeePeriod, LEGACY_BLOCK_SECONDS};
 
 const CONFIG_V100: &[u8] = b"config";
+/// `fee_max` capped each claim, so like `ConfigV104` it migrates to a per claim period
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct ConfigV100 {
     pub this: CanonicalAddr,
@@ -37,9 +39,8 @@ impl ConfigV100 {
             owner: self.owner.clone(),
             beneficiary: self.beneficiary.clone(),
             fee_collector: self.fee_collector.clone(),
-            fee_amount: self.fee_amount,
-            fee_max: self.fee_max,
-            fee_reset_every_num_blocks: self.fee_reset_every_num_blocks,
+            fee_model: FeeModel::from_legacy(self.fee_amount, self.fee_max),
+            fee_period: FeePeriod::PerClaim {},
             money_market: self.money_market.clone(),
             atoken: self.atoken.clone(),
             stable_denom: self.stable_denom.clone(),
@@ -54,3 +55,142 @@ impl ConfigV100 {
         }
     }
 }
+
+const CONFIG_V104: &[u8] = b"config_v104";
+/// config from before fees were a `FeeModel`. `fee_max` capped each claim and
+/// `fee_reset_every_num_blocks` was never read, so it migrates to a per claim period
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ConfigV104 {
+    pub this: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub beneficiary: CanonicalAddr,
+    pub fee_collector: CanonicalAddr,
+    pub fee_amount: Decimal,
+    pub fee_max: Uint128,
+    pub fee_reset_every_num_blocks: u64,
+    pub money_market: CanonicalAddr,
+    pub atoken: CanonicalAddr,
+    pub stable_denom: String,
+    pub dp_token: CanonicalAddr,
+    pub pool_name: String,
+    pub pool_title: String,
+    pub pool_description: String,
+    pub owner_can_change_config: bool,
+    pub nft_contract: Option<CanonicalAddr>,
+    pub nft_collection_active: Option<u64>,
+    pub nft_collection_redeemed: Option<u64>,
+}
+impl ConfigV104 {
+    pub fn may_load(storage: &dyn Storage) -> StdResult<Option<Self>> {
+        singleton_read(storage, CONFIG_V104).may_load()
+    }
+
+    pub fn remove(storage: &mut dyn Storage) {
+        singleton::<Self>(storage, CONFIG_V104).remove()
+    }
+
+    pub fn migrate_from(&self) -> Config {
+        Config {
+            this: self.this.clone(),
+            owner: self.owner.clone(),
+            beneficiary: self.beneficiary.clone(),
+            fee_collector: self.fee_collector.clone(),
+            fee_model: FeeModel::from_legacy(self.fee_amount, self.fee_max),
+            fee_period: FeePeriod::PerClaim {},
+            money_market: self.money_market.clone(),
+            atoken: self.atoken.clone(),
+            stable_denom: self.stable_denom.clone(),
+            dp_token: self.dp_token.clone(),
+            pool_name: self.pool_name.clone(),
+            pool_title: self.pool_title.clone(),
+            pool_description: self.pool_description.clone(),
+            owner_can_change_config: self.owner_can_change_config,
+            nft_contract: self.nft_contract.clone(),
+            nft_collection_active: self.nft_collection_active,
+            nft_collection_redeemed: self.nft_collection_redeemed,
+        }
+    }
+}
+
+const CONFIG_V105: &[u8] = b"config_v105";
+/// config from before fee periods were in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ConfigV105 {
+    pub this: CanonicalAddr,
+    pub owner: CanonicalAddr,
+    pub beneficiary: CanonicalAddr,
+    pub fee_collector: CanonicalAddr,
+    pub fee_model: FeeModel,
+    pub fee_reset_every_num_blocks: u64,
+    pub money_market: CanonicalAddr,
+    pub atoken: CanonicalAddr,
+    pub stable_denom: String,
+    pub dp_token: CanonicalAddr,
+    pub pool_name: String,
+    pub pool_title: String,
+    pub pool_description: String,
+    pub owner_can_change_config: bool,
+    pub nft_contract: Option<CanonicalAddr>,
+    pub nft_collection_active: Option<u64>,
+    pub nft_collection_redeemed: Option<u64>,
+}
+impl ConfigV105 {
+    pub fn may_load(storage: &dyn Storage) -> StdResult<Option<Self>> {
+        singleton_read(storage, CONFIG_V105).may_load()
+    }
+
+    pub fn remove(storage: &mut dyn Storage) {
+        singleton::<Self>(storage, CONFIG_V105).remove()
+    }
+
+    pub fn migrate_from(&self) -> Config {
+        Config {
+            this: self.this.clone(),
+            owner: self.owner.clone(),
+            beneficiary: self.beneficiary.clone(),
+            fee_collector: self.fee_collector.clone(),
+            fee_model: self.fee_model.clone(),
+            fee_period: FeePeriod::from_blocks(self.fee_reset_every_num_blocks),
+            money_market: self.money_market.clone(),
+            atoken: self.atoken.clone(),
+            stable_denom: self.stable_denom.clone(),
+            dp_token: self.dp_token.clone(),
+            pool_name: self.pool_name.clone(),
+            pool_title: self.pool_title.clone(),
+            pool_description: self.pool_description.clone(),
+            owner_can_change_config: self.owner_can_change_config,
+            nft_contract: self.nft_contract.clone(),
+            nft_collection_active: self.nft_collection_active,
+            nft_collection_redeemed: self.nft_collection_redeemed,
+        }
+    }
+}
+
+const LAST_CLAIMED_V1: &[u8] = b"last_claimed";
+/// fee period tracking from when periods were counted in blocks
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LastClaimedV1 {
+    pub last_claimed_at_block_height: u64,
+    pub fees_collected: Uint128,
+    pub total_earned_at_last_claimed: Uint128,
+}
+impl LastClaimedV1 {
+    pub fn may_load(storage: &dyn Storage) -> StdResult<Option<Self>> {
+        singleton_read(storage, LAST_CLAIMED_V1).may_load()
+    }
+
+    pub fn remove(storage: &mut dyn Storage) {
+        singleton::<Self>(storage, LAST_CLAIMED_V1).remove()
+    }
+
+    /// the period start is estimated back from the current block at the legacy block time
+    pub fn migrate_from(&self, block_height: u64, block_time: u64) -> LastClaimed {
+        let blocks_ago = block_height.saturating_sub(self.last_claimed_at_block_height);
+        LastClaimed {
+            period_start: block_time
+                .saturating_sub(blocks_ago.saturating_mul(LEGACY_BLOCK_SECONDS)),
+            fees_collected: self.fees_collected,
+            total_earned_at_last_claimed: self.total_earned_at_last_claimed,
+        }
+    }
+}
@@ -3,6 +3,9 @@ use cosmwasm_std::*;
 use cw20::Cw20ExecuteMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use yieldpay_core::dex::AssetInfo;
+
+use crate::stable;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -31,7 +34,7 @@ pub struct ConfigResponse {
 pub fn config(deps: Deps, market: &CanonicalAddr) -> StdResult<ConfigResponse> {
     let market_config: ConfigResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps.api.addr_humanize(market).unwrap().to_string(),
+            contract_addr: deps.api.addr_humanize(market)?.to_string(),
             msg: to_binary(&QueryMsg::Config {})?,
         }))?;
 
@@ -48,7 +51,7 @@ pub struct EpochStateResponse {
 pub fn epoch_state(deps: Deps, market: &CanonicalAddr) -> StdResult<EpochStateResponse> {
     let epoch_state: EpochStateResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps.api.addr_humanize(market).unwrap().to_string(),
+            contract_addr: deps.api.addr_humanize(market)?.to_string(),
             msg: to_binary(&QueryMsg::EpochState {
                 block_height: None,
                 distributed_interest: None,
@@ -70,22 +73,26 @@ pub enum Cw20HookMsg {
     /// Return stable coins to a user
     /// according to exchange rate
     RedeemStable {},
+    /// markets with a cw20 stable take deposits through its Send
+    DepositStable {},
 }
 
 pub fn deposit_stable_msg(
     deps: Deps,
     market: &CanonicalAddr,
-    denom: &str,
+    stable: &AssetInfo,
     amount: Uint128,
 ) -> StdResult<Vec<CosmosMsg>> {
-    Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: deps.api.addr_humanize(market).unwrap().to_string(),
-        msg: to_binary(&HandleMsg::DepositStable {})?,
-        funds: vec![Coin {
-            denom: denom.to_string(),
-            amount,
-        }],
-    })])
+    let msg = match stable {
+        AssetInfo::NativeToken { .. } => to_binary(&HandleMsg::DepositStable {})?,
+        AssetInfo::Token { .. } => to_binary(&Cw20HookMsg::DepositStable {})?,
+    };
+    Ok(vec![stable::execute_msg(
+        stable,
+        &deps.api.addr_humanize(market)?,
+        msg,
+        amount,
+    )?])
 }
 
 pub fn redeem_stable_msg(
@@ -95,11 +102,11 @@ pub fn redeem_stable_msg(
     amount: Uint128,
 ) -> StdResult<Vec<CosmosMsg>> {
     Ok(vec![CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: deps.api.addr_humanize(token).unwrap().to_string(),
+        contract_addr: deps.api.addr_humanize(token)?.to_string(),
         msg: to_binary(&Cw20ExecuteMsg::Send {
-            contract: deps.api.addr_humanize(market).unwrap().to_string(),
+            contract: deps.api.addr_humanize(market)?.to_string(),
             amount,
-            msg: to_binary(&Cw20HookMsg::RedeemStable {}).unwrap(),
+            msg: to_binary(&Cw20HookMsg::RedeemStable {})?,
         })?,
         funds: vec![],
     })])
@@ -87,7 +87,7 @@ pub fn nft_exists(
     };
     let token_response: TokensResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: deps.api.addr_humanize(nft_address).unwrap().to_string(),
+            contract_addr: deps.api.addr_humanize(nft_address)?.to_string(),
             msg: to_binary(&msg)?,
         }))?;
 
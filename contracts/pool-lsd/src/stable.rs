@@ -0,0 +1,113 @@
+//! the pool's underlying: a native denom, or a cw20 for pools whose market takes one
+use cosmwasm_std::{
+    to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, QuerierWrapper, StdResult, Uint128,
+    WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use yieldpay_core::dex::AssetInfo;
+
+use crate::addr;
+use crate::config::{stable_token_read, Config};
+use crate::error::ContractError;
+
+pub fn read(deps: Deps, config: &Config) -> StdResult<AssetInfo> {
+    Ok(match stable_token_read(deps.storage)? {
+        Some(token) => AssetInfo::Token {
+            contract_addr: deps.api.addr_humanize(&token)?.to_string(),
+        },
+        None => AssetInfo::NativeToken {
+            denom: config.stable_denom.clone(),
+        },
+    })
+}
+
+/// the native stable sent along with a message. cw20 pools take it through the Receive hook
+pub fn native_received(
+    deps: Deps,
+    config: &Config,
+    funds: &[Coin],
+) -> Result<Uint128, ContractError> {
+    if let Some(token) = stable_token_read(deps.storage)? {
+        return Err(ContractError::NotAllowOtherDenoms {
+            denom: addr::humanize(deps.api, &token)?.to_string(),
+        });
+    }
+    if funds.len() > 1 {
+        return Err(ContractError::NotAllowOtherDenoms {
+            denom: config.stable_denom.clone(),
+        });
+    }
+    Ok(funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| c.amount)
+        .unwrap_or_default())
+}
+
+pub fn balance(querier: &QuerierWrapper, stable: &AssetInfo, address: &Addr) -> StdResult<Uint128> {
+    Ok(match stable {
+        AssetInfo::NativeToken { denom } => querier.query_balance(address, denom)?.amount,
+        AssetInfo::Token { contract_addr } => {
+            let balance: BalanceResponse = querier.query_wasm_smart(
+                contract_addr,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            balance.balance
+        }
+    })
+}
+
+pub fn transfer_msg(stable: &AssetInfo, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match stable {
+        AssetInfo::NativeToken { denom } => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        AssetInfo::Token { contract_addr } => WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+/// executes `msg` on `contract` with `amount` attached. a cw20 goes through Send,
+/// so the contract gets `msg` wrapped in its Receive hook
+pub fn execute_msg(
+    stable: &AssetInfo,
+    contract: &Addr,
+    msg: Binary,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    Ok(match stable {
+        AssetInfo::NativeToken { denom } => WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg,
+            funds: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        AssetInfo::Token { contract_addr } => WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: contract.to_string(),
+                amount,
+                msg,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
@@ -0,0 +1,197 @@
+use cosmwasm_std::{Addr, CanonicalAddr, Decimal, Empty, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use yieldpay_core::dex::AssetInfo;
+use yieldpay_core::pool_msg::{DepositRoute, GovernanceAction, VoteOption};
+use yieldpay_core::pool_resp::{IbcTransferStatus, ProposalStatus};
+
+/// DP staked for governance, checkpointed every block so votes use the stake at proposal time
+pub const STAKED: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "staked",
+    "staked__checkpoints",
+    "staked__changelog",
+    Strategy::EveryBlock,
+);
+pub const TOTAL_STAKED: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_staked",
+    "total_staked__checkpoints",
+    "total_staked__changelog",
+    Strategy::EveryBlock,
+);
+
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+pub const VOTES: Map<(u64, &Addr), VoteOption> = Map::new("votes");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Addr,
+    pub action: GovernanceAction,
+    pub description: String,
+    pub start_height: u64,
+    /// seconds since epoch
+    pub voting_ends_at: u64,
+    pub yes_votes: Uint128,
+    pub no_votes: Uint128,
+    pub total_weight: Uint128,
+    /// taken from the governance config when the proposal was made
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub executed: bool,
+}
+
+impl Proposal {
+    pub fn status(&self, now: u64) -> ProposalStatus {
+        if self.executed {
+            return ProposalStatus::Executed;
+        }
+        if now < self.voting_ends_at {
+            return ProposalStatus::Open;
+        }
+        let votes = self.yes_votes + self.no_votes;
+        if votes.is_zero() || votes < self.total_weight * self.quorum {
+            return ProposalStatus::Rejected;
+        }
+        if self.yes_votes > votes * self.threshold {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        }
+    }
+}
+
+pub const EARN_COUNT: Item<u64> = Item::new("earn_count");
+/// every earn, keyed by a running count. only ever appended to
+pub const EARN_HISTORY: Map<u64, EarnRecord> = Map::new("earn_history");
+pub const FEE_COUNT: Item<u64> = Item::new("fee_count");
+/// every fee collection, keyed by a running count. only ever appended to
+pub const FEE_HISTORY: Map<u64, FeeRecord> = Map::new("fee_history");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct EarnRecord {
+    pub id: u64,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+    pub exchange_rate: Decimal,
+    /// yield that came in since the previous accrual
+    pub earnable: Uint128,
+    /// charged on `earnable`. left in the pool for the fee collector
+    pub fee: Uint128,
+    pub beneficiary: Addr,
+    pub beneficiary_amount: Uint128,
+    /// whatever depositors voted away from the beneficiary
+    pub split: Vec<Payout>,
+    /// paid to whoever called Harvest
+    pub keeper_bounty: Option<Payout>,
+    /// filled in by the swap's reply
+    pub swap: Option<SwapRecord>,
+    /// released by sponsors' matches, paid to the beneficiary with `beneficiary_amount`
+    pub matched: Option<Uint128>,
+    /// kept in the market as endowment rather than paid out
+    pub retained: Option<Uint128>,
+    /// taken into the insurance reserve ahead of the beneficiary's share
+    pub insured: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SwapRecord {
+    pub pair: Addr,
+    pub ask_asset: AssetInfo,
+    pub offer_amount: Uint128,
+    pub return_amount: Option<Uint128>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub recipient: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeRecord {
+    pub id: u64,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+    pub fee: Uint128,
+    pub fee_collector: Addr,
+}
+
+pub const IBC_TRANSFER_COUNT: Item<u64> = Item::new("ibc_transfer_count");
+/// every ibc payout to the beneficiary, keyed by a running count
+pub const IBC_TRANSFERS: Map<u64, IbcTransfer> = Map::new("ibc_transfers");
+/// (channel, packet sequence) of a pending attempt -> transfer id, for the ack/timeout callback
+pub const IBC_SEQUENCES: Map<(&str, u64), u64> = Map::new("ibc_sequences");
+/// stable paid out over ibc that hasn't been delivered or paid locally yet. a timed out or failed
+/// transfer is refunded to the pool, so this is kept back from sweeps
+pub const IBC_UNSETTLED: Item<Uint128> = Item::new("ibc_unsettled");
+/// the transfer just sent, until its reply brings the packet sequence
+pub const PENDING_IBC_TRANSFER: Item<u64> = Item::new("pending_ibc_transfer");
+/// the earn whose payout is being swapped, until the swap's reply
+pub const PENDING_SWAP: Item<u64> = Item::new("pending_swap");
+/// sponsor -> their matching budget. held in the pool, outside the market
+pub const MATCHES: Map<&Addr, Matching> = Map::new("matches");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct Matching {
+    pub ratio: Decimal,
+    /// most released by a single earn. zero for no cap
+    pub cap: Uint128,
+    /// seconds since epoch
+    pub expires_at: u64,
+    pub budget: Uint128,
+    pub released: Uint128,
+}
+
+/// money market -> its aToken. the markets the owner lets strategies go to
+pub const MARKET_WHITELIST: Map<&str, CanonicalAddr> = Map::new("market_whitelist");
+/// money market -> a strategy deposits are spread to, besides the config's money market
+pub const STRATEGIES: Map<&str, MarketStrategy> = Map::new("strategies");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MarketStrategy {
+    pub money_market: CanonicalAddr,
+    pub atoken: CanonicalAddr,
+    /// share of what the pool has invested. the config's money market gets what is left
+    pub weight: Decimal,
+}
+
+/// donor -> everything they have donated, before fees
+pub const DONORS: Map<&Addr, Uint128> = Map::new("donors");
+pub const TOTAL_DONATED: Item<Uint128> = Item::new("total_donated");
+/// the pairs the owner lets deposit routes swap through
+pub const PAIR_WHITELIST: Map<&str, Empty> = Map::new("pair_whitelist");
+/// denom -> how it is swapped into the stable when deposited
+pub const DEPOSIT_ROUTES: Map<&str, DepositRoute> = Map::new("deposit_routes");
+/// a deposit being swapped into the stable, until the swap's reply
+pub const PENDING_CONVERSION: Item<PendingConversion> = Item::new("pending_conversion");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingConversion {
+    pub depositor: Addr,
+    pub denom: String,
+    pub offer_amount: Uint128,
+    pub min_received: Uint128,
+    /// the pool's stable before the swap, so the reply can tell what it returned
+    pub balance_before: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcTransfer {
+    pub id: u64,
+    pub earn_id: u64,
+    /// paid here if every attempt fails
+    pub beneficiary: Addr,
+    pub channel_id: String,
+    pub remote_address: String,
+    pub amount: Uint128,
+    pub timeout_seconds: u64,
+    pub attempts: u32,
+    pub max_retries: u32,
+    pub sequence: Option<u64>,
+    pub status: IbcTransferStatus,
+}
@@ -1,19 +1,50 @@
-use crate::config::LastClaimed;
+use crate::config::{self, LastClaimed, DEFAULT_BENEFICIARY_CHANGE_DELAY};
 use crate::contract;
 use crate::error::ContractError;
-use crate::handler::core::calc_fee;
-use crate::mock_querier::mock_dependencies;
-use crate::querier::anchor::{ConfigResponse, EpochStateResponse, QueryMsg as AnchorQueryMsg};
-use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{from_binary, to_binary, Decimal, Uint128};
+use crate::handler::convert::CONVERT_REPLY_ID;
+use crate::handler::core::{calc_fee, register_dp_token};
+use crate::handler::ibc::{transfer_msg, IBC_TRANSFER_REPLY_ID, MSG_TRANSFER_TYPE_URL};
+use crate::handler::ownership::BENEFICIARY_PROPOSAL_EXPIRY;
+use crate::handler::swap::SWAP_REPLY_ID;
+use crate::migrations::ConfigV104;
+use crate::mock_querier::{mock_dependencies, CustomMockWasmQuerier};
+use crate::querier::anchor::{
+    ConfigResponse, Cw20HookMsg as AnchorCw20HookMsg, EpochStateResponse,
+    HandleMsg as AnchorHandleMsg, QueryMsg as AnchorQueryMsg,
+};
+use crate::querier::nft::ExecMsg as NftExecMsg;
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Env, Event,
+    OwnedDeps, Reply, Response, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cosmwasm_storage::singleton;
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw721::TokensResponse;
 use schemars::_serde_json::json;
 use std::str::FromStr;
-use yieldpay_core::pool_anchor_msg::InstantiateMsg;
+use yieldpay_core::dex::{Asset, AssetInfo, PairExecuteMsg};
+use yieldpay_core::fee::{FeeModel, FeePeriod, FeeTier};
+use yieldpay_core::pool_anchor_msg::{InstantiateMsg, MigrateMsg};
 use yieldpay_core::pool_anchor_response;
-use yieldpay_core::pool_msg::{ExecuteMsg, QueryMsg};
+use yieldpay_core::pool_msg::{
+    Cw20HookMsg, DepositRoute, ExecuteMsg, GovernanceAction, HarvestConfig, IbcLifecycleComplete,
+    IbcPayout, PayoutShare, QueryMsg, SudoMsg, SwapPayout, VoteOption,
+};
+use yieldpay_core::pool_resp::{
+    BeneficiaryChangeResponse, ClaimableRewardResponse, DepositCapacityResponse,
+    DepositRoutesResponse, DonorResponse, DonorsResponse, EarnHistoryResponse, EndowmentResponse,
+    FeeHistoryResponse, FeeResponse, HarvestConfigResponse, IbcTransferStatus,
+    IbcTransfersResponse, InsuranceReserveResponse, LiquidBufferResponse, MatchesResponse,
+    PayoutSplitResponse, PendingTransfersResponse, ProposalResponse, ProposalStatus,
+    StakedResponse, StrategiesResponse, YieldHookResponse,
+};
+use yieldpay_core::roles::{Role, RoleMembersResponse, RolesResponse};
+use yieldpay_core::yield_receiver::{YieldPeriod, YieldReceiverMsg};
 
 const MONEY_MARKET: &str = "money-market";
 const ATOKEN_CONTRACT: &str = "terra1m0rjzm27qetjj8fx89knnhl8frvlrmjcfultav";
+const DP_TOKEN: &str = "dp-token";
 
 #[test]
 fn instantiate() {
@@ -21,26 +52,7 @@ fn instantiate() {
     let env = mock_env();
     let info = mock_info("owner", &[]);
 
-    deps.querier.register_wasm_smart_query_handler(
-        MONEY_MARKET.to_string(),
-        Box::new(|x| match from_binary::<AnchorQueryMsg>(x).unwrap() {
-            AnchorQueryMsg::Config {} => to_binary(&ConfigResponse {
-                owner_addr: "".to_string(),
-                aterra_contract: ATOKEN_CONTRACT.to_string(),
-                interest_model: "".to_string(),
-                distribution_model: "".to_string(),
-                overseer_contract: "".to_string(),
-                collector_contract: "".to_string(),
-                distributor_contract: "".to_string(),
-                stable_denom: "uusd".to_string(),
-                max_borrow_factor: Default::default(),
-            }),
-            AnchorQueryMsg::EpochState { .. } => to_binary(&EpochStateResponse {
-                exchange_rate: Default::default(),
-                aterra_supply: Default::default(),
-            }),
-        }),
-    );
+    register_money_market(&mut deps);
 
     let msg = InstantiateMsg {
         pool_name: "test-pool".to_string(),
@@ -48,15 +60,19 @@ fn instantiate() {
         pool_description: "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.".to_string(),
         beneficiary: "test-beneficiary".to_string(),
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 123456,
         owner_can_change_config: false,
         nft_contract: None,
         nft_collection_active: None,
-        nft_collection_redeemed: None
+        nft_collection_redeemed: None,
+        beneficiary_change_delay: None,
+        stable_token: None,
     };
     let json = json!(msg).to_string();
     println!("{}", json);
@@ -68,26 +84,28 @@ fn instantiate() {
 #[test]
 fn test_calc_fee() {
     let last_claimed = LastClaimed {
-        last_claimed_at_block_height: 100,
+        period_start: 1900,
         fees_collected: Default::default(),
         total_earned_at_last_claimed: Default::default(),
     };
+    let percent = FeeModel::Percent {
+        rate: Decimal::from_str("0.05").unwrap(),
+        cap: Uint128::from(1_000_000_000u128),
+    };
     let (fee, _new_last) = calc_fee(
         Uint128::zero(),
-        Decimal::from_str("0.05").unwrap(),
-        Uint128::from(1_000_000_000u128),
-        1000u64,
+        &percent,
+        &FeePeriod::Seconds { seconds: 1000 },
         2000u64,
         last_claimed.clone(),
     );
     assert_eq!(fee, Uint128::zero());
     let (fee, new_last) = calc_fee(
         Uint128::from(1_000_000u64),
-        Decimal::from_str("0.05").unwrap(),
-        Uint128::from(1_000_000_000u128),
-        1000u64,
+        &percent,
+        &FeePeriod::Seconds { seconds: 1000 },
         2000u64,
-        last_claimed,
+        last_claimed.clone(),
     );
 
     assert_eq!(
@@ -98,10 +116,9 @@ fn test_calc_fee() {
     assert_eq!(fee, Uint128::from(50_000u64));
     let (fee, new_last2) = calc_fee(
         Uint128::from(1_000_000u64),
-        Decimal::from_str("0.05").unwrap(),
-        Uint128::from(1_000_000_000u128),
-        1000u64,
-        3000u64,
+        &percent,
+        &FeePeriod::Seconds { seconds: 1000 },
+        2500u64,
         new_last,
     );
     assert_eq!(
@@ -111,6 +128,201 @@ fn test_calc_fee() {
     assert_eq!(new_last2.fees_collected, Uint128::from(100_000u64));
 
     assert_eq!(fee, Uint128::from(50_000u64));
+
+    // the cap is per period
+    let capped = FeeModel::Percent {
+        rate: Decimal::from_str("0.05").unwrap(),
+        cap: Uint128::from(80_000u64),
+    };
+    let (fee, new_last) = calc_fee(
+        Uint128::from(1_000_000u64),
+        &capped,
+        &FeePeriod::Seconds { seconds: 1000 },
+        2000u64,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::from(50_000u64));
+    let (fee, new_last) = calc_fee(
+        Uint128::from(1_000_000u64),
+        &capped,
+        &FeePeriod::Seconds { seconds: 1000 },
+        2500u64,
+        new_last,
+    );
+    assert_eq!(fee, Uint128::from(30_000u64));
+    assert_eq!(new_last.fees_collected, Uint128::from(80_000u64));
+    let (fee, new_last) = calc_fee(
+        Uint128::from(1_000_000u64),
+        &capped,
+        &FeePeriod::Seconds { seconds: 1000 },
+        3000u64,
+        new_last,
+    );
+    assert_eq!(fee, Uint128::from(50_000u64));
+    assert_eq!(new_last.period_start, 2900u64);
+    assert_eq!(new_last.fees_collected, Uint128::from(50_000u64));
+
+    // flat, never more than was earned
+    let flat = FeeModel::Flat {
+        amount: Uint128::from(20_000u64),
+    };
+    let (fee, _) = calc_fee(
+        Uint128::from(1_000_000u64),
+        &flat,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::from(20_000u64));
+    let (fee, new_last) = calc_fee(
+        Uint128::from(5_000u64),
+        &flat,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::from(5_000u64));
+    assert_eq!(new_last.total_earned_at_last_claimed, Uint128::zero());
+
+    let tiered = FeeModel::Tiered {
+        tiers: vec![
+            FeeTier {
+                threshold: Uint128::zero(),
+                rate: Decimal::percent(10),
+            },
+            FeeTier {
+                threshold: Uint128::from(1_000_000u64),
+                rate: Decimal::percent(5),
+            },
+        ],
+        cap: Uint128::zero(),
+    };
+    let (fee, _) = calc_fee(
+        Uint128::from(500_000u64),
+        &tiered,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::from(50_000u64));
+    let (fee, _) = calc_fee(
+        Uint128::from(2_000_000u64),
+        &tiered,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::from(100_000u64));
+
+    let free_below = FeeModel::FreeBelow {
+        threshold: Uint128::from(100_000u64),
+        rate: Decimal::percent(5),
+        cap: Uint128::zero(),
+    };
+    let (fee, _) = calc_fee(
+        Uint128::from(99_999u64),
+        &free_below,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed.clone(),
+    );
+    assert_eq!(fee, Uint128::zero());
+    let (fee, _) = calc_fee(
+        Uint128::from(100_000u64),
+        &free_below,
+        &FeePeriod::PerClaim {},
+        2000,
+        last_claimed,
+    );
+    assert_eq!(fee, Uint128::from(5_000u64));
+}
+
+#[test]
+fn test_fee_period() {
+    let per_claim = FeePeriod::PerClaim {};
+    assert!(per_claim.is_new_period(1000, 1000));
+    assert_eq!(per_claim.start_of(1000, 1500), 1500);
+
+    // blocks carry over at the average block time
+    assert_eq!(
+        FeePeriod::from_blocks(100),
+        FeePeriod::Seconds { seconds: 600 }
+    );
+    assert_eq!(FeePeriod::from_blocks(0), per_claim);
+    assert!(!FeePeriod::Seconds { seconds: 0 }.is_valid());
+
+    // 2024-02-15 falls in the month from 2024-02-01 and the quarter from 2024-01-01
+    let feb_15 = 1_707_955_200u64;
+    assert_eq!(FeePeriod::Monthly {}.start_of(0, feb_15), 1_706_745_600);
+    assert_eq!(FeePeriod::Quarterly {}.start_of(0, feb_15), 1_704_067_200);
+    // 2024-05-10 is in the quarter from 2024-04-01
+    assert_eq!(
+        FeePeriod::Quarterly {}.start_of(0, 1_715_299_200),
+        1_711_929_600
+    );
+    assert!(!FeePeriod::Monthly {}.is_new_period(1_706_745_600, feb_15));
+    // 2024-03-01
+    assert!(FeePeriod::Monthly {}.is_new_period(1_706_745_600, 1_709_251_200));
+}
+
+#[test]
+fn test_migrate_legacy_fee_max() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let canon = |addr: &str| deps.api.addr_canonicalize(addr).unwrap();
+    let legacy = ConfigV104 {
+        this: canon(MOCK_CONTRACT_ADDR),
+        owner: canon("owner"),
+        beneficiary: canon("beneficiary"),
+        fee_collector: canon("fee-collector"),
+        fee_amount: Decimal::percent(5),
+        fee_max: Uint128::from(1_000u64),
+        fee_reset_every_num_blocks: 100,
+        money_market: canon(MONEY_MARKET),
+        atoken: canon(ATOKEN_CONTRACT),
+        stable_denom: "uusd".to_string(),
+        dp_token: canon(DP_TOKEN),
+        pool_name: "pool".to_string(),
+        pool_title: "".to_string(),
+        pool_description: "".to_string(),
+        owner_can_change_config: false,
+        nft_contract: None,
+        nft_collection_active: None,
+        nft_collection_redeemed: None,
+    };
+    singleton(&mut deps.storage, b"config_v104")
+        .save(&legacy)
+        .unwrap();
+    cw2::set_contract_version(&mut deps.storage, "gofundust-pool-anchor", "0.1.4").unwrap();
+
+    contract::migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+    // before: 5% of each 100_000 claim, capped at 1_000 on every claim however close together
+    let config = config::read(&deps.storage).unwrap();
+    assert_eq!(config.fee_period, FeePeriod::PerClaim {});
+    let now = env.block.time.seconds();
+    let last_claimed = LastClaimed {
+        period_start: now,
+        fees_collected: Uint128::zero(),
+        total_earned_at_last_claimed: Uint128::zero(),
+    };
+    let (fee, last_claimed) = calc_fee(
+        Uint128::from(100_000u64),
+        &config.fee_model,
+        &config.fee_period,
+        now,
+        last_claimed,
+    );
+    assert_eq!(fee, Uint128::from(1_000u64));
+    // after: a second claim in the same block still pays the full per claim cap
+    let (fee, _) = calc_fee(
+        Uint128::from(100_000u64),
+        &config.fee_model,
+        &config.fee_period,
+        now,
+        last_claimed,
+    );
+    assert_eq!(fee, Uint128::from(1_000u64));
 }
 
 #[test]
@@ -119,26 +331,7 @@ fn test_nft_set_reset() {
     let env = mock_env();
     let info = mock_info("owner", &[]);
 
-    deps.querier.register_wasm_smart_query_handler(
-        MONEY_MARKET.to_string(),
-        Box::new(|x| match from_binary::<AnchorQueryMsg>(x).unwrap() {
-            AnchorQueryMsg::Config {} => to_binary(&ConfigResponse {
-                owner_addr: "".to_string(),
-                aterra_contract: ATOKEN_CONTRACT.to_string(),
-                interest_model: "".to_string(),
-                distribution_model: "".to_string(),
-                overseer_contract: "".to_string(),
-                collector_contract: "".to_string(),
-                distributor_contract: "".to_string(),
-                stable_denom: "uusd".to_string(),
-                max_borrow_factor: Default::default(),
-            }),
-            AnchorQueryMsg::EpochState { .. } => to_binary(&EpochStateResponse {
-                exchange_rate: Default::default(),
-                aterra_supply: Default::default(),
-            }),
-        }),
-    );
+    register_money_market(&mut deps);
 
     let msg = InstantiateMsg {
         pool_name: "test-pool".to_string(),
@@ -146,15 +339,19 @@ fn test_nft_set_reset() {
         pool_description: "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.".to_string(),
         beneficiary: "test-beneficiary".to_string(),
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 123456,
         owner_can_change_config: false,
         nft_contract: None,
         nft_collection_active: None,
-        nft_collection_redeemed: None
+        nft_collection_redeemed: None,
+        beneficiary_change_delay: None,
+        stable_token: None,
     };
 
     let _ = contract::instantiate(deps.as_mut(), env.clone(), info.clone(), msg)
@@ -174,15 +371,19 @@ fn test_nft_set_reset() {
         pool_description: "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.".to_string(),
         beneficiary: "test-beneficiary".to_string(),
         fee_collector: "test-fee-collector".to_string(),
-        fee_amount: "0.05".to_string(),
-        fee_max: Default::default(),
-        fee_reset_every_num_blocks: 0,
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
         money_market: MONEY_MARKET.to_string(),
         dp_code_id: 123456,
         owner_can_change_config: false,
         nft_contract: Some("nft-minter".to_string()),
         nft_collection_active: Some(2u64),
-        nft_collection_redeemed: Some(17u64)
+        nft_collection_redeemed: Some(17u64),
+        beneficiary_change_delay: None,
+        stable_token: None,
     };
     let _ = contract::instantiate(deps.as_mut(), env.clone(), info.clone(), msg)
         .expect("testing: should init contract");
@@ -263,3 +464,3378 @@ fn test_nft_set_reset() {
         }
     }
 }
+
+fn mock_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        pool_name: "test-pool".to_string(),
+        pool_title: "title".to_string(),
+        pool_description: "description".to_string(),
+        beneficiary: "test-beneficiary".to_string(),
+        fee_collector: "test-fee-collector".to_string(),
+        fee_model: FeeModel::Percent {
+            rate: Decimal::percent(5),
+            cap: Uint128::zero(),
+        },
+        fee_period: FeePeriod::PerClaim {},
+        money_market: MONEY_MARKET.to_string(),
+        dp_code_id: 123456,
+        owner_can_change_config: false,
+        nft_contract: None,
+        nft_collection_active: None,
+        nft_collection_redeemed: None,
+        beneficiary_change_delay: None,
+        stable_token: None,
+    }
+}
+
+fn register_money_market(deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>) {
+    register_market(deps, MONEY_MARKET, ATOKEN_CONTRACT, Decimal::zero());
+}
+
+/// an anchor style money market at `market`, handing out `atoken` at `exchange_rate`
+fn register_market(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    market: &str,
+    atoken: &str,
+    exchange_rate: Decimal,
+) {
+    let atoken = atoken.to_string();
+    deps.querier.register_wasm_smart_query_handler(
+        market.to_string(),
+        Box::new(move |x| match from_binary::<AnchorQueryMsg>(x).unwrap() {
+            AnchorQueryMsg::Config {} => to_binary(&ConfigResponse {
+                owner_addr: "".to_string(),
+                aterra_contract: atoken.clone(),
+                interest_model: "".to_string(),
+                distribution_model: "".to_string(),
+                overseer_contract: "".to_string(),
+                collector_contract: "".to_string(),
+                distributor_contract: "".to_string(),
+                stable_denom: "uusd".to_string(),
+                max_borrow_factor: Default::default(),
+            }),
+            AnchorQueryMsg::EpochState { .. } => to_binary(&EpochStateResponse {
+                exchange_rate,
+                aterra_supply: Default::default(),
+            }),
+        }),
+    );
+}
+
+/// `token` reports a balance of `balance` for everyone
+fn register_balance(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    token: &str,
+    balance: u128,
+) {
+    deps.querier.register_wasm_smart_query_handler(
+        token.to_string(),
+        Box::new(move |_| {
+            to_binary(&cw20::BalanceResponse {
+                balance: Uint128::from(balance),
+            })
+        }),
+    );
+}
+
+/// a pool instantiated by "owner" from `msg`, over the default money market
+fn mock_pool(msg: InstantiateMsg) -> OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier> {
+    let mut deps = mock_dependencies(&[]);
+    register_money_market(&mut deps);
+    contract::instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    deps
+}
+
+fn query_pending_transfers(
+    deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+) -> PendingTransfersResponse {
+    from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::PendingTransfers {}).unwrap())
+        .unwrap()
+}
+
+#[test]
+fn test_beneficiary_handover() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+
+    let configure = ExecuteMsg::Configure {
+        beneficiary: Some("new-beneficiary".to_string()),
+        fee_collector: None,
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        configure,
+    )
+    .unwrap();
+
+    // nothing changes until the new beneficiary accepts
+    let qry = from_binary::<pool_anchor_response::ConfigResponse>(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(qry.beneficiary, "test-beneficiary");
+    let pending = query_pending_transfers(&deps);
+    assert!(pending.owner.is_none());
+    assert_eq!(pending.beneficiary.unwrap().proposed, "new-beneficiary");
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::AcceptBeneficiary {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    let mut late = env.clone();
+    late.block.time = late
+        .block
+        .time
+        .plus_seconds(BENEFICIARY_PROPOSAL_EXPIRY + 1);
+    let err = contract::execute(
+        deps.as_mut(),
+        late,
+        mock_info("new-beneficiary", &[]),
+        ExecuteMsg::AcceptBeneficiary {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TransferExpired("beneficiary".to_string())
+    );
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("new-beneficiary", &[]),
+        ExecuteMsg::AcceptBeneficiary {},
+    )
+    .unwrap();
+    assert!(query_pending_transfers(&deps).beneficiary.is_none());
+
+    // accepted, but depositors still get the exit window
+    let change = from_binary::<Option<BeneficiaryChangeResponse>>(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::BeneficiaryChange {}).unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(change.current_beneficiary, "test-beneficiary");
+    assert_eq!(change.new_beneficiary, "new-beneficiary");
+    assert_eq!(change.delay, DEFAULT_BENEFICIARY_CHANGE_DELAY);
+    assert_eq!(
+        change.effective_at,
+        env.block.time.seconds() + DEFAULT_BENEFICIARY_CHANGE_DELAY
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::ApplyBeneficiaryChange {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BeneficiaryChangeNotDue {
+            effective_at: change.effective_at
+        }
+    );
+
+    let mut due = env.clone();
+    due.block.time = due
+        .block
+        .time
+        .plus_seconds(DEFAULT_BENEFICIARY_CHANGE_DELAY);
+    contract::execute(
+        deps.as_mut(),
+        due.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::ApplyBeneficiaryChange {},
+    )
+    .unwrap();
+    let qry = from_binary::<pool_anchor_response::ConfigResponse>(
+        &contract::query(deps.as_ref(), due.clone(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(qry.beneficiary, "new-beneficiary");
+    let change = from_binary::<Option<BeneficiaryChangeResponse>>(
+        &contract::query(deps.as_ref(), due, QueryMsg::BeneficiaryChange {}).unwrap(),
+    )
+    .unwrap();
+    assert!(change.is_none());
+}
+
+#[test]
+fn test_beneficiary_change_delay() {
+    let mut deps = mock_dependencies(&[]);
+    register_money_market(&mut deps);
+    let env = mock_env();
+    let day = 86_400;
+    let mut msg = mock_instantiate_msg();
+    msg.beneficiary_change_delay = Some(100);
+    let err = contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BeneficiaryChangeDelayTooShort { min: day }
+    );
+    msg.beneficiary_change_delay = Some(2 * day);
+    contract::instantiate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay: day },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BeneficiaryChangeDelayTooShort { min: day }
+    );
+
+    let hand_over = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                     env: &Env,
+                     from: &str,
+                     to: &str| {
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(from, &[]),
+            ExecuteMsg::Configure {
+                beneficiary: Some(to.to_string()),
+                fee_collector: None,
+            },
+        )
+        .unwrap();
+        contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(to, &[]),
+            ExecuteMsg::AcceptBeneficiary {},
+        )
+        .unwrap();
+    };
+    let query_change = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                        env: &Env| {
+        from_binary::<Option<BeneficiaryChangeResponse>>(
+            &contract::query(deps.as_ref(), env.clone(), QueryMsg::BeneficiaryChange {}).unwrap(),
+        )
+        .unwrap()
+    };
+    hand_over(&mut deps, &env, "test-beneficiary", "new-beneficiary");
+
+    // the old beneficiary stays in charge for the exit window
+    let noop = ExecuteMsg::Configure {
+        beneficiary: None,
+        fee_collector: None,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("new-beneficiary", &[]),
+        noop.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let change = query_change(&deps, &env).unwrap();
+    assert_eq!(change.new_beneficiary, "new-beneficiary");
+    assert_eq!(change.effective_at, env.block.time.seconds() + 2 * day);
+
+    // once it is due the queries report it as made, before anyone touches the pool
+    let mut due = env;
+    due.block.time = due.block.time.plus_seconds(2 * day);
+    assert_eq!(query_change(&deps, &due), None);
+    let qry = from_binary::<pool_anchor_response::ConfigResponse>(
+        &contract::query(deps.as_ref(), due.clone(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(qry.beneficiary, "new-beneficiary");
+    // and it lands without anyone calling ApplyBeneficiaryChange
+    contract::execute(
+        deps.as_mut(),
+        due.clone(),
+        mock_info("new-beneficiary", &[]),
+        noop,
+    )
+    .unwrap();
+
+    // a shorter delay waits out the current one
+    contract::execute(
+        deps.as_mut(),
+        due.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay: day },
+    )
+    .unwrap();
+    hand_over(&mut deps, &due, "new-beneficiary", "third-beneficiary");
+    let change = query_change(&deps, &due).unwrap();
+    assert_eq!(change.effective_at, due.block.time.seconds() + 2 * day);
+    assert_eq!(change.delay, 2 * day);
+
+    let mut later = due;
+    later.block.time = later.block.time.plus_seconds(2 * day);
+    hand_over(&mut deps, &later, "third-beneficiary", "fourth-beneficiary");
+    let change = query_change(&deps, &later).unwrap();
+    assert_eq!(change.effective_at, later.block.time.seconds() + day);
+    assert_eq!(change.delay, day);
+
+    // a longer one applies at once
+    contract::execute(
+        deps.as_mut(),
+        later.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay: 3 * day },
+    )
+    .unwrap();
+    assert_eq!(query_change(&deps, &later).unwrap().delay, 3 * day);
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    let owner = mock_info("owner", &[]);
+
+    let propose = ExecuteMsg::ProposeNewOwner {
+        owner: "new-owner".to_string(),
+        expires_in: 100,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(deps.as_mut(), env.clone(), owner.clone(), propose).unwrap();
+    let pending = query_pending_transfers(&deps).owner.unwrap();
+    assert_eq!(pending.proposed, "new-owner");
+    assert_eq!(pending.expires_at, env.block.time.seconds() + 100);
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("new-owner", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    let qry = from_binary::<pool_anchor_response::ConfigResponse>(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(qry.owner, "new-owner");
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("new-owner", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoPendingTransfer("owner".to_string()));
+}
+
+fn stake(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    env: &Env,
+    staker: &str,
+    amount: u128,
+) {
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(DP_TOKEN, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: staker.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::Stake {}).unwrap(),
+        }),
+    )
+    .unwrap();
+}
+
+fn vote(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    env: &Env,
+    voter: &str,
+    proposal_id: u64,
+    vote: VoteOption,
+) -> Result<Response, ContractError> {
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(voter, &[]),
+        ExecuteMsg::CastVote { proposal_id, vote },
+    )
+}
+
+#[test]
+fn test_governance() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    // only DP can be staked
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("other-token", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::from(60u128),
+            msg: to_binary(&Cw20HookMsg::Stake {}).unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    stake(&mut deps, &env, "alice", 60);
+    stake(&mut deps, &env, "bob", 40);
+
+    let propose = ExecuteMsg::Propose {
+        action: GovernanceAction::ChangePayoutSplit {
+            split: vec![PayoutShare {
+                recipient: "carol".to_string(),
+                share: Decimal::percent(25),
+            }],
+        },
+        description: "give carol a quarter".to_string(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("mallory", &[]),
+        propose.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVotingPower);
+
+    env.block.height += 1;
+    contract::execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), propose).unwrap();
+    // stake added after the proposal doesn't count towards it
+    stake(&mut deps, &env, "mallory", 1_000);
+    assert_eq!(
+        vote(&mut deps, &env, "mallory", 1, VoteOption::No).unwrap_err(),
+        ContractError::NoVotingPower
+    );
+
+    vote(&mut deps, &env, "alice", 1, VoteOption::Yes).unwrap();
+    assert_eq!(
+        vote(&mut deps, &env, "alice", 1, VoteOption::No).unwrap_err(),
+        ContractError::AlreadyVoted(1)
+    );
+    vote(&mut deps, &env, "bob", 1, VoteOption::No).unwrap();
+    let execute = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        execute.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VotingOpen(1));
+
+    env.block.time = env.block.time.plus_seconds(7 * 86_400);
+    let proposal = from_binary::<ProposalResponse>(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Proposal { proposal_id: 1 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(proposal.yes_votes, Uint128::from(60u128));
+    assert_eq!(proposal.no_votes, Uint128::from(40u128));
+    // quorum is against what was staked when the proposal was made
+    assert_eq!(proposal.total_weight, Uint128::from(100u128));
+    assert_eq!(proposal.status, ProposalStatus::Passed);
+    assert_eq!(
+        vote(&mut deps, &env, "alice", 1, VoteOption::Yes).unwrap_err(),
+        ContractError::VotingClosed(1)
+    );
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        execute.clone(),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        execute,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalExecuted(1));
+    // the new split waits out the exit window
+    let split = from_binary::<PayoutSplitResponse>(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::PayoutSplit {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(split.beneficiary, "test-beneficiary");
+    assert!(split.split.is_empty());
+    let pending = split.pending.unwrap();
+    assert_eq!(pending.split[0].recipient, "carol");
+    assert_eq!(pending.effective_at, env.block.time.seconds() + 7 * 86_400);
+
+    env.block.time = env.block.time.plus_seconds(7 * 86_400);
+    let split = from_binary::<PayoutSplitResponse>(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::PayoutSplit {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(split.split[0].recipient, "carol");
+    assert_eq!(split.pending, None);
+
+    // a proposal nobody votes on misses quorum
+    env.block.height += 1;
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Propose {
+            action: GovernanceAction::CloseFund {},
+            description: "wind it down".to_string(),
+        },
+    )
+    .unwrap();
+    env.block.time = env.block.time.plus_seconds(7 * 86_400);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteProposal { proposal_id: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed(2));
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Unstake {
+            amount: Uint128::from(41u128),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientStake { .. }));
+    let res = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bob", &[]),
+        ExecuteMsg::Unstake {
+            amount: Uint128::from(40u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    let staked = from_binary::<StakedResponse>(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Staked {
+                address: "bob".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(staked.amount.is_zero());
+}
+
+#[test]
+fn test_governance_close_fund() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    let close_fund = ExecuteMsg::Propose {
+        action: GovernanceAction::CloseFund {},
+        description: "wind it down".to_string(),
+    };
+
+    // alice alone has too little of the stake to make quorum
+    stake(&mut deps, &env, "alice", 5);
+    stake(&mut deps, &env, "bob", 95);
+    env.block.height += 1;
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        close_fund.clone(),
+    )
+    .unwrap();
+    vote(&mut deps, &env, "alice", 1, VoteOption::Yes).unwrap();
+    env.block.time = env.block.time.plus_seconds(7 * 86_400);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteProposal { proposal_id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ProposalNotPassed(1));
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        close_fund,
+    )
+    .unwrap();
+    vote(&mut deps, &env, "alice", 2, VoteOption::Yes).unwrap();
+    vote(&mut deps, &env, "bob", 2, VoteOption::Yes).unwrap();
+    env.block.time = env.block.time.plus_seconds(7 * 86_400);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteProposal { proposal_id: 2 },
+    )
+    .unwrap();
+
+    // closing only stops new deposits, for good
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("depositor", &[Coin::new(100, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FundClosed);
+    // depositors still redeem, and what is left keeps earning for the beneficiary, fee taken
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(DP_TOKEN, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "depositor".to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&Cw20HookMsg::Redeem {}).unwrap(),
+        }),
+    )
+    .unwrap();
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+    contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_roles() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    let query_roles = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                       address: &str| {
+        from_binary::<RolesResponse>(
+            &contract::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Roles {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .roles
+    };
+    assert_eq!(query_roles(&deps, "owner"), vec![Role::Owner]);
+    assert_eq!(
+        query_roles(&deps, "test-beneficiary"),
+        vec![Role::Beneficiary]
+    );
+    assert_eq!(
+        query_roles(&deps, "test-fee-collector"),
+        vec![Role::FeeCollector]
+    );
+
+    // only the owner hands out admin, admins hand out the rest
+    let grant = |role: Role, address: &str| ExecuteMsg::GrantRole {
+        role,
+        address: address.to_string(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        grant(Role::Admin, "admin"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            action: "grant_role".to_string(),
+            expected: "owner".to_string(),
+            actual: "test-beneficiary".to_string(),
+        }
+    );
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        grant(Role::Admin, "admin"),
+    )
+    .unwrap();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        grant(Role::Guardian, "guardian"),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        grant(Role::Admin, "another-admin"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        grant(Role::Beneficiary, "admin"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RoleNotGrantable("beneficiary".to_string())
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        grant(Role::FeeCollector, "admin"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::RoleNotGrantable("fee_collector".to_string())
+    );
+
+    // a guardian can pause deposits, but it takes the owner or an admin to unpause them
+    let pause = |paused: bool| ExecuteMsg::PauseDeposits { paused };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        pause(true),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("guardian", &[]),
+        pause(true),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(1_000, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DepositsPaused);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("guardian", &[]),
+        pause(false),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        pause(false),
+    )
+    .unwrap();
+
+    // beyond that, roles don't reach into the beneficiary's or fee collector's handlers
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Configure {
+            beneficiary: Some("new-beneficiary".to_string()),
+            fee_collector: None,
+        },
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::DropBeneficiaryProposal {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    assert!(query_pending_transfers(&deps).beneficiary.is_some());
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        grant(Role::Operator, "operator"),
+    )
+    .unwrap_err();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        grant(Role::Operator, "operator"),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    // but an operator can run the earns
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        ExecuteMsg::SetBeneficiaryChangeDelay { delay: 0 },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("admin", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "admin".to_string(),
+            expires_in: 100,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    // the fee collector role has a single holder, and follows the fee collector
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::Configure {
+            beneficiary: None,
+            fee_collector: Some("new-fee-collector".to_string()),
+        },
+    )
+    .unwrap();
+    assert!(query_roles(&deps, "test-fee-collector").is_empty());
+    assert_eq!(
+        query_roles(&deps, "new-fee-collector"),
+        vec![Role::FeeCollector]
+    );
+    // the owner (the factory) can move it on, e.g. to a fee splitter
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Configure {
+            beneficiary: None,
+            fee_collector: Some("fee-splitter".to_string()),
+        },
+    )
+    .unwrap();
+    assert!(query_roles(&deps, "new-fee-collector").is_empty());
+    assert_eq!(query_roles(&deps, "fee-splitter"), vec![Role::FeeCollector]);
+    // but moving the fee collector doesn't let a locked out owner pick the beneficiary
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Configure {
+            beneficiary: Some("owner".to_string()),
+            fee_collector: Some("fee-splitter".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            action: "configure_beneficiary".to_string(),
+            expected: "beneficiary".to_string(),
+            actual: "owner".to_string(),
+        }
+    );
+    assert_eq!(
+        query_pending_transfers(&deps).beneficiary.unwrap().proposed,
+        "new-beneficiary"
+    );
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RevokeRole {
+            role: Role::Admin,
+            address: "admin".to_string(),
+        },
+    )
+    .unwrap();
+    let members = from_binary::<RoleMembersResponse>(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::RoleMembers {
+                role: Role::Admin,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(members.members.is_empty());
+}
+
+#[test]
+fn test_typed_errors() {
+    let mut deps = mock_dependencies(&[]);
+    register_money_market(&mut deps);
+    let env = mock_env();
+
+    // nothing stored before instantiate
+    let err = contract::query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap_err();
+    assert_eq!(err, ContractError::ConfigMissing("config".to_string()));
+    let err = contract::query(deps.as_ref(), env.clone(), QueryMsg::LastClaimed {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ConfigMissing("last_claimed".to_string())
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ConfigMissing("config".to_string()));
+
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        mock_instantiate_msg(),
+    )
+    .unwrap();
+
+    // the DP token only arrives with the instantiate reply
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("depositor", &[Coin::new(1_000_000, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DpTokenNotRegistered);
+    for hook in [Cw20HookMsg::Redeem {}, Cw20HookMsg::Stake {}] {
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(DP_TOKEN, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "depositor".to_string(),
+                amount: Uint128::from(100u128),
+                msg: to_binary(&hook).unwrap(),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::DpTokenNotRegistered);
+    }
+    let err = contract::query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::DepositAmountOf {
+            owner: "depositor".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DpTokenNotRegistered);
+    // config still answers, with a blank DP token
+    let config: pool_anchor_response::ConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap())
+            .unwrap();
+    assert_eq!(config.dp_token, "");
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::Configure {
+            beneficiary: None,
+            fee_collector: Some("x".to_string()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAddress("x".to_string()));
+    let err = contract::query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Staked {
+            address: "Not-Normalized".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidAddress("Not-Normalized".to_string())
+    );
+}
+
+/// money market at an exchange rate of 1.1, with `atokens` held by the pool and `dp_supply` DP out
+fn register_earnings(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    atokens: u128,
+    dp_supply: u128,
+) {
+    register_market(
+        deps,
+        MONEY_MARKET,
+        ATOKEN_CONTRACT,
+        Decimal::from_str("1.1").unwrap(),
+    );
+    register_balance(deps, ATOKEN_CONTRACT, atokens);
+    deps.querier.register_wasm_smart_query_handler(
+        DP_TOKEN.to_string(),
+        Box::new(move |_| {
+            to_binary(&cw20::TokenInfoResponse {
+                name: "dp".to_string(),
+                symbol: "DP".to_string(),
+                decimals: 6,
+                total_supply: Uint128::from(dp_supply),
+            })
+        }),
+    );
+}
+
+#[test]
+fn test_earn_and_collect_fees() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    // 100_000 earned, 5% of it as fee
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.claimable, Uint128::from(95_000u128));
+    assert_eq!(claimable.fee, Uint128::from(5_000u128));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    // the fee stays behind for the fee collector. redeeming rounds down, the 1 left stays owed
+    assert_eq!(resp.messages.len(), 2);
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+
+    // nothing earned since, so nothing to record
+    register_earnings(&mut deps, 1_000_000, 1_100_000);
+    env.block.height += 10;
+    env.block.time = env.block.time.plus_seconds(60);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToEarn);
+
+    let history: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EarnHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history.earns.len(), 1);
+    let first = &history.earns[0];
+    assert_eq!(first.id, 1);
+    assert_eq!(first.exchange_rate, Decimal::from_str("1.1").unwrap());
+    assert_eq!(first.earnable, Uint128::from(100_000u128));
+    assert_eq!(first.fee, Uint128::from(5_000u128));
+    assert_eq!(first.beneficiary, "test-beneficiary");
+    assert_eq!(first.beneficiary_amount, Uint128::from(94_999u128));
+    assert!(first.split.is_empty());
+
+    let page: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EarnHistory {
+                start_after: Some(1),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(page.earns.is_empty());
+
+    // the fee is still sitting in the pool until the fee collector takes it
+    register_earnings(&mut deps, 1_000_000, 1_095_000);
+    let fee: FeeResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Fee {}).unwrap())
+            .unwrap();
+    assert_eq!(fee.accrued, Uint128::from(5_000u128));
+    assert_eq!(fee.fee, Uint128::zero());
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-fee-collector".to_string(),
+            amount: vec![Coin::new(4_999, "uusd")],
+        })
+    );
+    register_earnings(&mut deps, 1_000_000, 1_100_000);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFeesAccrued);
+
+    let fees: FeeHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::FeeHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(fees.fees.len(), 1);
+    assert_eq!(fees.fees[0].id, 1);
+    assert_eq!(fees.fees[0].fee, Uint128::from(4_999u128));
+    assert_eq!(fees.fees[0].fee_collector, "test-fee-collector");
+}
+
+#[test]
+fn test_harvest() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::HarvestDisabled);
+
+    let harvest = HarvestConfig {
+        min_interval: 3600,
+        min_earnable: Uint128::from(200_000u128),
+        bounty: Decimal::percent(1),
+        bounty_cap: Uint128::from(500u128),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::SetHarvestConfig {
+            harvest: Some(harvest.clone()),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetHarvestConfig {
+            harvest: Some(HarvestConfig {
+                bounty: Decimal::percent(20),
+                ..harvest.clone()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidHarvestConfig {
+            max: Decimal::percent(10)
+        }
+    );
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetHarvestConfig {
+            harvest: Some(harvest.clone()),
+        },
+    )
+    .unwrap();
+
+    // never paid out, so it is due straight away. the bounty is capped
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1..]
+            .iter()
+            .map(|msg| msg.msg.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "test-beneficiary".to_string(),
+                amount: vec![Coin::new(94_499, "uusd")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper".to_string(),
+                amount: vec![Coin::new(500, "uusd")],
+            }),
+        ]
+    );
+
+    // 10_000 more earned, the 5_000 fee still in the pool. not enough, and too soon
+    register_earnings(&mut deps, 1_000_000, 1_085_000);
+    env.block.time = env.block.time.plus_seconds(60);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap_err();
+    let last_payout_at = env.block.time.seconds() - 60;
+    assert_eq!(
+        err,
+        ContractError::HarvestNotDue {
+            due_at: last_payout_at + 3600
+        }
+    );
+
+    env.block.time = env.block.time.plus_seconds(3600);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[2].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: vec![Coin::new(95, "uusd")],
+        })
+    );
+    let history: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EarnHistory {
+                start_after: Some(1),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        history.earns[0].beneficiary_amount,
+        Uint128::from(9_405u128)
+    );
+    assert_eq!(
+        history.earns[0].keeper_bounty.as_ref().unwrap().amount,
+        Uint128::from(95u128)
+    );
+
+    let config: HarvestConfigResponse = from_binary(
+        &contract::query(deps.as_ref(), env.clone(), QueryMsg::HarvestConfig {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.harvest, Some(harvest));
+    assert_eq!(config.last_payout_at, Some(env.block.time.seconds()));
+
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetHarvestConfig { harvest: None },
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteMsg::Harvest {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::HarvestDisabled);
+}
+
+#[test]
+fn test_earn_limits() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    let set_limits = ExecuteMsg::SetEarnLimits {
+        min_earn_amount: Some(Uint128::from(10_000u128)),
+        min_blocks_between_earn: Some(100),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        set_limits.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_limits,
+    )
+    .unwrap();
+
+    // never paid out, so the interval doesn't hold it back
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.min_earn_amount, Uint128::from(10_000u128));
+    assert_eq!(claimable.next_earn_allowed_at, 0);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.next_earn_allowed_at, env.block.height + 100);
+
+    // 9_500 more for the beneficiary, under the minimum. 5_000 fee from before still in the pool
+    register_earnings(&mut deps, 1_000_000, 1_085_000);
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::EarnTooSoon {
+            next_earn_allowed_at: env.block.height + 100
+        }
+    );
+    env.block.height += 100;
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::EarnBelowMinimum {
+            amount: Uint128::from(9_501u128),
+            minimum: Uint128::from(10_000u128)
+        }
+    );
+
+    // the beneficiary's minimum doesn't hold back fee collections
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::CollectFees {},
+    )
+    .unwrap();
+
+    // lowering it lets the earn through
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-fee-collector", &[]),
+        ExecuteMsg::SetEarnLimits {
+            min_earn_amount: Some(Uint128::from(5_000u128)),
+            min_blocks_between_earn: None,
+        },
+    )
+    .unwrap();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_yield_hook() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let mut env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    // the beneficiary's call, nobody else's
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetYieldHook { enabled: true },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetYieldHook { enabled: true },
+    )
+    .unwrap();
+    let hook: YieldHookResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::YieldHook {}).unwrap())
+            .unwrap();
+    assert!(hook.enabled);
+
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "test-beneficiary".to_string(),
+            msg: to_binary(&YieldReceiverMsg::YieldReceived {
+                pool: MOCK_CONTRACT_ADDR.to_string(),
+                amount: Uint128::from(94_999u128),
+                period: YieldPeriod {
+                    start: None,
+                    end: env.block.time.seconds(),
+                },
+            })
+            .unwrap(),
+            funds: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+
+    // the next one covers the time since the first
+    register_earnings(&mut deps, 1_000_000, 1_085_000);
+    let first_at = env.block.time.seconds();
+    env.block.time = env.block.time.plus_seconds(60);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    match &resp.messages[1].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+            YieldReceiverMsg::YieldReceived { period, .. } => assert_eq!(
+                period,
+                YieldPeriod {
+                    start: Some(first_at),
+                    end: first_at + 60,
+                }
+            ),
+        },
+        msg => panic!("unexpected {:?}", msg),
+    }
+
+    // back to bank sends
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetYieldHook { enabled: false },
+    )
+    .unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_070_000);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert!(matches!(resp.messages[1].msg, CosmosMsg::Bank(_)));
+}
+
+fn ibc_transfers(
+    deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+) -> IbcTransfersResponse {
+    from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IbcTransfers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+/// MsgTransferResponse with the given sequence
+fn transfer_reply(sequence: u8) -> Reply {
+    Reply {
+        id: IBC_TRANSFER_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(Binary::from(vec![0x08, sequence])),
+        }),
+    }
+}
+
+#[test]
+fn test_ibc_payout() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    let ibc_payout = IbcPayout {
+        channel_id: "channel-0".to_string(),
+        remote_address: "remote-treasury".to_string(),
+        timeout_seconds: 600,
+        max_retries: 1,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetIbcPayout {
+            ibc_payout: Some(ibc_payout.clone()),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetIbcPayout {
+            ibc_payout: Some(IbcPayout {
+                timeout_seconds: 0,
+                ..ibc_payout.clone()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidIbcPayout {});
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetIbcPayout {
+            ibc_payout: Some(ibc_payout),
+        },
+    )
+    .unwrap();
+
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    let transfer = transfer_msg(
+        &env,
+        "channel-0",
+        "remote-treasury",
+        &Coin::new(94_999, "uusd"),
+        env.block.time.plus_seconds(600),
+    )
+    .unwrap();
+    // sent with the memo that gets the pool called back with the ack or timeout
+    match &transfer {
+        CosmosMsg::Stargate { type_url, value } => {
+            assert_eq!(type_url, MSG_TRANSFER_TYPE_URL);
+            let memo = format!(r#"{{"ibc_callback":"{}"}}"#, MOCK_CONTRACT_ADDR);
+            assert!(value
+                .as_slice()
+                .windows(memo.len())
+                .any(|window| window == memo.as_bytes()));
+        }
+        msg => panic!("unexpected {:?}", msg),
+    }
+    assert_eq!(
+        resp.messages[1],
+        SubMsg::reply_on_success(transfer.clone(), IBC_TRANSFER_REPLY_ID)
+    );
+    // a transfer that can't be tracked reverts the payout rather than sit unsettled
+    let err = contract::reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: IBC_TRANSFER_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IbcTransferSequenceMissing {});
+    contract::reply(deps.as_mut(), env.clone(), transfer_reply(7)).unwrap();
+    let transfers = ibc_transfers(&deps).transfers;
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].earn_id, 1);
+    assert_eq!(transfers[0].sequence, Some(7));
+    assert_eq!(transfers[0].attempts, 1);
+    assert_eq!(transfers[0].status, IbcTransferStatus::Pending);
+
+    // somebody else's packet
+    let resp = contract::sudo(
+        deps.as_mut(),
+        env.clone(),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-1".to_string(),
+            sequence: 7,
+        }),
+    )
+    .unwrap();
+    assert!(resp.messages.is_empty());
+
+    // timed out, so it goes again
+    let resp = contract::sudo(
+        deps.as_mut(),
+        env.clone(),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-0".to_string(),
+            sequence: 7,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages,
+        vec![SubMsg::reply_on_success(transfer, IBC_TRANSFER_REPLY_ID)]
+    );
+    contract::reply(deps.as_mut(), env.clone(), transfer_reply(8)).unwrap();
+    assert_eq!(ibc_transfers(&deps).transfers[0].attempts, 2);
+
+    // out of retries, paid here instead
+    let resp = contract::sudo(
+        deps.as_mut(),
+        env.clone(),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel: "channel-0".to_string(),
+            sequence: 8,
+            ack: "".to_string(),
+            success: false,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+    assert_eq!(
+        ibc_transfers(&deps).transfers[0].status,
+        IbcTransferStatus::PaidLocally
+    );
+
+    // and a delivered one
+    register_earnings(&mut deps, 1_000_000, 1_085_000);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    contract::reply(deps.as_mut(), env.clone(), transfer_reply(9)).unwrap();
+    contract::sudo(
+        deps.as_mut(),
+        env,
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel: "channel-0".to_string(),
+            sequence: 9,
+            ack: "AQ==".to_string(),
+            success: true,
+        }),
+    )
+    .unwrap();
+    let transfers = ibc_transfers(&deps).transfers;
+    assert_eq!(transfers[1].earn_id, 2);
+    assert_eq!(transfers[1].status, IbcTransferStatus::Delivered);
+}
+
+#[test]
+fn test_ibc_timeout_refund() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetIbcPayout {
+            ibc_payout: Some(IbcPayout {
+                channel_id: "channel-0".to_string(),
+                remote_address: "remote-treasury".to_string(),
+                timeout_seconds: 600,
+                max_retries: 0,
+            }),
+        },
+    )
+    .unwrap();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    contract::reply(deps.as_mut(), env.clone(), transfer_reply(7)).unwrap();
+
+    // the packet timed out and the transfer module refunded the pool
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(94_999, "uusd")]);
+    let sweep_stable = ExecuteMsg::Sweep {
+        denom_or_cw20: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        recipient: "owner".to_string(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        sweep_stable.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToSweep);
+
+    // out of retries, so the refund goes to the beneficiary here
+    let resp = contract::sudo(
+        deps.as_mut(),
+        env.clone(),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-0".to_string(),
+            sequence: 7,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(94_999, "uusd")],
+        })
+    );
+    assert_eq!(
+        ibc_transfers(&deps).transfers[0].status,
+        IbcTransferStatus::PaidLocally
+    );
+
+    // settled, so only stray stable is left to sweep
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(10, "uusd")]);
+    let resp =
+        contract::execute(deps.as_mut(), env, mock_info("owner", &[]), sweep_stable).unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![Coin::new(10, "uusd")],
+        })
+    );
+}
+
+#[test]
+fn test_swap_payout() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+
+    let swap_payout = SwapPayout {
+        pair: "ust-luna-pair".to_string(),
+        ask_asset: AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+        belief_price: Decimal::from_ratio(70u128, 1u128),
+        max_spread: Decimal::percent(1),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetSwapPayout {
+            swap_payout: Some(SwapPayout {
+                max_spread: Decimal::percent(20),
+                ..swap_payout.clone()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidSwapPayout {
+            max: Decimal::percent(10)
+        }
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetSwapPayout {
+            swap_payout: Some(SwapPayout {
+                belief_price: Decimal::zero(),
+                ..swap_payout.clone()
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SwapPayoutNeedsBeliefPrice {});
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetSwapPayout {
+            swap_payout: Some(swap_payout),
+        },
+    )
+    .unwrap();
+
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1],
+        SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "ust-luna-pair".to_string(),
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::from(94_999u128),
+                    },
+                    // checked against the beneficiary's price, not the pair's spot price
+                    belief_price: Some(Decimal::from_ratio(70u128, 1u128)),
+                    max_spread: Some(Decimal::percent(1)),
+                    to: Some("test-beneficiary".to_string()),
+                })
+                .unwrap(),
+                funds: vec![Coin::new(94_999, "uusd")],
+            },
+            SWAP_REPLY_ID
+        )
+    );
+    let resp = contract::reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: SWAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm").add_attribute("return_amount", "1234")],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert!(resp.messages.is_empty());
+
+    // the swap fails, so the beneficiary gets the stable instead
+    register_earnings(&mut deps, 1_000_000, 1_085_000);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    let resp = contract::reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: SWAP_REPLY_ID,
+            result: SubMsgResult::Err("Operation exceeds max spread limit".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(9_500, "uusd")],
+        })
+    );
+
+    let history: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::EarnHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let swapped = history.earns[0].swap.as_ref().unwrap();
+    assert_eq!(swapped.offer_amount, Uint128::from(94_999u128));
+    assert_eq!(swapped.return_amount, Some(Uint128::from(1_234u128)));
+    assert_eq!(swapped.error, None);
+    let fell_back = history.earns[1].swap.as_ref().unwrap();
+    assert_eq!(fell_back.return_amount, None);
+    assert_eq!(
+        fell_back.error.as_deref(),
+        Some("Operation exceeds max spread limit")
+    );
+}
+
+#[test]
+fn test_cw20_deposit() {
+    let mut deps = mock_pool(InstantiateMsg {
+        stable_token: Some("usdc-token".to_string()),
+        ..mock_instantiate_msg()
+    });
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    // native coins aren't this pool's stable
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(1_000, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NotAllowOtherDenoms {
+            denom: "usdc-token".to_string()
+        }
+    );
+    let deposit = |recipient: Option<&str>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&Cw20HookMsg::Deposit {
+                recipient: recipient.map(String::from),
+            })
+            .unwrap(),
+        })
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("other-token", &[]),
+        deposit(None),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("usdc-token", &[]),
+        deposit(None),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "usdc-token".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: MONEY_MARKET.to_string(),
+                amount: Uint128::from(1_000u128),
+                msg: to_binary(&AnchorCw20HookMsg::DepositStable {}).unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    let mint = |recipient: &str| {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: DP_TOKEN.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: recipient.to_string(),
+                amount: Uint128::from(1_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    };
+    assert_eq!(resp.messages[1].msg, mint("alice"));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("usdc-token", &[]),
+        deposit(Some("bob")),
+    )
+    .unwrap();
+    assert_eq!(resp.messages[1].msg, mint("bob"));
+
+    // and yield is paid out in the token too
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "usdc-token".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "test-beneficiary".to_string(),
+                amount: Uint128::from(94_999u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetIbcPayout {
+            ibc_payout: Some(IbcPayout {
+                channel_id: "channel-0".to_string(),
+                remote_address: "remote-treasury".to_string(),
+                timeout_seconds: 600,
+                max_retries: 0,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::IbcPayoutNeedsNativeStable {});
+}
+
+#[test]
+fn test_deposit_route() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    let route = DepositRoute {
+        pair: "luna-ust-pair".to_string(),
+        max_spread: Decimal::percent(1),
+    };
+    let set_route = |denom: &str, route: Option<DepositRoute>| ExecuteMsg::SetDepositRoute {
+        denom: denom.to_string(),
+        route,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("depositor", &[]),
+        set_route("uluna", Some(route.clone())),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_route(
+            "uluna",
+            Some(DepositRoute {
+                max_spread: Decimal::percent(20),
+                ..route.clone()
+            }),
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidDepositRoute {
+            max: Decimal::percent(10)
+        }
+    );
+    // only through pairs the owner has whitelisted
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_route("uluna", Some(route.clone())),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PairNotWhitelisted("luna-ust-pair".to_string())
+    );
+    let whitelist = |whitelisted: bool| ExecuteMsg::WhitelistPair {
+        pair: "luna-ust-pair".to_string(),
+        whitelisted,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        whitelist(true),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        whitelist(true),
+    )
+    .unwrap();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_route("uluna", Some(route.clone())),
+    )
+    .unwrap();
+    let routes: DepositRoutesResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::DepositRoutes {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(routes.routes.len(), 1);
+    assert_eq!(routes.routes[0].denom, "uluna");
+
+    // a swapped deposit has to say what it expects back
+    let deposit = |min_received: u128| ExecuteMsg::Deposit {
+        min_received: Some(Uint128::from(min_received)),
+    };
+    for msg in [ExecuteMsg::Deposit { min_received: None }, deposit(0)] {
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("depositor", &[Coin::new(10, "uluna")]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::MinReceivedRequired {});
+    }
+
+    // the luna is swapped first, nothing is minted yet
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("depositor", &[Coin::new(10, "uluna")]),
+        deposit(900),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages,
+        vec![SubMsg::reply_on_success(
+            WasmMsg::Execute {
+                contract_addr: "luna-ust-pair".to_string(),
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uluna".to_string(),
+                        },
+                        amount: Uint128::from(10u128),
+                    },
+                    belief_price: None,
+                    max_spread: Some(Decimal::percent(1)),
+                    to: None,
+                })
+                .unwrap(),
+                funds: vec![Coin::new(10, "uluna")],
+            },
+            CONVERT_REPLY_ID
+        )]
+    );
+    // DP is minted on what the swap returned
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(950, "uusd")]);
+    let resp = contract::reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: CONVERT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: DP_TOKEN.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "depositor".to_string(),
+                amount: Uint128::from(950u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+
+    // and fails the deposit when it comes to less than asked for
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("depositor", &[Coin::new(10, "uluna")]),
+        deposit(900),
+    )
+    .unwrap();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![Coin::new(1_800, "uusd")]);
+    let err = contract::reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: CONVERT_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DepositBelowMinimum {
+            received: Uint128::from(850u128),
+            minimum: Uint128::from(900u128),
+        }
+    );
+
+    // taking the pair off the whitelist drops its routes, and other denoms are refused again
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        whitelist(false),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("depositor", &[Coin::new(10, "uluna")]),
+        deposit(900),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAllowZeroAmount {});
+}
+
+#[test]
+fn test_donate() {
+    let mut deps = mock_dependencies(&[]);
+    register_money_market(&mut deps);
+    deps.querier.register_wasm_smart_query_handler(
+        "nft-minter".to_string(),
+        Box::new(|_| to_binary(&TokensResponse { tokens: vec![] })),
+    );
+    let env = mock_env();
+    contract::instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            nft_contract: Some("nft-minter".to_string()),
+            ..mock_instantiate_msg()
+        },
+    )
+    .unwrap();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    // straight to the beneficiary, less the fee
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(1_000, "uusd")]),
+        ExecuteMsg::Donate { direct: true },
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "test-fee-collector".to_string(),
+                amount: vec![Coin::new(50, "uusd")],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "test-beneficiary".to_string(),
+                amount: vec![Coin::new(950, "uusd")],
+            }),
+        ]
+    );
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[]),
+        ExecuteMsg::SetDonorCollection {
+            collection_id: Some(7),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized { .. }));
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetDonorCollection {
+            collection_id: Some(7),
+        },
+    )
+    .unwrap();
+
+    // into the market to be paid out with the yield, and donors get an NFT
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(500, "uusd")]),
+        ExecuteMsg::Donate { direct: false },
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MONEY_MARKET.to_string(),
+                msg: to_binary(&AnchorHandleMsg::DepositStable {}).unwrap(),
+                funds: vec![Coin::new(500, "uusd")],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "nft-minter".to_string(),
+                msg: to_binary(&NftExecMsg::QuickMint {
+                    token_prefix: format!("test-pool/{}-d", env.block.height),
+                    owner: "alice".to_string(),
+                    collection_id: 7,
+                    num_to_mint: 1,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(500, "uluna")]),
+        ExecuteMsg::Donate { direct: false },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotAllowZeroAmount {});
+
+    let donor: DonorResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Donor {
+                address: "alice".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(donor.total, Uint128::from(1_500u128));
+    let donors: DonorsResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::Donors {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(donors.total, Uint128::from(1_500u128));
+    assert_eq!(donors.donors, vec![donor]);
+    assert_eq!(donors.donor_collection, Some(7));
+}
+
+#[test]
+fn test_matching() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    let expires_at = env.block.time.seconds() + 1_000;
+    let pledge = |ratio: Decimal| ExecuteMsg::PledgeMatch {
+        ratio,
+        cap: Uint128::from(40_000u128),
+        expires_at,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("acme", &[Coin::new(100_000, "uusd")]),
+        pledge(Decimal::zero()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidMatch {});
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("acme", &[Coin::new(100_000, "uusd")]),
+        pledge(Decimal::percent(50)),
+    )
+    .unwrap();
+
+    // half the yield, capped at 40_000, goes to the beneficiary with it
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(134_999, "uusd")],
+        })
+    );
+    let history: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EarnHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        history.earns[0].beneficiary_amount,
+        Uint128::from(94_999u128)
+    );
+    assert_eq!(history.earns[0].matched, Some(Uint128::from(40_000u128)));
+
+    let query_matches = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                         env: Env| {
+        from_binary::<MatchesResponse>(
+            &contract::query(
+                deps.as_ref(),
+                env,
+                QueryMsg::Matches {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let matches = query_matches(&deps, env.clone());
+    assert_eq!(matches.remaining, Uint128::from(60_000u128));
+    assert_eq!(matches.matches[0].released, Uint128::from(40_000u128));
+
+    // the rest goes back to the sponsor once it has expired
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("acme", &[]),
+        ExecuteMsg::ReclaimMatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MatchNotExpired { expires_at });
+    let mut later = env.clone();
+    later.block.time = later.block.time.plus_seconds(1_000);
+    let matches = query_matches(&deps, later.clone());
+    assert_eq!(matches.remaining, Uint128::zero());
+    assert!(matches.matches[0].expired);
+    let err = contract::execute(
+        deps.as_mut(),
+        later.clone(),
+        mock_info("someone", &[]),
+        ExecuteMsg::ReclaimMatch {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoMatch("someone".to_string()));
+    let resp = contract::execute(
+        deps.as_mut(),
+        later.clone(),
+        mock_info("acme", &[]),
+        ExecuteMsg::ReclaimMatch {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "acme".to_string(),
+            amount: vec![Coin::new(60_000, "uusd")],
+        })
+    );
+    assert!(query_matches(&deps, later).matches.is_empty());
+}
+
+#[test]
+fn test_endowment() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetRetainRatio {
+            retain_ratio: Decimal::percent(101),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidRetainRatio);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::SetRetainRatio {
+            retain_ratio: Decimal::percent(20),
+        },
+    )
+    .unwrap();
+
+    // 20% of the 95_000 owed stays in the market
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.claimable, Uint128::from(76_000u128));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(75_999, "uusd")],
+        })
+    );
+    let endowment: EndowmentResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Endowment {}).unwrap())
+            .unwrap();
+    assert_eq!(endowment.principal, Uint128::from(19_000u128));
+    assert_eq!(endowment.last_retained, Uint128::from(19_000u128));
+    assert_eq!(endowment.first_retained_at, Some(env.block.time.seconds()));
+
+    // what was redeemed has left the market. the endowment that is still there isn't earnable,
+    // only what the redemption rounded away
+    register_earnings(&mut deps, 930_910, 1_000_000);
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.claimable, Uint128::from(1u128));
+    let history: EarnHistoryResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            env,
+            QueryMsg::EarnHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history.earns[0].retained, Some(Uint128::from(19_000u128)));
+}
+
+#[test]
+fn test_liquid_buffer() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_100_000);
+
+    let set_target = |percent: u64| ExecuteMsg::SetLiquidBuffer {
+        target: Decimal::percent(percent),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_target(101),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidBufferTarget);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_target(10),
+    )
+    .unwrap();
+
+    // the deposit only goes as far as filling the buffer, so nothing goes to the market
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(100_000, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: DP_TOKEN.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "alice".to_string(),
+                amount: Uint128::from(100_000u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    // and the buffer is counted in the pool's value, not as yield
+    register_earnings(&mut deps, 1_000_000, 1_200_000);
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.pool_value, Uint128::from(1_200_000u128));
+    assert_eq!(claimable.claimable, Uint128::zero());
+
+    let redeem = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                  amount: u128| {
+        contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(DP_TOKEN, &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "alice".to_string(),
+                amount: Uint128::from(amount),
+                msg: to_binary(&Cw20HookMsg::Redeem {}).unwrap(),
+            }),
+        )
+        .unwrap()
+    };
+    // small redemptions come straight out of the buffer
+    let resp = redeem(&mut deps, 30_000);
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(30_000, "uusd")],
+        })
+    );
+    // bigger ones go to the market, topping the buffer up on the way
+    let resp = redeem(&mut deps, 200_000);
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: ATOKEN_CONTRACT.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: MONEY_MARKET.to_string(),
+                amount: Uint128::from(209_090u128),
+                msg: to_binary(&AnchorCw20HookMsg::RedeemStable {}).unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(200_000, "uusd")],
+        })
+    );
+    let query_buffer = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>| {
+        from_binary::<LiquidBufferResponse>(
+            &contract::query(deps.as_ref(), mock_env(), QueryMsg::LiquidBuffer {}).unwrap(),
+        )
+        .unwrap()
+    };
+    register_earnings(&mut deps, 790_910, 1_000_000);
+    let buffer = query_buffer(&deps);
+    assert_eq!(buffer.held, Uint128::from(99_999u128));
+    assert_eq!(buffer.target_amount, Uint128::from(100_000u128));
+
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToRebalance);
+    // a lower target puts the excess back in the market
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_target(5),
+    )
+    .unwrap();
+    let resp = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("keeper", &[]),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MONEY_MARKET.to_string(),
+            msg: to_binary(&AnchorHandleMsg::DepositStable {}).unwrap(),
+            funds: vec![Coin::new(49_999, "uusd")],
+        })
+    );
+    assert_eq!(query_buffer(&deps).held, Uint128::from(50_000u128));
+}
+
+fn register_vault(
+    deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+    atokens: u128,
+) {
+    register_market(deps, "vault-market", "vault-atoken", Decimal::one());
+    register_balance(deps, "vault-atoken", atokens);
+}
+
+#[test]
+fn test_strategies() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_100_000);
+    register_vault(&mut deps, 0);
+
+    let set_strategy = |money_market: &str, weight: &str| ExecuteMsg::SetStrategy {
+        money_market: money_market.to_string(),
+        weight: Decimal::from_str(weight).unwrap(),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_strategy(MONEY_MARKET, "0.4"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidStrategy(MONEY_MARKET.to_string())
+    );
+    // only markets the owner has whitelisted
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_strategy("vault-market", "0.4"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketNotWhitelisted("vault-market".to_string())
+    );
+    let whitelist = |whitelisted: bool| ExecuteMsg::WhitelistMarket {
+        money_market: "vault-market".to_string(),
+        whitelisted,
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        whitelist(true),
+    )
+    .unwrap_err();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        whitelist(true),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_strategy("vault-market", "1.1"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidStrategyWeights);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_strategy("vault-market", "0.4"),
+    )
+    .unwrap();
+
+    // deposits are split by weight, the rest going to the pool's own money market
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("alice", &[Coin::new(100_000, "uusd")]),
+        ExecuteMsg::Deposit { min_received: None },
+    )
+    .unwrap();
+    let deposit = |money_market: &str, amount: u128| {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: money_market.to_string(),
+            msg: to_binary(&AnchorHandleMsg::DepositStable {}).unwrap(),
+            funds: vec![Coin::new(amount, "uusd")],
+        })
+    };
+    assert_eq!(resp.messages[0].msg, deposit("vault-market", 40_000));
+    assert_eq!(resp.messages[1].msg, deposit(MONEY_MARKET, 60_000));
+
+    // the pool is valued across all of them
+    register_vault(&mut deps, 40_000);
+    let query_strategies = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>| {
+        from_binary::<StrategiesResponse>(
+            &contract::query(deps.as_ref(), mock_env(), QueryMsg::Strategies {}).unwrap(),
+        )
+        .unwrap()
+    };
+    let strategies = query_strategies(&deps);
+    assert_eq!(strategies.strategies[0].weight, Decimal::percent(60));
+    assert_eq!(strategies.strategies[1].value, Uint128::from(40_000u128));
+    assert_eq!(strategies.total_value, Uint128::from(1_140_000u128));
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.pool_value, Uint128::from(1_140_000u128));
+
+    // rebalancing moves what is over its weight to what is under it
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::Rebalance {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: ATOKEN_CONTRACT.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: MONEY_MARKET.to_string(),
+                amount: Uint128::from(378_181u128),
+                msg: to_binary(&AnchorCw20HookMsg::RedeemStable {}).unwrap(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(resp.messages[1].msg, deposit("vault-market", 415_999));
+
+    // taking the market off the whitelist leaves its strategy at no weight until it has been drained
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        whitelist(false),
+    )
+    .unwrap();
+    let strategies = query_strategies(&deps);
+    assert_eq!(strategies.strategies.len(), 2);
+    assert_eq!(strategies.strategies[0].weight, Decimal::one());
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_strategy("vault-market", "0.4"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MarketNotWhitelisted("vault-market".to_string())
+    );
+    register_vault(&mut deps, 0);
+    contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("test-beneficiary", &[]),
+        set_strategy("vault-market", "0"),
+    )
+    .unwrap();
+    assert_eq!(query_strategies(&deps).strategies.len(), 1);
+}
+
+#[test]
+fn test_insurance_reserve() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+
+    let set_reserve = |percent: u64, cap: u128| ExecuteMsg::SetInsuranceReserve {
+        ratio: Decimal::percent(percent),
+        cap: Uint128::from(cap),
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_reserve(10, 5_000),
+    )
+    .unwrap_err();
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        set_reserve(101, 5_000),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInsuranceRatio);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        set_reserve(10, 5_000),
+    )
+    .unwrap();
+
+    // 10% of the 95_000 owed would be 9_500, but the cap stops it at 5_000
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    let claimable: ClaimableRewardResponse =
+        from_binary(&contract::query(deps.as_ref(), env.clone(), QueryMsg::Claimable {}).unwrap())
+            .unwrap();
+    assert_eq!(claimable.claimable, Uint128::from(90_000u128));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        ExecuteMsg::Earn {},
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "test-beneficiary".to_string(),
+            amount: vec![Coin::new(89_999, "uusd")],
+        })
+    );
+    let query_reserve = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>| {
+        from_binary::<InsuranceReserveResponse>(
+            &contract::query(deps.as_ref(), mock_env(), QueryMsg::InsuranceReserve {}).unwrap(),
+        )
+        .unwrap()
+    };
+    let reserve = query_reserve(&deps);
+    assert_eq!(reserve.held, Uint128::from(5_000u128));
+    assert_eq!(reserve.total_funded, Uint128::from(5_000u128));
+
+    // a loss is made up out of the reserve before the redemption is paid
+    register_earnings(&mut deps, 900_000, 1_000_000);
+    assert_eq!(query_reserve(&deps).shortfall, Uint128::from(10_000u128));
+    let resp = contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info(DP_TOKEN, &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::from(1_000u128),
+            msg: to_binary(&Cw20HookMsg::Redeem {}).unwrap(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(1_000, "uusd")],
+        })
+    );
+    let reserve = query_reserve(&deps);
+    assert_eq!(reserve.held, Uint128::zero());
+    assert_eq!(reserve.total_used, Uint128::from(5_000u128));
+    assert_eq!(reserve.shortfall, Uint128::from(6_000u128));
+}
+
+#[test]
+fn test_sweep() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("acme", &[Coin::new(300, "uusd")]),
+        ExecuteMsg::PledgeMatch {
+            ratio: Decimal::one(),
+            cap: Uint128::zero(),
+            expires_at: env.block.time.seconds() + 1_000,
+        },
+    )
+    .unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin::new(1_000, "uusd"), Coin::new(500, "uluna")],
+    );
+
+    let sweep = |denom_or_cw20: AssetInfo| ExecuteMsg::Sweep {
+        denom_or_cw20,
+        recipient: "alice".to_string(),
+    };
+    let native = |denom: &str| {
+        sweep(AssetInfo::NativeToken {
+            denom: denom.to_string(),
+        })
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        native("uluna"),
+    )
+    .unwrap_err();
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        native("uluna"),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(500, "uluna")],
+        })
+    );
+    // the matching budget stays
+    let resp = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        native("uusd"),
+    )
+    .unwrap();
+    assert_eq!(
+        resp.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "alice".to_string(),
+            amount: vec![Coin::new(700, "uusd")],
+        })
+    );
+    let err = contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        native("ukrw"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NothingToSweep);
+    for token in [DP_TOKEN, ATOKEN_CONTRACT] {
+        let err = contract::execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            sweep(AssetInfo::Token {
+                contract_addr: token.to_string(),
+            }),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CannotSweep(token.to_string()));
+    }
+}
+
+#[test]
+fn test_deposit_caps() {
+    let mut deps = mock_pool(mock_instantiate_msg());
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_earnings(&mut deps, 1_000_000, 1_000_000);
+    register_dp_holdings(&mut deps);
+
+    let set_caps = |tvl_cap: u128, address_cap: u128| ExecuteMsg::SetDepositCaps {
+        tvl_cap: Uint128::from(tvl_cap),
+        address_cap: Uint128::from(address_cap),
+    };
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("test-beneficiary", &[]),
+        set_caps(1_050_000, 50_000),
+    )
+    .unwrap_err();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        set_caps(1_050_000, 50_000),
+    )
+    .unwrap();
+
+    let deposit = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                   amount: u128| {
+        contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[Coin::new(amount, "uusd")]),
+            ExecuteMsg::Deposit { min_received: None },
+        )
+    };
+    let err = deposit(&mut deps, 20_001).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AddressCapReached {
+            cap: Uint128::from(50_000u128),
+            remaining: Uint128::from(20_000u128),
+        }
+    );
+    deposit(&mut deps, 20_000).unwrap();
+
+    // staking DP for governance doesn't free up room under the cap
+    stake(&mut deps, &env, "alice", 5_000);
+    let err = deposit(&mut deps, 15_001).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::AddressCapReached {
+            cap: Uint128::from(50_000u128),
+            remaining: Uint128::from(15_000u128),
+        }
+    );
+
+    let capacity = |deps: &OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                    address: Option<&str>| {
+        from_binary::<DepositCapacityResponse>(
+            &contract::query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::DepositCapacity {
+                    address: address.map(str::to_string),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        capacity(&deps, Some("alice")).remaining,
+        Some(Uint128::from(15_000u128))
+    );
+
+    // the pool-wide cap binds first once it is the lower of the two
+    contract::execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        set_caps(1_010_000, 50_000),
+    )
+    .unwrap();
+    assert_eq!(
+        capacity(&deps, Some("alice")).remaining,
+        Some(Uint128::from(10_000u128))
+    );
+    let err = deposit(&mut deps, 15_000).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TvlCapReached {
+            cap: Uint128::from(1_010_000u128),
+            remaining: Uint128::from(10_000u128),
+        }
+    );
+    assert_eq!(
+        capacity(&deps, None).remaining,
+        Some(Uint128::from(10_000u128))
+    );
+}
+
+/// alice already holds 30_000 of the 1_000_000 DP out
+fn register_dp_holdings(deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>) {
+    deps.querier.register_wasm_smart_query_handler(
+        DP_TOKEN.to_string(),
+        Box::new(|x| match from_binary::<Cw20QueryMsg>(x).unwrap() {
+            Cw20QueryMsg::Balance { address } => to_binary(&cw20::BalanceResponse {
+                balance: Uint128::from(if address == "alice" { 30_000u128 } else { 0 }),
+            }),
+            _ => to_binary(&cw20::TokenInfoResponse {
+                name: "dp".to_string(),
+                symbol: "DP".to_string(),
+                decimals: 6,
+                total_supply: Uint128::from(1_000_000u128),
+            }),
+        }),
+    );
+}
+
+#[test]
+fn test_cw20_deposit_caps() {
+    let mut deps = mock_pool(InstantiateMsg {
+        stable_token: Some("usdc-token".to_string()),
+        ..mock_instantiate_msg()
+    });
+    let env = mock_env();
+    register_dp_token(deps.as_mut(), env.clone(), Addr::unchecked(DP_TOKEN)).unwrap();
+    register_dp_holdings(&mut deps);
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetDepositCaps {
+            tvl_cap: Uint128::zero(),
+            address_cap: Uint128::from(50_000u128),
+        },
+    )
+    .unwrap();
+
+    let deposit = |deps: &mut OwnedDeps<MockStorage, MockApi, CustomMockWasmQuerier>,
+                   sender: &str,
+                   recipient: Option<&str>,
+                   amount: u128| {
+        contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("usdc-token", &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::from(amount),
+                msg: to_binary(&Cw20HookMsg::Deposit {
+                    recipient: recipient.map(String::from),
+                })
+                .unwrap(),
+            }),
+        )
+    };
+    let alice_capped = ContractError::AddressCapReached {
+        cap: Uint128::from(50_000u128),
+        remaining: Uint128::from(20_000u128),
+    };
+    // the cap is checked against whoever gets the DP
+    assert_eq!(
+        deposit(&mut deps, "bob", Some("alice"), 20_001).unwrap_err(),
+        alice_capped
+    );
+    // and sending it to a fresh address doesn't get alice past her own
+    assert_eq!(
+        deposit(&mut deps, "alice", Some("fresh"), 20_001).unwrap_err(),
+        alice_capped
+    );
+    deposit(&mut deps, "alice", Some("fresh"), 20_000).unwrap();
+    deposit(&mut deps, "bob", None, 50_000).unwrap();
+}
@@ -1,14 +1,17 @@
-use cosmwasm_std::Uint128;
 //use cosmwasm_bignumber::Uint256;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::dex::AssetInfo;
+use crate::fee::{FeeModel, FeePeriod};
+use crate::roles::Role;
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub fee_collector: String,
-    pub fee_amount: String,
-    pub fee_max: Uint128,
-    pub fee_reset_every_num_blocks: u64,
+    /// fee terms new funds are created with
+    pub fee_model: FeeModel,
+    pub fee_period: FeePeriod,
     pub money_market: String,
     pub dp_code_id: u64,
     pub anchor_pool_code_id: u64,
@@ -25,9 +28,11 @@ pub struct MigrateMsg {}
 pub enum ExecuteMsg {
     Configure {
         fee_collector: Option<String>,
-        fee_amount: Option<String>,
-        fee_max: Option<Uint128>,
-        fee_reset_every_num_blocks: Option<u64>,
+        /// registered funds to move onto the fee collector (e.g. a fee splitter).
+        /// new funds always get the one configured here
+        fee_collector_funds: Option<Vec<String>>,
+        fee_model: Option<FeeModel>,
+        fee_period: Option<FeePeriod>,
         money_market: Option<String>,
         dp_code_id: Option<u64>,
         anchor_pool_code_id: Option<u64>,
@@ -41,24 +46,16 @@ pub enum ExecuteMsg {
         beneficiary: String,
     },
 
-    AddAnchorFund {
-        contract: String,
-    },
-    /// owner only.
+    /// owner/admin/operator only
+    AddAnchorFund { contract: String },
+    /// owner/operator.
     /// migrate fund to current code-id configured
-    MigrateAnchorFund {
-        contract: String,
-    },
+    MigrateAnchorFund { contract: String },
     /// remove anchor fund from listings. It doesn't close the fund. it just removes it from listings. visible = false to hide
-    /// beneficiary can 'hide' fund from listing.
-    HideAnchorFund {
-        contract: String,
-        visible: bool,
-    },
+    /// beneficiary/guardian can 'hide' fund from listing. only the owner can unhide it.
+    HideAnchorFund { contract: String, visible: bool },
     /// removes NFTs from the fund.
-    RemoveNftFromFund {
-        contract: String,
-    },
+    RemoveNftFromFund { contract: String },
     CreateCollectionsForFund {
         contract: String,
         active_meta: String,
@@ -66,6 +63,21 @@ pub enum ExecuteMsg {
     },
     /// switches NFT administrator to the admin of this contract
     RevertNftAdmin {},
+    /// owner grants admin. owner/admin grant guardian and operator
+    GrantRole { role: Role, address: String },
+    /// same permissions as GrantRole
+    RevokeRole { role: Role, address: String },
+    /// owner only. the new owner needs to call AcceptOwnership within `expires_in` seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// owner only. cancels a pending ownership transfer
+    DropOwnershipProposal {},
+    /// proposed owner only.
+    AcceptOwnership {},
+    /// owner only. sends everything the factory holds of a token sent to it by mistake to `recipient`
+    Sweep {
+        denom_or_cw20: AssetInfo,
+        recipient: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
@@ -98,4 +110,15 @@ pub enum QueryMsg {
     AnchorFundEx {
         contract: String,
     },
+    OwnershipProposal {},
+    /// -> RolesResponse
+    Roles {
+        address: String,
+    },
+    /// -> RoleMembersResponse
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
@@ -1,14 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::fee::{FeeModel, FeePeriod};
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
+    pub owner: String,
     pub fee_collector: String,
     pub money_market: String,
     pub dp_code_id: u64,
-    pub fee_amount: String,
-    pub fee_max: String,
-    pub fee_reset_every_num_blocks: u64,
+    pub fee_model: FeeModel,
+    pub fee_period: FeePeriod,
     pub anchor_pool_code_id: u64,
     pub nft_code_id: Option<u64>,
     pub nft_instantiate: Option<String>,
@@ -34,3 +36,9 @@ pub struct FundsResponse {
 pub struct FundsCountResponse {
     pub count: usize,
 }
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, JsonSchema, Debug)]
+pub struct OwnershipProposalResponse {
+    pub proposed_owner: String,
+    /// seconds since epoch
+    pub expires_at: u64,
+}
@@ -0,0 +1,195 @@
+//! how a fund's fee is worked out from what it has earned.
+//!
+//! shared by the factory (the terms new funds get) and the pool (the terms it charges on).
+//! a zero `cap` means uncapped. caps apply per `FeePeriod`, measured against block time.
+use cosmwasm_std::{Decimal, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeModel {
+    /// `rate` of each claim, at most `cap` per fee period
+    Percent { rate: Decimal, cap: Uint128 },
+    /// `amount` on every claim
+    Flat { amount: Uint128 },
+    /// the rate of the highest tier the claim reaches, at most `cap` per fee period
+    Tiered { tiers: Vec<FeeTier>, cap: Uint128 },
+    /// nothing on claims under `threshold`, otherwise `rate` with at most `cap` per fee period
+    FreeBelow {
+        threshold: Uint128,
+        rate: Decimal,
+        cap: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeTier {
+    /// smallest claim this rate applies to
+    pub threshold: Uint128,
+    pub rate: Decimal,
+}
+
+impl FeeModel {
+    /// the old `fee_amount`/`fee_max` pair, where a zero `fee_amount` meant `fee_max` flat.
+    /// `fee_max` capped each claim, so this goes with `FeePeriod::PerClaim`
+    pub fn from_legacy(fee_amount: Decimal, fee_max: Uint128) -> FeeModel {
+        if fee_amount.is_zero() {
+            FeeModel::Flat { amount: fee_max }
+        } else {
+            FeeModel::Percent {
+                rate: fee_amount,
+                cap: fee_max,
+            }
+        }
+    }
+
+    /// rates can't exceed 1 and tiers have to be listed by increasing threshold
+    pub fn is_valid(&self) -> bool {
+        match self {
+            FeeModel::Percent { rate, .. } | FeeModel::FreeBelow { rate, .. } => {
+                *rate <= Decimal::one()
+            }
+            FeeModel::Flat { .. } => true,
+            FeeModel::Tiered { tiers, .. } => {
+                !tiers.is_empty()
+                    && tiers.iter().all(|tier| tier.rate <= Decimal::one())
+                    && tiers
+                        .windows(2)
+                        .all(|pair| pair[0].threshold < pair[1].threshold)
+            }
+        }
+    }
+
+    /// fee on a single claim of `earnable`, before any period cap. never more than `earnable`
+    pub fn fee(&self, earnable: Uint128) -> Uint128 {
+        let fee = match self {
+            FeeModel::Percent { rate, .. } => earnable * *rate,
+            FeeModel::Flat { amount } => *amount,
+            FeeModel::Tiered { tiers, .. } => tiers
+                .iter()
+                .rev()
+                .find(|tier| earnable >= tier.threshold)
+                .map(|tier| earnable * tier.rate)
+                .unwrap_or_default(),
+            FeeModel::FreeBelow {
+                threshold, rate, ..
+            } => {
+                if earnable < *threshold {
+                    Uint128::zero()
+                } else {
+                    earnable * *rate
+                }
+            }
+        };
+        fee.min(earnable)
+    }
+
+    /// most that can be charged in one fee period
+    pub fn period_cap(&self) -> Option<Uint128> {
+        match self {
+            FeeModel::Percent { cap, .. }
+            | FeeModel::Tiered { cap, .. }
+            | FeeModel::FreeBelow { cap, .. } => {
+                if cap.is_zero() {
+                    None
+                } else {
+                    Some(*cap)
+                }
+            }
+            FeeModel::Flat { .. } => None,
+        }
+    }
+}
+
+/// terra's average block time, used to carry block based periods over to seconds
+pub const LEGACY_BLOCK_SECONDS: u64 = 6;
+
+const DAY_SECONDS: u64 = 86_400;
+
+/// how long a fee cap lasts before it starts over. calendar periods are in UTC
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeePeriod {
+    /// every claim starts a new period
+    PerClaim {},
+    /// back to back periods of `seconds`, counted from when the first one started
+    Seconds {
+        seconds: u64,
+    },
+    Monthly {},
+    Quarterly {},
+}
+
+impl FeePeriod {
+    /// the old `fee_reset_every_num_blocks`, where 0 never carried a cap over between claims
+    pub fn from_blocks(blocks: u64) -> FeePeriod {
+        if blocks == 0 {
+            FeePeriod::PerClaim {}
+        } else {
+            FeePeriod::Seconds {
+                seconds: blocks.saturating_mul(LEGACY_BLOCK_SECONDS),
+            }
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, FeePeriod::Seconds { seconds: 0 })
+    }
+
+    /// start of the period `now` falls in, when the last recorded one started at `started`.
+    /// all in seconds since epoch
+    pub fn start_of(&self, started: u64, now: u64) -> u64 {
+        match self {
+            FeePeriod::PerClaim {} => now,
+            FeePeriod::Seconds { seconds } => {
+                if *seconds == 0 || now < started {
+                    started
+                } else {
+                    started + (now - started) / seconds * seconds
+                }
+            }
+            FeePeriod::Monthly {} => month_start(now, 1),
+            FeePeriod::Quarterly {} => month_start(now, 3),
+        }
+    }
+
+    /// whether a claim at `now` is in a different period to the one that started at `started`
+    pub fn is_new_period(&self, started: u64, now: u64) -> bool {
+        match self {
+            FeePeriod::PerClaim {} => true,
+            _ => self.start_of(started, now) != started,
+        }
+    }
+}
+
+/// first second of the run of `months` months (counted from january) that `now` falls in
+fn month_start(now: u64, months: u32) -> u64 {
+    let (year, month) = civil_from_days((now / DAY_SECONDS) as i64);
+    let month = month - (month - 1) % months;
+    days_from_civil(year, month) as u64 * DAY_SECONDS
+}
+
+/// (year, month) of a day count since 1970-01-01. Howard Hinnant's `civil_from_days`
+fn civil_from_days(days: i64) -> (i64, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month as u32)
+}
+
+/// days from 1970-01-01 to the first of `month` in `year`
+fn days_from_civil(year: i64, month: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,66 @@
+use cosmwasm_std::Decimal;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::roles::Role;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareKind {
+    Treasury,
+    Developer,
+    Referrer,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeShare {
+    pub recipient: String,
+    pub kind: ShareKind,
+    pub share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// have to add up to 1. the first share also gets any rounding dust
+    pub shares: Vec<FeeShare>,
+    /// cw20 tokens to split along with the native balance, e.g. the stable of cw20 pools
+    pub tokens: Option<Vec<String>>,
+}
+
+/// We currently take no arguments for migrations
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// anyone can exec this. splits whatever fees arrived since the last distribution
+    Distribute {},
+    /// sends the sender everything allocated to them, distributing first
+    Claim {},
+    /// owner/admin only. fees already received are split under the old shares first
+    UpdateShares { shares: Vec<FeeShare> },
+    /// owner/admin only. replaces the cw20 tokens that are split. what has already been received
+    /// of a dropped token is split first
+    UpdateTokens { tokens: Vec<String> },
+    /// owner grants admin. owner/admin grant guardian and operator
+    GrantRole { role: Role, address: String },
+    /// same permissions as GrantRole
+    RevokeRole { role: Role, address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// -> ConfigResponse
+    Config {},
+    /// -> ClaimableResponse. only counts fees that have been distributed
+    Claimable { address: String },
+    /// -> PayoutsResponse, oldest first
+    Payouts {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// -> RolesResponse
+    Roles { address: String },
+}
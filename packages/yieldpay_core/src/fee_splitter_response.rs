@@ -0,0 +1,30 @@
+use cosmwasm_std::Coin;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::fee_splitter_msg::FeeShare;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub shares: Vec<FeeShare>,
+    pub tokens: Vec<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ClaimableResponse {
+    pub address: String,
+    pub amounts: Vec<Coin>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutResponse {
+    pub id: u64,
+    pub recipient: String,
+    pub amounts: Vec<Coin>,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutsResponse {
+    pub payouts: Vec<PayoutResponse>,
+}
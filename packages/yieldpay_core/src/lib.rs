@@ -1,10 +1,16 @@
+pub mod dex;
 pub mod factory_msg;
 pub mod factory_response;
+pub mod fee;
+pub mod fee_splitter_msg;
+pub mod fee_splitter_response;
 pub mod pool_anchor_msg;
 pub mod pool_anchor_response;
 pub mod pool_msg;
 pub mod pool_resp;
+pub mod roles;
 // following are cloned from pylon utils, due to version upgrade to 0.9.1 (and they are using 0.8)
 //pub mod nftcallback;
 // pub mod tax;
 pub mod token;
+pub mod yield_receiver;
@@ -1,7 +1,8 @@
-use cosmwasm_std::Uint128;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::fee::{FeeModel, FeePeriod};
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub pool_name: String,
@@ -9,15 +10,20 @@ pub struct InstantiateMsg {
     pub pool_description: String,
     pub beneficiary: String,
     pub fee_collector: String,
-    pub fee_amount: String,
-    pub fee_max: Uint128,
-    pub fee_reset_every_num_blocks: u64,
+    pub fee_model: FeeModel,
+    /// how long the fee cap lasts
+    pub fee_period: FeePeriod,
     pub money_market: String,
     pub dp_code_id: u64,
     pub owner_can_change_config: bool,
     pub nft_contract: Option<String>,
     pub nft_collection_active: Option<u64>,
     pub nft_collection_redeemed: Option<u64>,
+    /// seconds between a new beneficiary accepting and them receiving yield. defaults to 7 days,
+    /// and can't be less than 1 day
+    pub beneficiary_change_delay: Option<u64>,
+    /// for markets whose stable is a cw20. deposits then come through its Send
+    pub stable_token: Option<String>,
 }
 
 /// We currently take no arguments for migrations
@@ -1,18 +1,195 @@
-use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{to_json_binary, Binary, CosmosMsg, Decimal, StdResult, Uint128, WasmMsg};
 use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::dex::AssetInfo;
+use crate::roles::Role;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
-    Deposit {}, // UST -> DP (user)
-    Earn {},    // x -> UST (beneficiary)
+    /// UST -> DP (user). a whitelisted denom is swapped to UST first, and fails
+    /// if that comes to less than `min_received`, which swapped deposits have to set
+    Deposit {
+        min_received: Option<Uint128>,
+    },
+    /// a one-off gift in UST, less the fee. `direct` sends it to the beneficiary now,
+    /// otherwise it goes in the market to be paid out with the yield. no DP is minted
+    Donate {
+        direct: bool,
+    },
+    /// a sponsor's matching budget, in UST. each earn releases `ratio` of the yield to the
+    /// beneficiary out of it, at most `cap` a time (zero for no cap), until `expires_at`.
+    /// pledging again tops the budget up and replaces the terms
+    PledgeMatch {
+        ratio: Decimal,
+        cap: Uint128,
+        expires_at: u64,
+    },
+    /// sponsor only, once their match has expired. returns what is left of the budget
+    ReclaimMatch {},
+    Earn {}, // x -> UST (beneficiary, fee collector or an operator)
+    /// fee collector only. sends the fees that have accrued, without paying the beneficiary
+    CollectFees {},
+    /// anyone can exec this once the fund is due for a harvest. pays the caller the bounty
+    /// and the rest to the beneficiary, the same as Earn
+    Harvest {},
+    /// beneficiary only (and owner/admin if the fund lets them change config).
+    /// None turns permissionless harvesting off
+    SetHarvestConfig {
+        harvest: Option<HarvestConfig>,
+    },
+    /// beneficiary/fee collector only (and owner/admin if the fund lets them change config).
+    /// keeps Earn, Harvest and CollectFees from sending dust
+    SetEarnLimits {
+        min_earn_amount: Option<Uint128>,
+        min_blocks_between_earn: Option<u64>,
+    },
+    /// beneficiary only (and owner/admin if the fund lets them change config).
+    /// the share of each earn kept in the market as the pool's endowment, at most 1
+    SetRetainRatio {
+        retain_ratio: Decimal,
+    },
+    /// owner/admin only. the most DP the pool can have out, and the most one address can hold.
+    /// zero for no cap
+    SetDepositCaps {
+        tvl_cap: Uint128,
+        address_cap: Uint128,
+    },
+    /// guardian/owner/admin can pause deposits, only owner/admin can unpause them
+    PauseDeposits {
+        paused: bool,
+    },
+    /// owner only. the share of each earn, at most 1, put in the pool's insurance reserve before
+    /// the beneficiary is paid. no slice is taken once the reserve holds `cap` (zero for no cap)
+    SetInsuranceReserve {
+        ratio: Decimal,
+        cap: Uint128,
+    },
+    /// owner only. sends everything the pool holds of a token sent to it by mistake to `recipient`.
+    /// the stable only above what the pool holds for the buffer, reserve and matches. never the DP
+    /// token or an aToken
+    Sweep {
+        denom_or_cw20: AssetInfo,
+        recipient: String,
+    },
+    /// beneficiary/fee collector only (and owner/admin if the fund lets them change config).
+    /// the share of deposits kept in the pool to serve redemptions without the market, at most 1
+    SetLiquidBuffer {
+        target: Decimal,
+    },
+    /// anyone can exec this. moves the liquid buffer and the strategies back to their targets
+    Rebalance {},
+    /// owner/admin only. the anchor-compatible money markets strategies can use. taking one off
+    /// drops its strategy's weight to zero
+    WhitelistMarket {
+        money_market: String,
+        whitelisted: bool,
+    },
+    /// beneficiary/owner/admin. spreads deposits to a whitelisted money market by weight. zero
+    /// removes it, or leaves it to be drained by Rebalance if it still holds aTokens
+    SetStrategy {
+        money_market: String,
+        weight: Decimal,
+    },
+    /// beneficiary only. pays the beneficiary's share with a YieldReceived hook
+    /// (see yield_receiver) instead of a bank send. it has to be a contract that handles it
+    SetYieldHook {
+        enabled: bool,
+    },
+    /// beneficiary only. sends the beneficiary's share to another chain, ahead of the hook.
+    /// None to go back to paying it here
+    SetIbcPayout {
+        ibc_payout: Option<IbcPayout>,
+    },
+    /// beneficiary only. swaps the beneficiary's share into another asset before paying it,
+    /// ahead of the hook. None to be paid in the stable denom
+    SetSwapPayout {
+        swap_payout: Option<SwapPayout>,
+    },
+    /// owner/admin only. the pairs deposit routes can swap through. taking one off drops the
+    /// routes using it
+    WhitelistPair {
+        pair: String,
+        whitelisted: bool,
+    },
+    /// beneficiary only (and owner/admin if the fund lets them change config).
+    /// accepts deposits in `denom`, swapped to the stable through `route`'s whitelisted pair.
+    /// None to stop
+    SetDepositRoute {
+        denom: String,
+        route: Option<DepositRoute>,
+    },
+    /// beneficiary only (and owner/admin if the fund lets them change config).
+    /// donors get an NFT from this collection of the fund's NFT contract. None to stop
+    SetDonorCollection {
+        collection_id: Option<u64>,
+    },
+    /// a new beneficiary is only proposed here. it takes effect once they call AcceptBeneficiary.
+    /// depositors can also change the beneficiary through a governance proposal
     Configure {
         beneficiary: Option<String>,
         fee_collector: Option<String>,
     },
+    /// beneficiary/owner can exec this. cancels a pending or queued beneficiary handover
+    DropBeneficiaryProposal {},
+    /// proposed beneficiary only. queues the handover started via Configure.
+    /// the change takes effect once the beneficiary change delay has passed
+    AcceptBeneficiary {},
+    /// anyone can exec this once a queued beneficiary change is due
+    ApplyBeneficiaryChange {},
+    /// owner only. applies to changes queued after this is set
+    SetBeneficiaryChangeDelay {
+        delay: u64,
+    },
+    /// owner only. the new owner needs to call AcceptOwnership within `expires_in` seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// owner only. cancels a pending ownership transfer
+    DropOwnershipProposal {},
+    /// proposed owner only.
+    AcceptOwnership {},
+    /// staker only. returns staked DP. votes already cast keep their weight
+    Unstake {
+        amount: Uint128,
+    },
+    /// staker only. voting weight is the DP staked when the proposal is made
+    Propose {
+        action: GovernanceAction,
+        description: String,
+    },
+    /// staker only. one vote per proposal
+    CastVote {
+        proposal_id: u64,
+        vote: VoteOption,
+    },
+    /// anyone can exec this once voting has ended on a proposal that passed
+    ExecuteProposal {
+        proposal_id: u64,
+    },
+    /// owner only. applies to proposals made after this is set
+    SetGovernanceConfig {
+        /// seconds
+        voting_period: Option<u64>,
+        /// share of the total stake that has to vote
+        quorum: Option<Decimal>,
+        /// share of the votes cast that have to be yes
+        threshold: Option<Decimal>,
+    },
+    /// owner grants admin. owner/admin grant guardian and operator
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    /// same permissions as GrantRole
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
     ConfigDetails {
         title: Option<String>,
         description: Option<String>,
@@ -37,7 +214,7 @@ impl ExecuteMsg {
     /// serializes the message
     pub fn into_binary(self) -> StdResult<Binary> {
         //  let msg = CollectablesExecuteMsg(self);
-        to_binary(&self)
+        to_json_binary(&self)
     }
 
     /// creates a cosmos_msg sending this struct to the named contract
@@ -58,30 +235,232 @@ impl ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     Redeem {},
+    /// send the pool's cw20 stable to deposit it. DP goes to `recipient`, or the sender
+    Deposit {
+        recipient: Option<String>,
+    },
+    /// send the pool's cw20 stable to donate it, like ExecuteMsg::Donate
+    Donate {
+        direct: bool,
+    },
+    /// send the pool's cw20 stable as a matching budget, like ExecuteMsg::PledgeMatch
+    PledgeMatch {
+        ratio: Decimal,
+        cap: Uint128,
+        expires_at: u64,
+    },
+    /// stake DP for governance voting
+    Stake {},
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GovernanceAction {
+    /// queued behind the beneficiary change delay, like an accepted handover
+    ChangeBeneficiary { beneficiary: String },
+    /// stops new deposits, for good: nothing reopens the fund. depositors can still redeem, and
+    /// what stays in keeps earning. earn, harvest, payouts and fees carry on as before
+    CloseFund {},
+    /// each recipient gets their share of the payout, the beneficiary gets the rest.
+    /// an empty split sends everything to the beneficiary again
+    ChangePayoutSplit { split: Vec<PayoutShare> },
+}
+/// when anyone can harvest a fund, and what they get for it
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct HarvestConfig {
+    /// seconds since the last payout
+    pub min_interval: u64,
+    /// or as soon as this much is owed to the beneficiary. zero to only go by time
+    pub min_earnable: Uint128,
+    /// share of the harvest paid to the caller
+    pub bounty: Decimal,
+    /// most one harvest pays as bounty. zero means uncapped
+    pub bounty_cap: Uint128,
+}
+/// where an ics20 transfer of the beneficiary's share goes
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcPayout {
+    /// transfer channel on this chain
+    pub channel_id: String,
+    /// beneficiary address on the remote chain
+    pub remote_address: String,
+    /// seconds from sending until the transfer times out
+    pub timeout_seconds: u64,
+    /// resends after a failed ack or timeout. after that it is paid to the beneficiary here
+    pub max_retries: u32,
+}
+/// the dex pair the beneficiary's share is swapped through
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SwapPayout {
+    /// a terraswap/astroport style pair with the stable denom on one side
+    pub pair: String,
+    /// what the beneficiary gets
+    pub ask_asset: AssetInfo,
+    /// what one of `ask_asset` is expected to cost in the stable denom. the pair checks
+    /// `max_spread` against this rather than its own spot price, which a sandwich can move
+    pub belief_price: Decimal,
+    /// the swap fails (and the payout falls back to the stable denom) past this
+    pub max_spread: Decimal,
+}
+/// how a whitelisted deposit denom is swapped into the stable
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DepositRoute {
+    /// a terraswap/astroport style pair of the denom and the stable
+    pub pair: String,
+    pub max_spread: Decimal,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutShare {
+    pub recipient: String,
+    pub share: Decimal,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteOption {
+    Yes,
+    No,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    DepositAmountOf { owner: String }, // -> Uint128
-    TotalDepositAmount {},             // -> Uint128
-    Config {},                         // -> Config
-    Claimable {},                      // -> Uint256
-    LastClaimed {},                    // -> LastClaimed
-    Fee {},                            // -> Uint256
-
-                                       // DebugRedeem { owner: String, amount: u64 }, // -> Uint256
-                                       /*
-                                       DebugAnchorEpoch {},     // -> Uint128
-                                       DebugATokenBalance {},   // -> Uint128
-                                       DebugDPTotalSupply {},   // -> Uint128
-                                       DebugPoolValueLocked {}, // -> Uint128
-                                       DebugEarnable {},        // -> Uint128
+    DepositAmountOf {
+        owner: String,
+    }, // -> Uint128
+    TotalDepositAmount {}, // -> Uint128
+    Config {},             // -> Config
+    Claimable {},          // -> Uint256
+    LastClaimed {},        // -> LastClaimed
+    Fee {},                // -> Uint256
+    PendingTransfers {},   // -> PendingTransfersResponse
+    BeneficiaryChange {},  // -> Option<BeneficiaryChangeResponse>
+    /// -> GovernanceConfigResponse
+    GovernanceConfig {},
+    /// -> ProposalResponse
+    Proposal {
+        proposal_id: u64,
+    },
+    /// -> ProposalsResponse
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// -> Option<VoteOption>
+    Vote {
+        proposal_id: u64,
+        voter: String,
+    },
+    /// -> StakedResponse
+    Staked {
+        address: String,
+    },
+    /// -> PayoutSplitResponse
+    PayoutSplit {},
+    /// -> RolesResponse
+    Roles {
+        address: String,
+    },
+    /// -> RoleMembersResponse
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> HarvestConfigResponse
+    HarvestConfig {},
+    /// -> EndowmentResponse
+    Endowment {},
+    /// -> LiquidBufferResponse
+    LiquidBuffer {},
+    /// -> StrategiesResponse
+    Strategies {},
+    /// -> WhitelistedMarketsResponse
+    WhitelistedMarkets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> InsuranceReserveResponse
+    InsuranceReserve {},
+    /// -> DepositCapacityResponse. with an address, what it can still deposit
+    DepositCapacity {
+        address: Option<String>,
+    },
+    /// -> YieldHookResponse
+    YieldHook {},
+    /// -> IbcPayoutResponse
+    IbcPayout {},
+    /// -> SwapPayoutResponse
+    SwapPayout {},
+    /// -> DepositRoutesResponse, the denoms deposits are taken in besides the stable
+    DepositRoutes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> WhitelistedPairsResponse
+    WhitelistedPairs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> MatchesResponse
+    Matches {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> DonorResponse
+    Donor {
+        address: String,
+    },
+    /// -> DonorsResponse
+    Donors {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// -> IbcTransfersResponse, oldest first
+    IbcTransfers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// -> EarnHistoryResponse, oldest first
+    EarnHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// -> FeeHistoryResponse, every CollectFees, oldest first
+    FeeHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // DebugRedeem { owner: String, amount: u64 }, // -> Uint256
+    /*
+    DebugAnchorEpoch {},     // -> Uint128
+    DebugATokenBalance {},   // -> Uint128
+    DebugDPTotalSupply {},   // -> Uint128
+    DebugPoolValueLocked {}, // -> Uint128
+    DebugEarnable {},        // -> Uint128
 
-                                                               */
+                            */
 }
 
 pub const NFT_REPLY_COLLECTION_ACTIVE: u64 = 2;
 pub const NFT_REPLY_COLLECTION_REDEEMED: u64 = 3;
+/// sent by the chain's ibc hooks middleware once a transfer from the pool is acked or times out
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct NftCallback {
     pub contract_address: String,
@@ -2,6 +2,12 @@ use cosmwasm_std::{Coin, Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::dex::AssetInfo;
+use crate::fee::{FeeModel, FeePeriod};
+use crate::pool_msg::{
+    DepositRoute, GovernanceAction, HarvestConfig, IbcPayout, PayoutShare, SwapPayout,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct DepositAmountResponse {
     pub amount: Uint128,
@@ -17,15 +23,26 @@ pub struct ClaimableRewardResponse {
     pub total_value: Uint128,
     pub pool_value: Uint128,
     pub earned: Uint128,
+    /// what an earn would pay the beneficiary (and payout split) now
     pub claimable: Uint128,
     pub fee: Uint128,
+    /// earn fails while `claimable` is below this
+    pub min_earn_amount: Uint128,
+    /// first block height an earn is allowed at
+    pub next_earn_allowed_at: u64,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct FeeResponse {
-    pub fee_amount: Decimal,
-    pub fee_max: Uint128,
-    pub fee_reset_every_num_blocks: u64,
+    pub fee_model: FeeModel,
+    pub fee_period: FeePeriod,
+    /// seconds since epoch
+    pub period_start: u64,
+    /// charged so far in the current fee period
+    pub fees_collected: Uint128,
+    /// what would be charged on the yield that hasn't accrued yet
     pub fee: Uint128,
+    /// what CollectFees would send now, `fee` included
+    pub accrued: Uint128,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
 pub struct RedeemResponse {
@@ -33,3 +50,296 @@ pub struct RedeemResponse {
     pub market_redeem_amount: Uint128,
     pub user_redeem_amount: Coin,
 }
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingTransfer {
+    pub proposed: String,
+    /// seconds since epoch
+    pub expires_at: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PendingTransfersResponse {
+    pub owner: Option<PendingTransfer>,
+    pub beneficiary: Option<PendingTransfer>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct BeneficiaryChangeResponse {
+    pub current_beneficiary: String,
+    pub new_beneficiary: String,
+    /// seconds since epoch
+    pub effective_at: u64,
+    pub delay: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct GovernanceConfigResponse {
+    /// seconds
+    pub voting_period: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub total_staked: Uint128,
+    pub fund_closed: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub proposer: String,
+    pub action: GovernanceAction,
+    pub description: String,
+    /// voting weight is taken from the stake at this height
+    pub start_height: u64,
+    /// seconds since epoch
+    pub voting_ends_at: u64,
+    pub yes_votes: Uint128,
+    pub no_votes: Uint128,
+    /// DP staked at start_height
+    pub total_weight: Uint128,
+    pub status: ProposalStatus,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct ProposalsResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct StakedResponse {
+    pub amount: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutSplitResponse {
+    pub beneficiary: String,
+    pub split: Vec<PayoutShare>,
+    /// a voted split still waiting out the exit window
+    pub pending: Option<PayoutSplitChangeResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutSplitChangeResponse {
+    pub split: Vec<PayoutShare>,
+    /// seconds since epoch
+    pub effective_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct PayoutResponse {
+    pub recipient: String,
+    pub amount: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct EarnRecordResponse {
+    pub id: u64,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+    pub exchange_rate: Decimal,
+    /// yield that came in since the previous accrual
+    pub earnable: Uint128,
+    /// charged on `earnable`, collected separately by the fee collector
+    pub fee: Uint128,
+    pub beneficiary: String,
+    pub beneficiary_amount: Uint128,
+    pub split: Vec<PayoutResponse>,
+    /// paid to whoever called Harvest
+    pub keeper_bounty: Option<PayoutResponse>,
+    /// how the beneficiary's share was swapped, if it was to be
+    pub swap: Option<SwapRecordResponse>,
+    /// released by sponsors' matches, paid to the beneficiary with `beneficiary_amount`
+    pub matched: Option<Uint128>,
+    /// kept in the market as endowment rather than paid out
+    pub retained: Option<Uint128>,
+    /// taken into the insurance reserve ahead of the beneficiary's share
+    pub insured: Option<Uint128>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SwapRecordResponse {
+    pub pair: String,
+    pub ask_asset: AssetInfo,
+    /// stable offered
+    pub offer_amount: Uint128,
+    /// what the beneficiary got in `ask_asset`. None if the swap failed
+    pub return_amount: Option<Uint128>,
+    /// why it failed. the beneficiary was paid `offer_amount` in the stable denom instead
+    pub error: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct EarnHistoryResponse {
+    pub earns: Vec<EarnRecordResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeRecordResponse {
+    pub id: u64,
+    pub height: u64,
+    /// seconds since epoch
+    pub time: u64,
+    pub fee: Uint128,
+    pub fee_collector: String,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct FeeHistoryResponse {
+    pub fees: Vec<FeeRecordResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct YieldHookResponse {
+    /// whether the beneficiary is paid through the YieldReceived hook
+    pub enabled: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MatchResponse {
+    pub sponsor: String,
+    pub ratio: Decimal,
+    /// most released by a single earn. zero for no cap
+    pub cap: Uint128,
+    /// seconds since epoch
+    pub expires_at: u64,
+    pub expired: bool,
+    /// what is left to release, or reclaim once expired
+    pub budget: Uint128,
+    pub released: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct MatchesResponse {
+    /// budget left across the matches that haven't expired
+    pub remaining: Uint128,
+    pub matches: Vec<MatchResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DonorResponse {
+    pub donor: String,
+    /// everything they have donated, before fees
+    pub total: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DonorsResponse {
+    /// donated to the pool by everyone
+    pub total: Uint128,
+    pub donors: Vec<DonorResponse>,
+    pub donor_collection: Option<u64>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DepositRouteResponse {
+    pub denom: String,
+    pub route: DepositRoute,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DepositRoutesResponse {
+    pub routes: Vec<DepositRouteResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct WhitelistedPairsResponse {
+    pub pairs: Vec<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct SwapPayoutResponse {
+    /// None when the beneficiary is paid in the stable denom
+    pub swap_payout: Option<SwapPayout>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcPayoutResponse {
+    /// None when the beneficiary is paid on this chain
+    pub ibc_payout: Option<IbcPayout>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcTransferStatus {
+    /// sent, waiting for the ack
+    Pending,
+    Delivered,
+    /// out of retries, so it went to the beneficiary on this chain
+    PaidLocally,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcTransferResponse {
+    pub id: u64,
+    /// the earn it paid out
+    pub earn_id: u64,
+    pub channel_id: String,
+    pub remote_address: String,
+    pub amount: Uint128,
+    pub attempts: u32,
+    /// packet sequence of the latest attempt
+    pub sequence: Option<u64>,
+    pub status: IbcTransferStatus,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct IbcTransfersResponse {
+    pub transfers: Vec<IbcTransferResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct LiquidBufferResponse {
+    /// share of the DP supply held in the pool
+    pub target: Decimal,
+    /// what that comes to now
+    pub target_amount: Uint128,
+    /// stable held in the pool for redemptions
+    pub held: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct StrategyResponse {
+    pub money_market: String,
+    pub atoken: String,
+    pub weight: Decimal,
+    pub exchange_rate: Decimal,
+    pub atokens: Uint128,
+    /// what the aTokens are worth in the stable
+    pub value: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct WhitelistedMarketResponse {
+    pub money_market: String,
+    pub atoken: String,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct WhitelistedMarketsResponse {
+    pub markets: Vec<WhitelistedMarketResponse>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct StrategiesResponse {
+    /// the config's money market first
+    pub strategies: Vec<StrategyResponse>,
+    /// across all of them
+    pub total_value: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct DepositCapacityResponse {
+    /// zero for no cap
+    pub tvl_cap: Uint128,
+    /// zero for no cap
+    pub address_cap: Uint128,
+    pub total_supply: Uint128,
+    /// what can still be deposited, by the address if one was given. None if nothing caps it
+    pub remaining: Option<Uint128>,
+    pub paused: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct InsuranceReserveResponse {
+    pub ratio: Decimal,
+    /// zero for no cap
+    pub cap: Uint128,
+    pub held: Uint128,
+    pub total_funded: Uint128,
+    pub total_used: Uint128,
+    /// how far the pool's value is below the DP supply right now
+    pub shortfall: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct EndowmentResponse {
+    pub retain_ratio: Decimal,
+    /// retained so far. it stays in the market and isn't counted as earnable
+    pub principal: Uint128,
+    /// retained by the most recent earn
+    pub last_retained: Uint128,
+    /// seconds since epoch. None until something is retained
+    pub first_retained_at: Option<u64>,
+    pub last_retained_at: Option<u64>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct HarvestConfigResponse {
+    /// None when only the beneficiary/operator can earn
+    pub harvest: Option<HarvestConfig>,
+    /// seconds since epoch
+    pub last_payout_at: Option<u64>,
+}
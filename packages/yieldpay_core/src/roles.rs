@@ -0,0 +1,197 @@
+//! role based access control shared by the factory and pool contracts.
+//!
+//! owner, beneficiary and fee collector have a single holder each and move with the
+//! ownership/beneficiary handovers and fee collector changes. admin, guardian and operator can
+//! have any number of holders and are granted and revoked directly.
+//!
+//! guardians pause a pool's deposits and hide funds from the factory's listings. operators run
+//! a pool's earns and migrate funds to the factory's configured code id.
+use cosmwasm_std::{Addr, Empty, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const ROLE_MEMBERS: Map<(&str, &Addr), Empty> = Map::new("role_members");
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    Admin,
+    Guardian,
+    Operator,
+    Beneficiary,
+    FeeCollector,
+}
+
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::Owner,
+        Role::Admin,
+        Role::Guardian,
+        Role::Operator,
+        Role::Beneficiary,
+        Role::FeeCollector,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Guardian => "guardian",
+            Role::Operator => "operator",
+            Role::Beneficiary => "beneficiary",
+            Role::FeeCollector => "fee_collector",
+        }
+    }
+
+    /// owner, beneficiary and fee collector only change hands through their own flows
+    pub fn is_grantable(&self) -> bool {
+        matches!(self, Role::Admin | Role::Guardian | Role::Operator)
+    }
+
+    /// roles allowed to grant/revoke this one
+    pub fn managed_by(&self) -> &'static [Role] {
+        match self {
+            Role::Admin => &[Role::Owner],
+            Role::Guardian | Role::Operator => &[Role::Owner, Role::Admin],
+            Role::Owner | Role::Beneficiary | Role::FeeCollector => &[],
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RolesError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error(
+        "Roles: Unauthorized (action: {action:?}, expected: {expected:?}, actual: {actual:?})"
+    )]
+    Unauthorized {
+        action: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Roles: {0} can't be granted or revoked directly")]
+    NotGrantable(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    pub address: String,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<String>,
+}
+
+pub fn has_role(storage: &dyn Storage, role: Role, address: &Addr) -> bool {
+    ROLE_MEMBERS.has(storage, (role.as_str(), address))
+}
+
+/// fails with `Unauthorized` unless `sender` holds one of `roles`
+pub fn ensure_any(
+    storage: &dyn Storage,
+    action: &str,
+    sender: &Addr,
+    roles: &[Role],
+) -> Result<(), RolesError> {
+    if roles.iter().any(|role| has_role(storage, *role, sender)) {
+        Ok(())
+    } else {
+        Err(RolesError::Unauthorized {
+            action: action.to_string(),
+            expected: roles
+                .iter()
+                .map(|role| role.as_str())
+                .collect::<Vec<_>>()
+                .join("|"),
+            actual: sender.to_string(),
+        })
+    }
+}
+
+/// replaces whoever held a single holder role (owner/beneficiary/fee collector)
+pub fn set_holder(storage: &mut dyn Storage, role: Role, address: &Addr) -> StdResult<()> {
+    let current = ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for member in current {
+        ROLE_MEMBERS.remove(storage, (role.as_str(), &member));
+    }
+    ROLE_MEMBERS.save(storage, (role.as_str(), address), &Empty {})
+}
+
+/// adds a holder without any checks. for instantiate/migrate and handovers
+pub fn add_member(storage: &mut dyn Storage, role: Role, address: &Addr) -> StdResult<()> {
+    ROLE_MEMBERS.save(storage, (role.as_str(), address), &Empty {})
+}
+
+/// removes a holder without any checks. for handovers
+pub fn remove_member(storage: &mut dyn Storage, role: Role, address: &Addr) {
+    ROLE_MEMBERS.remove(storage, (role.as_str(), address))
+}
+
+pub fn grant(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    role: Role,
+    address: &Addr,
+) -> Result<(), RolesError> {
+    if !role.is_grantable() {
+        return Err(RolesError::NotGrantable(role.as_str().to_string()));
+    }
+    ensure_any(storage, "grant_role", sender, role.managed_by())?;
+    add_member(storage, role, address)?;
+    Ok(())
+}
+
+pub fn revoke(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    role: Role,
+    address: &Addr,
+) -> Result<(), RolesError> {
+    if !role.is_grantable() {
+        return Err(RolesError::NotGrantable(role.as_str().to_string()));
+    }
+    ensure_any(storage, "revoke_role", sender, role.managed_by())?;
+    remove_member(storage, role, address);
+    Ok(())
+}
+
+pub fn roles_of(storage: &dyn Storage, address: &Addr) -> Vec<Role> {
+    Role::ALL
+        .iter()
+        .copied()
+        .filter(|role| has_role(storage, *role, address))
+        .collect()
+}
+
+pub fn members(
+    storage: &dyn Storage,
+    role: Role,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Addr>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(
+            storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,39 @@
+use cosmwasm_std::{to_json_binary, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// the span of yield a payout covers, seconds since epoch
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+pub struct YieldPeriod {
+    /// the previous payout. None for a pool's first
+    pub start: Option<u64>,
+    pub end: u64,
+}
+
+/// beneficiary contracts that opt in get their payout through this instead of a bank send.
+/// add the variant to the contract's own ExecuteMsg
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum YieldReceiverMsg {
+    /// `amount` is attached as funds
+    YieldReceived {
+        pool: String,
+        amount: Uint128,
+        period: YieldPeriod,
+    },
+}
+
+impl YieldReceiverMsg {
+    pub fn into_cosmos_msg<T: Into<String>>(
+        self,
+        contract_addr: T,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_json_binary(&self)?,
+            funds,
+        }
+        .into())
+    }
+}